@@ -0,0 +1,169 @@
+//! RPC helpers for off-chain clients, built on top of the instruction builders, and
+//! `CollectionView` for caching fetched collections. Only available with the `client` feature.
+//!
+//! `invoke_include_token` is re-exported here, it lives in `cpi` so that on-chain callers get it
+//! with `client-builders` alone, without solana-client.
+#![allow(clippy::result_large_err)]
+
+use {
+    crate::{
+        id,
+        instruction::{self, CreateCollectionAccountArgs},
+        state::{stars_cost, CollectionAccountData},
+        utils::get_treasury_account,
+    },
+    serde::{Deserialize, Serialize},
+    solana_client::{client_error::Result, rpc_client::RpcClient},
+    solana_program::{borsh::try_from_slice_unchecked, instruction::Instruction, pubkey::Pubkey},
+    solana_sdk::{
+        signature::{Keypair, Signature, Signer},
+        transaction::Transaction,
+    },
+};
+
+pub use crate::cpi::{invoke_include_token, IncludeTokenAccounts};
+
+/// Signs `instructions` with `payer` and `signers`, submits them and waits for confirmation.
+pub fn send_instructions(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    signers: &[&Keypair],
+    instructions: &[Instruction],
+) -> Result<Signature> {
+    let mut transaction = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    let mut keypairs = vec![payer];
+    keypairs.extend_from_slice(signers);
+    transaction.sign(&keypairs, rpc.get_latest_blockhash()?);
+    rpc.send_and_confirm_transaction(&transaction)
+}
+
+/// Creates the collection account `collection`, with `payer` funding its rent.
+pub fn create_collection(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    collection: &Keypair,
+    args: CreateCollectionAccountArgs,
+) -> Result<Signature> {
+    let ix = instruction::create_collection_account(id(), collection.pubkey(), payer.pubkey(), args);
+    send_instructions(rpc, payer, &[collection], &[ix])
+}
+
+/// Includes the NFT `mint` held in `token_account` in the collection, with `authority`
+/// paying for the index account.
+pub fn include_token(
+    rpc: &RpcClient,
+    authority: &Keypair,
+    collection: &Pubkey,
+    mint: &Pubkey,
+    token_account: &Pubkey,
+) -> Result<Signature> {
+    let ix = instruction::include_token(
+        id(),
+        *collection,
+        authority.pubkey(),
+        *mint,
+        *token_account,
+        authority.pubkey(),
+    );
+    send_instructions(rpc, authority, &[], &[ix])
+}
+
+/// Lights up `amount` stars on the collection, paid by `payer`.
+pub fn light_up_stars(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    collection: &Pubkey,
+    amount: u64,
+) -> Result<Signature> {
+    let (treasury_account, _) = get_treasury_account();
+    let ix = instruction::light_up_stars_custom(id(), *collection, payer.pubkey(), treasury_account, amount);
+    send_instructions(rpc, payer, &[], &[ix])
+}
+
+/// Lamports the treasury gains from selling `stars_to_sell` stars at `star_price`, priced
+/// exactly like the paid light-ups. `None` when the total overflows, where a light-up would
+/// fail with `Overflow`.
+pub fn projected_treasury(stars_to_sell: u64, star_price: u64) -> Option<u64> {
+    stars_cost(stars_to_sell, star_price)
+}
+
+/// Fetches and decodes the collection account `collection`.
+pub fn get_collection(rpc: &RpcClient, collection: &Pubkey) -> Result<CollectionAccountData> {
+    let data = rpc.get_account_data(collection)?;
+    Ok(try_from_slice_unchecked(&data)?)
+}
+
+/// Serde mirror of `CollectionAccountData` for caching fetched collections as JSON, the
+/// on-chain type itself stays Borsh only. Keys are base58 strings and the category its
+/// variant name, so the JSON reads the same as the explorers show it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CollectionView {
+    pub authority: String,
+    pub title: String,
+    pub symbol: String,
+    pub description: String,
+    pub icon_image: String,
+    pub supply: u64,
+    pub stars: u64,
+    pub header_image: Option<String>,
+    pub short_description: Option<String>,
+    pub banner: Option<String>,
+    pub tags: Vec<String>,
+    pub version: u8,
+    pub created_at: i64,
+    pub frozen: bool,
+    pub royalty_bps: Option<u16>,
+    pub star_price: u64,
+    pub index_seq: u64,
+    pub verified: bool,
+    pub secondary_authority: Option<String>,
+    pub metadata_uri: Option<String>,
+    pub has_allowlist: bool,
+    pub category: Option<String>,
+    pub creators: Vec<CreatorView>,
+    pub parent: Option<String>,
+    pub cooldown_slots: u64,
+    pub collection_mint: Option<String>,
+}
+
+/// A creator of `CollectionView::creators` and their share in basis points.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CreatorView {
+    pub address: String,
+    pub share: u16,
+}
+
+impl From<&CollectionAccountData> for CollectionView {
+    fn from(collection: &CollectionAccountData) -> Self {
+        CollectionView {
+            authority: collection.authority.to_string(),
+            title: collection.title.clone(),
+            symbol: collection.symbol.clone(),
+            description: collection.description.clone(),
+            icon_image: collection.icon_image.clone(),
+            supply: collection.supply,
+            stars: collection.stars,
+            header_image: collection.header_image.clone(),
+            short_description: collection.short_description.clone(),
+            banner: collection.banner.clone(),
+            tags: collection.tags.clone().unwrap_or_default(),
+            version: collection.version,
+            created_at: collection.created_at,
+            frozen: collection.frozen,
+            royalty_bps: collection.royalty_bps,
+            star_price: collection.star_price,
+            index_seq: collection.index_seq,
+            verified: collection.verified,
+            secondary_authority: collection.secondary_authority.map(|key| key.to_string()),
+            metadata_uri: collection.metadata_uri.clone(),
+            has_allowlist: collection.has_allowlist,
+            category: collection.category.map(|category| format!("{:?}", category)),
+            creators: collection.creators().unwrap_or_default().iter()
+                .map(|(address, share)| CreatorView { address: address.to_string(), share: *share })
+                .collect(),
+            parent: collection.parent.map(|key| key.to_string()),
+            cooldown_slots: collection.cooldown_slots,
+            collection_mint: collection.collection_mint.map(|key| key.to_string()),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! Helpers for programs calling the collection program through CPI, built on top of the
+//! instruction builders. Only available with the `client-builders` feature.
+use {
+    crate::instruction,
+    solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, program::invoke_signed},
+};
+
+/// Accounts of an `IncludeToken` invoked from another program, see
+/// `CollectionInstruction::IncludeToken` for what each of them must be.
+pub struct IncludeTokenAccounts<'a, 'info> {
+    /// The collection program itself
+    pub collection_program: &'a AccountInfo<'info>,
+    pub collection: &'a AccountInfo<'info>,
+    /// Owner or single-token delegate of `mint_token_account`, often a PDA of the caller
+    pub collection_authority: &'a AccountInfo<'info>,
+    /// Secondary authority, only when the collection has one
+    pub secondary_authority: Option<&'a AccountInfo<'info>>,
+    pub mint: &'a AccountInfo<'info>,
+    pub mint_token_account: &'a AccountInfo<'info>,
+    /// The index address `utils::find_index_address` derives for `mint`
+    pub index: &'a AccountInfo<'info>,
+    pub payer: &'a AccountInfo<'info>,
+    pub rent_sysvar: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+    /// The allowlist address `utils::find_allowlist_address` derives for `collection`, it
+    /// does not have to exist
+    pub allowlist: &'a AccountInfo<'info>,
+}
+
+/// Includes `accounts.mint` in `accounts.collection` with the instruction `include_token`
+/// builds, so the account order always matches what the program expects.
+///
+/// `signer_seeds` sign for the PDAs of the calling program among the collection authority
+/// and the payer, accounts that signed the transaction itself need none. The index account
+/// is a PDA of the collection program, which signs for it on its own.
+pub fn invoke_include_token(accounts: &IncludeTokenAccounts, signer_seeds: &[&[&[u8]]]) -> ProgramResult {
+    let mut instruction = instruction::include_token(
+        *accounts.collection_program.key,
+        *accounts.collection.key,
+        *accounts.collection_authority.key,
+        *accounts.mint.key,
+        *accounts.mint_token_account.key,
+        *accounts.payer.key,
+    );
+    let mut account_infos = vec![
+        accounts.collection.clone(),
+        accounts.collection_authority.clone(),
+        accounts.mint.clone(),
+        accounts.mint_token_account.clone(),
+        accounts.index.clone(),
+        accounts.payer.clone(),
+        accounts.rent_sysvar.clone(),
+        accounts.system_program.clone(),
+        accounts.allowlist.clone(),
+        accounts.collection_program.clone(),
+    ];
+    if let Some(secondary_authority) = accounts.secondary_authority {
+        instruction::add_secondary_authority(&mut instruction, *secondary_authority.key);
+        account_infos.insert(2, secondary_authority.clone());
+    }
+    invoke_signed(&instruction, &account_infos, signer_seeds)
+}
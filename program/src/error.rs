@@ -60,6 +60,111 @@ pub enum CollectionError {
     /// You must be the treasury manager and signer on this transaction
     #[error("You must be the treasury manager and signer on this transaction")]
     NotTreasuryManager,
+
+    /// Token is not included in the collection
+    #[error("Token is not included in the collection")]
+    TokenNotIncluded,
+
+    /// Arithmetic overflow
+    #[error("Arithmetic overflow")]
+    Overflow,
+
+    /// Token is already included in a collection
+    #[error("Token is already included in a collection")]
+    TokenAlreadyIncluded,
+
+    /// Collection metadata failed validation
+    #[error("Invalid collection metadata")]
+    InvalidMetadata,
+
+    /// Mint must have zero decimals and a supply of one
+    #[error("Mint is not an NFT")]
+    NotAnNft,
+
+    /// Collection is frozen and can no longer be changed
+    #[error("Collection is frozen")]
+    CollectionFrozen,
+
+    /// Collection already holds the maximum number of stars
+    #[error("Star ceiling reached")]
+    StarCeilingReached,
+
+    /// Signer is not the curator of verified collections
+    #[error("Not curator")]
+    NotCurator,
+
+    /// Collection has no such tag
+    #[error("Tag not found")]
+    TagNotFound,
+
+    /// Funding account can not make the new account rent exempt
+    #[error("Insufficient rent")]
+    InsufficientRent,
+
+    /// Account data declares a string longer than the data itself
+    #[error("Invalid account data")]
+    InvalidAccountData,
+
+    /// Collection reached its free stars for the current slot
+    #[error("Rate limited")]
+    RateLimited,
+
+    /// Account is not the kind of account the instruction claims it is
+    #[error("Account type mismatch")]
+    AccountTypeMismatch,
+
+    /// Collection still holds tokens, an allowlist or a cooldown account that would be orphaned
+    #[error("Collection not empty")]
+    CollectionNotEmpty,
+
+    /// The same account was passed for two roles that must be distinct
+    #[error("Invalid account arguments")]
+    InvalidAccountArguments,
+
+    /// Allowlist account is not the allowlist address of the collection
+    #[error("Allowlist account mismatch")]
+    AllowlistAccountMismatch,
+
+    /// Collection has an allowlist and the mint is not on it
+    #[error("Mint not allowed")]
+    MintNotAllowed,
+
+    /// Serialized args would not fit into a transaction
+    #[error("Args too large")]
+    ArgsTooLarge,
+
+    /// Recipient is not the treasury recipient bound by the upgrade authority
+    #[error("Wrong recipient")]
+    WrongRecipient,
+
+    /// A single light-up asked for more than `MAX_STARS_PER_CALL` stars
+    #[error("Amount too large")]
+    AmountTooLarge,
+
+    /// Parent collection is the collection itself or one of its sub-collections
+    #[error("Invalid parent")]
+    InvalidParent,
+
+    /// Signer lit a free star of the collection less than its cooldown ago
+    #[error("Cooldown")]
+    Cooldown,
+
+    /// Cooldown account is not the cooldown address of the collection
+    #[error("Cooldown account mismatch")]
+    CooldownAccountMismatch,
+
+    /// Mint is the collection mint, the NFT representing the collection itself
+    #[error("Cannot include collection mint")]
+    CannotIncludeCollectionMint,
+
+    /// Token account does not hold the star mint of the treasury config
+    #[error("Wrong star mint")]
+    WrongStarMint,
+
+    /// Collection account predates the current layout and has no room for it, run
+    /// `MigrateAccount` first
+    #[error("Migration required")]
+    MigrationRequired,
 }
 
 impl PrintProgramError for CollectionError {
@@ -0,0 +1,88 @@
+//! Structured events for indexers. With the `emit-events` feature every state-changing
+//! handler invokes the program itself with an `EmitEvent` instruction, so indexers can
+//! decode the inner instruction data instead of parsing logs.
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::AccountInfo,
+        entrypoint::ProgramResult,
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    },
+    crate::{instruction::CollectionInstruction, utils::find_event_authority_address},
+};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub enum CollectionEvent {
+    CollectionCreated { collection: Pubkey, authority: Pubkey },
+    TokenIncluded { collection: Pubkey, mint: Pubkey, index: u64 },
+    TokenRemoved { collection: Pubkey, mint: Pubkey },
+    /// `delta` stars were lit, bringing the collection to `total`
+    StarsLit { collection: Pubkey, delta: u64, total: u64 },
+    MetadataUpdated { collection: Pubkey },
+    AuthorityTransferred { collection: Pubkey, new_authority: Pubkey },
+    CollectionFrozen { collection: Pubkey },
+    StarPriceSet { collection: Pubkey, star_price: u64 },
+    VerifiedSet { collection: Pubkey, verified: bool },
+    AccountClosed { account: Pubkey },
+    /// `amount` lamports left the treasury
+    Withdrawn { amount: u64 },
+    /// `mint` was added to the allowlist of `collection` or, when not `allowed`, removed from it
+    AllowlistUpdated { collection: Pubkey, mint: Pubkey, allowed: bool },
+    /// the authority corrected the star count of `collection` by `delta`, leaving `total`
+    StarsAdjusted { collection: Pubkey, delta: i64, total: u64 },
+    TreasuryRecipientSet { treasury_recipient: Pubkey },
+    /// `collection` became a sub-collection of `parent` or, with `None`, was detached
+    ParentSet { collection: Pubkey, parent: Option<Pubkey> },
+    /// free stars of `collection` now need `cooldown_slots` slots between two of the same signer
+    CooldownSet { collection: Pubkey, cooldown_slots: u64 },
+    /// `collection` is now represented by the NFT `collection_mint` or, with `None`, by none
+    CollectionMintSet { collection: Pubkey, collection_mint: Option<Pubkey> },
+    /// stars now cost `star_token_price` base units of `star_mint` or, with `None`, no token buys them
+    StarTokenSet { star_mint: Option<Pubkey>, star_token_price: u64 },
+    /// `amount` base units of `mint` left the treasury
+    TokensWithdrawn { mint: Pubkey, amount: u64 },
+}
+
+/// Creates the `EmitEvent` self-invocation carrying `event`, signed by the event authority.
+pub fn event_instruction(program_id: Pubkey, event: CollectionEvent) -> Instruction {
+    let (event_authority, _) = find_event_authority_address(&program_id);
+    Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new_readonly(event_authority, true)],
+        data: CollectionInstruction::EmitEvent(event).try_to_vec().unwrap(),
+    }
+}
+
+/// Appends the event authority and the program itself to `instruction`, which the
+/// program needs to emit events. Instructions without them still run, silently.
+#[cfg(feature = "client-builders")]
+pub fn append_event_accounts(instruction: &mut Instruction) {
+    let (event_authority, _) = find_event_authority_address(&instruction.program_id);
+    instruction.accounts.push(AccountMeta::new_readonly(event_authority, false));
+    instruction.accounts.push(AccountMeta::new_readonly(instruction.program_id, false));
+}
+
+/// Emits `event` when the caller appended the accounts of `append_event_accounts`.
+#[cfg(feature = "emit-events")]
+pub fn emit_event(program_id: &Pubkey, accounts: &[AccountInfo], event: CollectionEvent) -> ProgramResult {
+    use {crate::seeds::{COLLECTION_SEED, EVENT_AUTHORITY_SEED}, solana_program::program::invoke_signed};
+
+    let (event_authority, bump_seed) = find_event_authority_address(program_id);
+    let event_authority_info = accounts.iter().find(|info| *info.key == event_authority);
+    let program_info = accounts.iter().find(|info| info.key == program_id);
+    if let (Some(event_authority_info), Some(program_info)) = (event_authority_info, program_info) {
+        invoke_signed(
+            &event_instruction(*program_id, event),
+            &[event_authority_info.clone(), program_info.clone()],
+            &[&[COLLECTION_SEED, EVENT_AUTHORITY_SEED, &[bump_seed]]],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "emit-events"))]
+#[inline(always)]
+pub fn emit_event(_program_id: &Pubkey, _accounts: &[AccountInfo], _event: CollectionEvent) -> ProgramResult {
+    Ok(())
+}
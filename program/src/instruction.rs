@@ -2,16 +2,45 @@
 use {
     borsh::{BorshDeserialize, BorshSerialize},
     solana_program::{
-        instruction::{AccountMeta, Instruction},
+        program_error::ProgramError,
         pubkey::Pubkey,
+        rent::Rent,
+    },
+    crate::{
+        error::CollectionError,
+        event::CollectionEvent,
+        state::{AccountType, CollectionAccountData, CollectionCategory},
+    },
+};
+#[cfg(feature = "client-builders")]
+use {
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
         sysvar,
+        system_instruction,
         system_program,
     },
-    crate::state::AccountType,
+    crate::utils::{find_allowlist_address, find_cooldown_address, find_index_address, find_treasury_config_address, get_program_data_address},
 };
 
+/// Maximum number of tokens an `IncludeTokenBatch` can carry. Every token adds three
+/// account keys (96 bytes) to the transaction, so nine more would not fit in the
+/// 1232 byte packet once the fixed accounts and a separate fee payer are counted.
+pub const MAX_INCLUDE_TOKEN_BATCH: usize = 8;
+
+/// Maximum number of collections a `LightUpStarsMulti` can light. Every collection adds its
+/// account key and an eight byte amount (41 bytes with its account index), twenty would just
+/// fit the 1232 byte packet beside a separate fee payer and the event accounts, sixteen leave
+/// room for a compute budget instruction.
+pub const MAX_LIGHT_UP_MULTI_COLLECTIONS: usize = 16;
+
+/// Largest `CreateCollectionAccountArgs::serialized_len` that still fits a transaction. The
+/// `create_collection_with_account` pair, signed by the payer and the new account, takes 391
+/// bytes of the 1232 byte packet and the instruction tag one more.
+pub const MAX_CREATE_COLLECTION_ARGS_LEN: usize = 840;
+
 #[repr(C)]
-#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone, Default)]
 pub struct CreateCollectionAccountArgs {
     pub title: String,
     pub symbol: String,
@@ -21,16 +50,122 @@ pub struct CreateCollectionAccountArgs {
     pub short_description: Option<String>,
     pub banner: Option<String>,
     pub tags: Option<Vec<String>>,
+    /// Royalty hint for marketplaces in basis points, at most 10000
+    pub royalty_bps: Option<u16>,
+    /// Lamports charged per star, `LAMPORTS_PER_STAR` when `None`
+    pub star_price: Option<u64>,
+    /// Co-signer required next to the authority for metadata and authority changes
+    pub secondary_authority: Option<Pubkey>,
+    /// Off-chain JSON with the extended metadata, held to the same rules as the image URIs
+    pub metadata_uri: Option<String>,
+    /// Fixed category for filtering, see `CollectionCategory`
+    pub category: Option<CollectionCategory>,
+    /// Royalty split between creators as `(creator, share in basis points)`, the shares add up
+    /// to 100%
+    pub creators: Option<Vec<(Pubkey, u16)>>,
+}
+
+impl BorshDeserialize for CreateCollectionAccountArgs {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let title = String::deserialize(buf)?;
+        let symbol = String::deserialize(buf)?;
+        let description = String::deserialize(buf)?;
+        let icon_image = String::deserialize(buf)?;
+        let header_image = Option::<String>::deserialize(buf)?;
+        let short_description = Option::<String>::deserialize(buf)?;
+        let banner = Option::<String>::deserialize(buf)?;
+        let tags = Option::<Vec<String>>::deserialize(buf)?;
+        // older clients end the args after the tags
+        let royalty_bps = if buf.is_empty() { None } else { Option::<u16>::deserialize(buf)? };
+        let star_price = if buf.is_empty() { None } else { Option::<u64>::deserialize(buf)? };
+        let secondary_authority = if buf.is_empty() { None } else { Option::<Pubkey>::deserialize(buf)? };
+        let metadata_uri = if buf.is_empty() { None } else { Option::<String>::deserialize(buf)? };
+        let category = if buf.is_empty() { None } else { Option::<CollectionCategory>::deserialize(buf)? };
+        let creators = if buf.is_empty() { None } else { Option::<Vec<(Pubkey, u16)>>::deserialize(buf)? };
+        Ok(CreateCollectionAccountArgs {
+            title,
+            symbol,
+            description,
+            icon_image,
+            header_image,
+            short_description,
+            banner,
+            tags,
+            royalty_bps,
+            star_price,
+            secondary_authority,
+            metadata_uri,
+            category,
+            creators,
+        })
+    }
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Default)]
+pub struct UpdateCollectionMetadataArgs {
+    pub title: Option<String>,
+    pub symbol: Option<String>,
+    pub description: Option<String>,
+    pub icon_image: Option<String>,
+    pub header_image: Option<String>,
+    pub short_description: Option<String>,
+    pub banner: Option<String>,
+    pub tags: Option<Vec<String>>,
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Clone)]
+/// Instruction data starts with a fixed tag byte per variant, followed by the Borsh encoding
+/// of its fields. Tags never change once deployed and new variants take the next free one:
+///
+///    0. `CreateCollectionAccount`
+///    1. `IncludeToken`
+///    2. `LightUpStarsOnce`
+///    3. `LightUpStarsHundred`
+///    4. `LightUpStarsThousand`
+///    5. `CloseAccount`
+///    6. `Withdraw`
+///    7. `UpdateCollectionMetadata`
+///    8. `RemoveToken`
+///    9. `LightUpStarsCustom`
+///   10. `TransferAuthority`
+///   11. `IncludeTokenBatch`
+///   12. `WithdrawAmount`
+///   13. `FreezeCollection`
+///   14. `SetTags`
+///   15. `LightUpStarsWithToken`
+///   16. `SetStarPrice`
+///   17. `ValidateMetadata`
+///   18. `SetVerified`
+///   19. `AddTag`
+///   20. `RemoveTag`
+///   21. `EmitEvent`
+///   22. `MoveToken`
+///   23. `SetDescription`
+///   24. `SetBanner`
+///   25. `LightUpStarsTenThousand`
+///   26. `SetMetadataUri`
+///   27. `AddToAllowlist`
+///   28. `RemoveFromAllowlist`
+///   29. `AdjustStars`
+///   30. `SetCategory`
+///   31. `SetTreasuryRecipient`
+///   32. `MigrateAccount`
+///   33. `SetParent`
+///   34. `SetCooldown`
+///   35. `ClearOptionalMetadata`
+///   36. `LightUpStarsMulti`
+///   37. `SetCollectionMint`
+///   38. `SetStarToken`
+///   39. `WithdrawToken`
+#[derive(Clone)]
 pub enum CollectionInstruction {
     /// create collection account
     ///
     /// Accounts expected by:
     /// 
-    ///   0. `[writeable, signer]` Collcection account
-    ///   1. `[signer]` Funding account (must be a system account)
+    ///   0. `[writable, signer]` Collection account, either a new account or one already
+    ///      allocated to the program with room for the collection
+    ///   1. `[writable, signer]` Funding account (must be a system account)
     ///   2. `[]` System rent account
     ///   3. `[]` System program id account
     CreateCollectionAccount(CreateCollectionAccountArgs),
@@ -39,63 +174,668 @@ pub enum CollectionInstruction {
     ///
     /// Accounts expected by:
     /// 
-    ///   0. `[writeable]` Collcection account
+    ///   0. `[writable]` Collection account
     ///   1. `[signer]` Authority of collection account
-    ///   2. `[]` Mint of token asset (supply must be 1)
-    ///   3. `[]` Token account of mint (amount must be 1)
-    ///   4. `[writable]`  Collection index account (pda of ['collection', program id, mint id])
-    ///   5. `[signer]` Funding account (must be a system account)
-    ///   6. `[]` Rent info
-    ///   7. `[]` System program id account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    ///   3. `[]` Mint of token asset (supply must be 1, not the collection mint)
+    ///   4. `[]` Token account of mint (amount must be 1)
+    ///   5. `[writable]`  Collection index account (pda of ['collection', program id, mint id])
+    ///   6. `[signer]` Funding account (must be a system account)
+    ///   7. `[]` Rent info
+    ///   8. `[]` System program id account
+    ///   9. `[]` Allowlist account (pda of ['collection', 'allowlist', collection]), required
+    ///      once the collection has one
     IncludeToken,
 
-    /// light up collection stars once
+    /// light up collection stars once, at most `MAX_FREE_STARS_PER_SLOT` times a slot. The star
+    /// is free and the collection account does not sign, anyone may light up any collection.
+    /// Accounts without room for the free star counters fail with `MigrationRequired`
     ///
     /// Accounts expected by:
     /// 
-    ///   0. `[writeable, singer]` Collcection account
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Account lighting the star, required once the collection has a cooldown
+    ///   2. `[writable]` Cooldown account (pda of ['collection', 'cooldown', collection]),
+    ///      required once the collection has a cooldown
     LightUpStarsOnce,
 
-    /// light up collection stars one hundred
+    /// light up collection stars one hundred, paid by the funding account, which must sign.
+    /// The collection account does not sign, anyone may light up any collection
     ///
     /// Accounts expected by:
     /// 
-    ///   0. `[writeable, singer]` Collcection account
-    ///   1. `[signer]` Funding account (must be a system account)
+    ///   0. `[writable]` Collection account
+    ///   1. `[writable, signer]` Funding account (must be a system account)
     ///   2. `[writable]` Collection treasury account (pda of ['collection', 'treasury', program id])
     ///   3. `[]` System program id account
     LightUpStarsHundred,
 
-    /// light up collection stars one thousand
+    /// light up collection stars one thousand, paid by the funding account, which must sign.
+    /// The collection account does not sign, anyone may light up any collection
     ///
     /// Accounts expected by:
     /// 
-    ///   0. `[writeable, singer]` Collcection account
-    ///   1. `[signer]` Funding account (must be a system account)
+    ///   0. `[writable]` Collection account
+    ///   1. `[writable, signer]` Funding account (must be a system account)
     ///   2. `[writable]` Collection treasury account (pda of ['collection', 'treasury', program id])
     ///   3. `[]` System program id account
     LightUpStarsThousand,
 
-    /// create collection account
+    /// close collection, index, allowlist or cooldown account, refunding its lamports. A
+    /// collection can only be closed once every index account of it and its allowlist and
+    /// cooldown accounts have been closed, closing the cooldown account turns the cooldown off.
     ///
     /// Accounts expected by:
     /// 
-    ///   0. `[writeable]` Account
-    ///   1. `[writeable]` Funding recipient account (must be a system account)
-    ///   2. `[signer]` Account's authority
+    ///   0. `[writable]` Account
+    ///   1. `[writable]` Funding recipient account (must be a system account)
+    ///   2. `[signer]` Account's authority (the collection authority for index, allowlist and
+    ///      cooldown accounts)
+    ///   3. `[writable]` Collection account of the index, allowlist or cooldown, for those accounts only
+    ///   4. `[signer]` Secondary authority of the collection, only when it has one (in place of
+    ///      the collection account at 3 when closing a collection)
     CloseAccount(AccountType),
 
-    /// withdraw funds in program pda
+    /// withdraw every lamport of program pda above its rent exempt reserve
     ///
     /// Accounts expected by:
     /// 
-    ///   0. `[signer]` Program id owner account (must be a system account)
+    ///   0. `[signer]` Upgrade authority of the program (the treasury manager)
     ///   1. `[writable]` Collection treasury account (pda of ['collection', 'treasury', program id])
-    ///   2. `[writable]` Destination account
+    ///   2. `[writable]` Destination account, the bound treasury recipient
+    ///   3. `[]` Program data account (pda of [program id] under the upgradeable loader)
+    ///   4. `[]` System program id account
+    ///   5. `[]` Treasury config account (pda of ['collection', 'treasury_config'])
     Withdraw,
+
+    /// update collection metadata, fields left as `None` are unchanged. With a funding account
+    /// the collection account is reallocated to fit the updated collection exactly: the funding
+    /// account pays the rent of a larger account, by at most 10KB per instruction, and gets back
+    /// the rent a smaller one frees. Without one the update has to fit the current space and
+    /// fails with `MigrationRequired` otherwise
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    ///   3. `[writable, signer]` Funding account, optional, right after the authorities
+    ///   4. `[]` System program id account, with the funding account
+    UpdateCollectionMetadata(UpdateCollectionMetadataArgs),
+
+    /// remove token from the collection and close its index account
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    ///   3. `[]` Mint of token asset
+    ///   4. `[writable]` Collection index account (pda of ['collection', program id, mint id])
+    ///   5. `[writable]` Rent recipient account
+    RemoveToken,
+
+    /// light up the given number of collection stars, charged at the collection's star price,
+    /// at most `MAX_STARS_PER_CALL`
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[writable, signer]` Funding account (must be a system account)
+    ///   2. `[writable]` Collection treasury account (pda of ['collection', 'treasury', program id])
+    ///   3. `[]` System program id account
+    LightUpStarsCustom(u64),
+
+    /// transfer collection authority to a new pubkey
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Current authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    TransferAuthority(Pubkey),
+
+    /// include up to `MAX_INCLUDE_TOKEN_BATCH` tokens to the collection
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    ///   3. `[signer]` Funding account (must be a system account)
+    ///   4. `[]` Rent info
+    ///   5. `[]` System program id account
+    ///
+    /// followed by the given number of token triples:
+    ///
+    ///   0. `[]` Mint of token asset (supply must be 1)
+    ///   1. `[]` Token account of mint (amount must be 1)
+    ///   2. `[writable]` Collection index account (pda of ['collection', program id, mint id])
+    ///
+    /// and, once the collection has one, its allowlist account.
+    IncludeTokenBatch(u8),
+
+    /// withdraw the given lamports from program pda, leaving it rent exempt
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[signer]` Upgrade authority of the program (the treasury manager)
+    ///   1. `[writable]` Collection treasury account (pda of ['collection', 'treasury', program id])
+    ///   2. `[writable]` Destination account, the bound treasury recipient
+    ///   3. `[]` Program data account (pda of [program id] under the upgradeable loader)
+    ///   4. `[]` System program id account
+    ///   5. `[]` Treasury config account (pda of ['collection', 'treasury_config'])
+    WithdrawAmount(u64),
+
+    /// freeze collection, its metadata and authority can not change afterwards
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    FreezeCollection,
+
+    /// replace collection tags, leaving the rest of the metadata unchanged. Longer tags than the
+    /// account has room for, as after `ClearOptionalMetadata`, fail with `MigrationRequired`
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    SetTags(Vec<String>),
+
+    /// light up collection stars by paying `amount` base units of the star mint set with
+    /// `SetStarToken`, one star per `star_token_price` and at most `MAX_STARS_PER_CALL`. Only
+    /// whole stars are charged, the rest of `amount` stays with the payer. Only the classic
+    /// token program is accepted, Token-2022 transfer fees would shortchange the treasury
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Owner of the funding token account
+    ///   2. `[writable]` Funding token account (of the star mint)
+    ///   3. `[writable]` Treasury token account (of the star mint, owned by the collection treasury)
+    ///   4. `[]` Token program id account (the classic token program)
+    ///   5. `[]` Treasury config account (pda of ['collection', 'treasury_config'])
+    LightUpStarsWithToken(u64),
+
+    /// set the lamports charged per star by the paid light-up instructions
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    SetStarPrice(u64),
+
+    /// check collection metadata without creating anything, for simulating before paying
+    /// for `CreateCollectionAccount`
+    ///
+    /// No accounts expected.
+    ValidateMetadata(CreateCollectionAccountArgs),
+
+    /// mark or unmark the collection as verified
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Upgrade authority of the program (the curator)
+    ///   2. `[]` Program data account (pda of [program id] under the upgradeable loader)
+    SetVerified(bool),
+
+    /// append a single tag to the collection tags, `MigrationRequired` when the account has no
+    /// room left for it
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    AddTag(String),
+
+    /// remove a tag from the collection tags, compared case-insensitively
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    RemoveTag(String),
+
+    /// no-op carrying a `CollectionEvent`, only invoked by the program itself
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[signer]` Event authority (pda of ["collection", "event_authority"])
+    EmitEvent(CollectionEvent),
+
+    /// move an included token to another collection, renumbering it there
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Source collection account
+    ///   1. `[signer]` Authority of the source collection account
+    ///   2. `[signer]` Secondary authority of the source collection, only when it has one
+    ///   3. `[writable]` Destination collection account
+    ///   4. `[signer]` Authority of the destination collection account
+    ///   5. `[signer]` Secondary authority of the destination collection, only when it has one
+    ///   6. `[]` Mint of token asset
+    ///   7. `[writable]` Collection index account (pda of ['collection', program id, mint id])
+    ///   8. `[]` Allowlist account of the destination collection, required once it has one
+    MoveToken,
+
+    /// replace only the collection description
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    SetDescription(String),
+
+    /// replace or, with `None`, clear only the collection banner. A banner the account has no
+    /// room for fails with `MigrationRequired`
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    SetBanner(Option<String>),
+
+    /// light up collection stars ten thousand, paid by the funding account, which must sign.
+    /// The collection account does not sign, anyone may light up any collection
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[writable, signer]` Funding account (must be a system account)
+    ///   2. `[writable]` Collection treasury account (pda of ['collection', 'treasury', program id])
+    ///   3. `[]` System program id account
+    LightUpStarsTenThousand,
+
+    /// replace or, with `None`, clear the URI of the off-chain collection metadata
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    SetMetadataUri(Option<String>),
+
+    /// permit `mint` in the collection, creating its allowlist on the first call. From then on
+    /// only permitted mints can be included
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    ///   3. `[writable]` Allowlist account (pda of ['collection', 'allowlist', collection])
+    ///   4. `[writable, signer]` Funding account (must be a system account)
+    ///   5. `[]` Rent info
+    ///   6. `[]` System program id account
+    AddToAllowlist(Pubkey),
+
+    /// withdraw the permission of `mint`, included tokens stay in the collection
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    ///   3. `[writable]` Allowlist account (pda of ['collection', 'allowlist', collection])
+    RemoveFromAllowlist(Pubkey),
+
+    /// correct the star count of a collection by a signed delta, flooring at zero
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    AdjustStars(i64),
+
+    /// replace or, with `None`, clear the category of a collection
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    SetCategory(Option<CollectionCategory>),
+
+    /// bind the only account withdrawals from the treasury may send to
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[signer]` Upgrade authority of the program (the treasury manager)
+    ///   1. `[writable]` Treasury config account (pda of ['collection', 'treasury_config'])
+    ///   2. `[writable, signer]` Funding account (must be a system account)
+    ///   3. `[]` Program data account (pda of [program id] under the upgradeable loader)
+    ///   4. `[]` Rent info
+    ///   5. `[]` System program id account
+    SetTreasuryRecipient(Pubkey),
+
+    /// upgrade a collection account to the current layout, growing it to `CollectionAccountData::LEN`
+    /// when it was allocated for an older version or shrunk by `ClearOptionalMetadata`. Current
+    /// accounts of full size are left untouched. Until then instructions writing more than such
+    /// an account has room for fail with `MigrationRequired`
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    ///   3. `[writable, signer]` Funding account for the extra rent (must be a system account)
+    ///   4. `[]` System program id account
+    MigrateAccount,
+
+    /// make another collection the parent of this one or, with `None`, detach it from its parent.
+    /// Both authorities sign a new link, a collection can not be its own parent or the parent of
+    /// its own parent
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    ///   3. `[]` Parent collection account (only when setting a parent)
+    ///   4. `[signer]` Authority of the parent collection account (only when setting a parent)
+    ///   5. `[signer]` Secondary authority of the parent collection, only when setting a parent
+    ///      that has one
+    SetParent(Option<Pubkey>),
+
+    /// require `cooldown_slots` slots between two free stars of the same signer, creating the
+    /// cooldown account on the first call. Zero turns the cooldown off. While it is on,
+    /// `LightUpStarsOnce` needs the signer and the cooldown account.
+    ///
+    /// Accounts expected by:
+    ///
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    ///   3. `[writable]` Cooldown account (pda of ['collection', 'cooldown', collection])
+    ///   4. `[writable, signer]` Funding account (must be a system account)
+    ///   5. `[]` Rent info
+    ///   6. `[]` System program id account
+    SetCooldown(u64),
+
+    /// clear the header image, short description, banner and tags, and shrink the account to
+    /// `CollectionAccountData::CLEARED_LEN` refunding the rent it no longer needs. Fields set
+    /// again later only fit into the room the others leave and fail with `MigrationRequired`
+    /// otherwise, `MigrateAccount` restores the full size
+    ///
+    /// Accounts expected by:
+    ///
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    ///   3. `[writable]` Recipient of the refunded rent
+    ClearOptionalMetadata,
+
+    /// light up stars of up to `MAX_LIGHT_UP_MULTI_COLLECTIONS` collections at once, the
+    /// given amounts in the order of the collection accounts, each charged at the star price
+    /// of its collection and at most `MAX_STARS_PER_CALL`
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable, signer]` Funding account (must be a system account)
+    ///   1. `[writable]` Collection treasury account (pda of ['collection', 'treasury', program id])
+    ///   2. `[]` System program id account
+    ///
+    /// followed by one `[writable]` collection account per amount.
+    LightUpStarsMulti(Vec<u64>),
+
+    /// link the collection to the NFT mint that represents it, like the mint of a Metaplex
+    /// collection, or unlink it with `None`. The mint must have supply 1 and 0 decimals
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[writable]` Collection account
+    ///   1. `[signer]` Authority of collection account
+    ///   2. `[signer]` Secondary authority, only when the collection has one
+    ///   3. `[]` Collection mint account (only when setting a mint)
+    SetCollectionMint(Option<Pubkey>),
+
+    /// set the only mint `LightUpStarsWithToken` accepts with its price in base units per
+    /// star, or stop selling stars for tokens with `None`. Needs the treasury config that
+    /// `SetTreasuryRecipient` creates
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[signer]` Upgrade authority of the program (the treasury manager)
+    ///   1. `[writable]` Treasury config account (pda of ['collection', 'treasury_config'])
+    ///   2. `[writable, signer]` Funding account (must be a system account)
+    ///   3. `[]` Program data account (pda of [program id] under the upgradeable loader)
+    ///   4. `[]` System program id account
+    SetStarToken(Option<(Pubkey, u64)>),
+
+    /// withdraw the given base units from a token account of the treasury, such as the star
+    /// token `LightUpStarsWithToken` collects, to a token account of the bound treasury recipient
+    ///
+    /// Accounts expected by:
+    /// 
+    ///   0. `[signer]` Upgrade authority of the program (the treasury manager)
+    ///   1. `[]` Collection treasury account (pda of ['collection', 'treasury', program id])
+    ///   2. `[writable]` Treasury token account (owned by the collection treasury)
+    ///   3. `[writable]` Destination token account (owned by the bound treasury recipient)
+    ///   4. `[]` Program data account (pda of [program id] under the upgradeable loader)
+    ///   5. `[]` Token program id account (the classic token program)
+    ///   6. `[]` Treasury config account (pda of ['collection', 'treasury_config'])
+    WithdrawToken(u64),
+}
+
+impl CollectionInstruction {
+    /// Leading byte of the variant in instruction data, see the table above.
+    pub fn tag(&self) -> u8 {
+        match self {
+            CollectionInstruction::CreateCollectionAccount(_) => 0,
+            CollectionInstruction::IncludeToken => 1,
+            CollectionInstruction::LightUpStarsOnce => 2,
+            CollectionInstruction::LightUpStarsHundred => 3,
+            CollectionInstruction::LightUpStarsThousand => 4,
+            CollectionInstruction::CloseAccount(_) => 5,
+            CollectionInstruction::Withdraw => 6,
+            CollectionInstruction::UpdateCollectionMetadata(_) => 7,
+            CollectionInstruction::RemoveToken => 8,
+            CollectionInstruction::LightUpStarsCustom(_) => 9,
+            CollectionInstruction::TransferAuthority(_) => 10,
+            CollectionInstruction::IncludeTokenBatch(_) => 11,
+            CollectionInstruction::WithdrawAmount(_) => 12,
+            CollectionInstruction::FreezeCollection => 13,
+            CollectionInstruction::SetTags(_) => 14,
+            CollectionInstruction::LightUpStarsWithToken(_) => 15,
+            CollectionInstruction::SetStarPrice(_) => 16,
+            CollectionInstruction::ValidateMetadata(_) => 17,
+            CollectionInstruction::SetVerified(_) => 18,
+            CollectionInstruction::AddTag(_) => 19,
+            CollectionInstruction::RemoveTag(_) => 20,
+            CollectionInstruction::EmitEvent(_) => 21,
+            CollectionInstruction::MoveToken => 22,
+            CollectionInstruction::SetDescription(_) => 23,
+            CollectionInstruction::SetBanner(_) => 24,
+            CollectionInstruction::LightUpStarsTenThousand => 25,
+            CollectionInstruction::SetMetadataUri(_) => 26,
+            CollectionInstruction::AddToAllowlist(_) => 27,
+            CollectionInstruction::RemoveFromAllowlist(_) => 28,
+            CollectionInstruction::AdjustStars(_) => 29,
+            CollectionInstruction::SetCategory(_) => 30,
+            CollectionInstruction::SetTreasuryRecipient(_) => 31,
+            CollectionInstruction::MigrateAccount => 32,
+            CollectionInstruction::SetParent(_) => 33,
+            CollectionInstruction::SetCooldown(_) => 34,
+            CollectionInstruction::ClearOptionalMetadata => 35,
+            CollectionInstruction::LightUpStarsMulti(_) => 36,
+            CollectionInstruction::SetCollectionMint(_) => 37,
+            CollectionInstruction::SetStarToken(_) => 38,
+            CollectionInstruction::WithdrawToken(_) => 39,
+        }
+    }
+
+    /// Human-readable name of the variant, as logged by the processor.
+    pub fn variant_name(&self) -> &'static str {
+        match self {
+            CollectionInstruction::CreateCollectionAccount(_) => "Create Collection Account",
+            CollectionInstruction::IncludeToken => "Include Token",
+            CollectionInstruction::LightUpStarsOnce => "Light Up Stars Once",
+            CollectionInstruction::LightUpStarsThousand => "Light Up Stars One Thousand",
+            CollectionInstruction::LightUpStarsHundred => "Light Up Stars One Hundred",
+            CollectionInstruction::CloseAccount(_) => "Close Account",
+            CollectionInstruction::Withdraw => "Withdraw",
+            CollectionInstruction::UpdateCollectionMetadata(_) => "Update Collection Metadata",
+            CollectionInstruction::RemoveToken => "Remove Token",
+            CollectionInstruction::LightUpStarsCustom(_) => "Light Up Stars Custom",
+            CollectionInstruction::TransferAuthority(_) => "Transfer Authority",
+            CollectionInstruction::IncludeTokenBatch(_) => "Include Token Batch",
+            CollectionInstruction::WithdrawAmount(_) => "Withdraw Amount",
+            CollectionInstruction::FreezeCollection => "Freeze Collection",
+            CollectionInstruction::SetTags(_) => "Set Tags",
+            CollectionInstruction::LightUpStarsWithToken(_) => "Light Up Stars With Token",
+            CollectionInstruction::SetStarPrice(_) => "Set Star Price",
+            CollectionInstruction::ValidateMetadata(_) => "Validate Metadata",
+            CollectionInstruction::SetVerified(_) => "Set Verified",
+            CollectionInstruction::AddTag(_) => "Add Tag",
+            CollectionInstruction::RemoveTag(_) => "Remove Tag",
+            CollectionInstruction::EmitEvent(_) => "Emit Event",
+            CollectionInstruction::MoveToken => "Move Token",
+            CollectionInstruction::SetDescription(_) => "Set Description",
+            CollectionInstruction::SetBanner(_) => "Set Banner",
+            CollectionInstruction::LightUpStarsTenThousand => "Light Up Stars Ten Thousand",
+            CollectionInstruction::SetMetadataUri(_) => "Set Metadata Uri",
+            CollectionInstruction::AddToAllowlist(_) => "Add To Allowlist",
+            CollectionInstruction::RemoveFromAllowlist(_) => "Remove From Allowlist",
+            CollectionInstruction::AdjustStars(_) => "Adjust Stars",
+            CollectionInstruction::SetCategory(_) => "Set Category",
+            CollectionInstruction::SetTreasuryRecipient(_) => "Set Treasury Recipient",
+            CollectionInstruction::MigrateAccount => "Migrate Account",
+            CollectionInstruction::SetParent(_) => "Set Parent",
+            CollectionInstruction::SetCooldown(_) => "Set Cooldown",
+            CollectionInstruction::ClearOptionalMetadata => "Clear Optional Metadata",
+            CollectionInstruction::LightUpStarsMulti(_) => "Light Up Stars Multi",
+            CollectionInstruction::SetCollectionMint(_) => "Set Collection Mint",
+            CollectionInstruction::SetStarToken(_) => "Set Star Token",
+            CollectionInstruction::WithdrawToken(_) => "Withdraw Token",
+        }
+    }
+
+    /// Encodes the instruction as its tag byte followed by its fields.
+    pub fn pack(&self) -> Vec<u8> {
+        self.try_to_vec().unwrap()
+    }
+
+    /// Decodes instruction data, reporting malformed input as `InvalidInstructionData`.
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(input).map_err(|_| ProgramError::InvalidInstructionData)
+    }
+}
+
+impl BorshSerialize for CollectionInstruction {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.tag().serialize(writer)?;
+        match self {
+            CollectionInstruction::CreateCollectionAccount(value) => value.serialize(writer),
+            CollectionInstruction::CloseAccount(value) => value.serialize(writer),
+            CollectionInstruction::UpdateCollectionMetadata(value) => value.serialize(writer),
+            CollectionInstruction::LightUpStarsCustom(value) => value.serialize(writer),
+            CollectionInstruction::TransferAuthority(value) => value.serialize(writer),
+            CollectionInstruction::IncludeTokenBatch(value) => value.serialize(writer),
+            CollectionInstruction::WithdrawAmount(value) => value.serialize(writer),
+            CollectionInstruction::SetTags(value) => value.serialize(writer),
+            CollectionInstruction::LightUpStarsWithToken(value) => value.serialize(writer),
+            CollectionInstruction::SetStarPrice(value) => value.serialize(writer),
+            CollectionInstruction::ValidateMetadata(value) => value.serialize(writer),
+            CollectionInstruction::SetVerified(value) => value.serialize(writer),
+            CollectionInstruction::AddTag(value) => value.serialize(writer),
+            CollectionInstruction::RemoveTag(value) => value.serialize(writer),
+            CollectionInstruction::EmitEvent(value) => value.serialize(writer),
+            CollectionInstruction::SetDescription(value) => value.serialize(writer),
+            CollectionInstruction::SetBanner(value) => value.serialize(writer),
+            CollectionInstruction::SetMetadataUri(value) => value.serialize(writer),
+            CollectionInstruction::AddToAllowlist(value) => value.serialize(writer),
+            CollectionInstruction::RemoveFromAllowlist(value) => value.serialize(writer),
+            CollectionInstruction::AdjustStars(value) => value.serialize(writer),
+            CollectionInstruction::SetCategory(value) => value.serialize(writer),
+            CollectionInstruction::SetTreasuryRecipient(value) => value.serialize(writer),
+            CollectionInstruction::SetParent(value) => value.serialize(writer),
+            CollectionInstruction::SetCooldown(value) => value.serialize(writer),
+            CollectionInstruction::LightUpStarsMulti(value) => value.serialize(writer),
+            CollectionInstruction::SetCollectionMint(value) => value.serialize(writer),
+            CollectionInstruction::SetStarToken(value) => value.serialize(writer),
+            CollectionInstruction::WithdrawToken(value) => value.serialize(writer),
+            CollectionInstruction::IncludeToken
+            | CollectionInstruction::LightUpStarsOnce
+            | CollectionInstruction::LightUpStarsHundred
+            | CollectionInstruction::LightUpStarsThousand
+            | CollectionInstruction::Withdraw
+            | CollectionInstruction::RemoveToken
+            | CollectionInstruction::FreezeCollection
+            | CollectionInstruction::MoveToken
+            | CollectionInstruction::LightUpStarsTenThousand
+            | CollectionInstruction::MigrateAccount
+            | CollectionInstruction::ClearOptionalMetadata => Ok(()),
+        }
+    }
+}
+
+impl BorshDeserialize for CollectionInstruction {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let instruction = match u8::deserialize(buf)? {
+            0 => CollectionInstruction::CreateCollectionAccount(CreateCollectionAccountArgs::deserialize(buf)?),
+            1 => CollectionInstruction::IncludeToken,
+            2 => CollectionInstruction::LightUpStarsOnce,
+            3 => CollectionInstruction::LightUpStarsHundred,
+            4 => CollectionInstruction::LightUpStarsThousand,
+            5 => CollectionInstruction::CloseAccount(AccountType::deserialize(buf)?),
+            6 => CollectionInstruction::Withdraw,
+            7 => CollectionInstruction::UpdateCollectionMetadata(UpdateCollectionMetadataArgs::deserialize(buf)?),
+            8 => CollectionInstruction::RemoveToken,
+            9 => CollectionInstruction::LightUpStarsCustom(u64::deserialize(buf)?),
+            10 => CollectionInstruction::TransferAuthority(Pubkey::deserialize(buf)?),
+            11 => CollectionInstruction::IncludeTokenBatch(u8::deserialize(buf)?),
+            12 => CollectionInstruction::WithdrawAmount(u64::deserialize(buf)?),
+            13 => CollectionInstruction::FreezeCollection,
+            14 => CollectionInstruction::SetTags(Vec::<String>::deserialize(buf)?),
+            15 => CollectionInstruction::LightUpStarsWithToken(u64::deserialize(buf)?),
+            16 => CollectionInstruction::SetStarPrice(u64::deserialize(buf)?),
+            17 => CollectionInstruction::ValidateMetadata(CreateCollectionAccountArgs::deserialize(buf)?),
+            18 => CollectionInstruction::SetVerified(bool::deserialize(buf)?),
+            19 => CollectionInstruction::AddTag(String::deserialize(buf)?),
+            20 => CollectionInstruction::RemoveTag(String::deserialize(buf)?),
+            21 => CollectionInstruction::EmitEvent(CollectionEvent::deserialize(buf)?),
+            22 => CollectionInstruction::MoveToken,
+            23 => CollectionInstruction::SetDescription(String::deserialize(buf)?),
+            24 => CollectionInstruction::SetBanner(Option::<String>::deserialize(buf)?),
+            25 => CollectionInstruction::LightUpStarsTenThousand,
+            26 => CollectionInstruction::SetMetadataUri(Option::<String>::deserialize(buf)?),
+            27 => CollectionInstruction::AddToAllowlist(Pubkey::deserialize(buf)?),
+            28 => CollectionInstruction::RemoveFromAllowlist(Pubkey::deserialize(buf)?),
+            29 => CollectionInstruction::AdjustStars(i64::deserialize(buf)?),
+            30 => CollectionInstruction::SetCategory(Option::<CollectionCategory>::deserialize(buf)?),
+            31 => CollectionInstruction::SetTreasuryRecipient(Pubkey::deserialize(buf)?),
+            32 => CollectionInstruction::MigrateAccount,
+            33 => CollectionInstruction::SetParent(Option::<Pubkey>::deserialize(buf)?),
+            34 => CollectionInstruction::SetCooldown(u64::deserialize(buf)?),
+            35 => CollectionInstruction::ClearOptionalMetadata,
+            36 => CollectionInstruction::LightUpStarsMulti(Vec::<u64>::deserialize(buf)?),
+            37 => CollectionInstruction::SetCollectionMint(Option::<Pubkey>::deserialize(buf)?),
+            38 => CollectionInstruction::SetStarToken(Option::<(Pubkey, u64)>::deserialize(buf)?),
+            39 => CollectionInstruction::WithdrawToken(u64::deserialize(buf)?),
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Unknown instruction tag",
+                ))
+            }
+        };
+        Ok(instruction)
+    }
 }
 
 /// Creates a `CreateCollectionAccount` instruction.
+#[cfg(feature = "client-builders")]
 pub fn create_collection_account(
     program_id: Pubkey,
     collection_account: Pubkey,
@@ -106,7 +846,7 @@ pub fn create_collection_account(
         program_id,
         accounts: vec![
             AccountMeta::new(collection_account, true),
-            AccountMeta::new_readonly(from_account, true),
+            AccountMeta::new(from_account, true),
             AccountMeta::new_readonly(sysvar::rent::id(), false),
             AccountMeta::new_readonly(system_program::id(), false),
         ],
@@ -114,16 +854,41 @@ pub fn create_collection_account(
     }
 }
 
-/// Creates an `IncludeToken` instruction.
+/// Creates the `create_account` of `new_account` with `space` bytes owned by the program,
+/// followed by the `CreateCollectionAccount` storing `args` in it. `space` must hold at least
+/// `args.space()` bytes, `CollectionAccountData::LEN` leaves room for every later update.
+#[cfg(feature = "client-builders")]
+pub fn create_collection_with_account(
+    program_id: Pubkey,
+    payer: Pubkey,
+    new_account: Pubkey,
+    rent: &Rent,
+    space: usize,
+    args: CreateCollectionAccountArgs,
+) -> Vec<Instruction> {
+    vec![
+        system_instruction::create_account(
+            &payer,
+            &new_account,
+            rent.minimum_balance(space),
+            space as u64,
+            &program_id,
+        ),
+        create_collection_account(program_id, new_account, payer, args),
+    ]
+}
+
+/// Creates an `IncludeToken` instruction, deriving the index account of `mint_account`.
+#[cfg(feature = "client-builders")]
 pub fn include_token(
     program_id: Pubkey,
     collection_account: Pubkey,
     collection_authority_account: Pubkey,
     mint_account: Pubkey,
     mint_token_account: Pubkey,
-    index_account: Pubkey,
     payer_account: Pubkey,
 ) -> Instruction{
+    let (index_account, _) = find_index_address(&program_id, &mint_account);
     Instruction {
         program_id,
         accounts: vec![
@@ -135,12 +900,45 @@ pub fn include_token(
             AccountMeta::new(payer_account, true),
             AccountMeta::new_readonly(sysvar::rent::id(), false),
             AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(find_allowlist_address(&program_id, &collection_account).0, false),
         ],
         data: CollectionInstruction::IncludeToken.try_to_vec().unwrap(),
     }
 }
 
+/// Creates an `IncludeTokenBatch` instruction from `(mint, token account)` pairs,
+/// deriving the index account of every mint.
+#[cfg(feature = "client-builders")]
+pub fn include_token_batch(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    tokens: &[(Pubkey, Pubkey)],
+    payer_account: Pubkey,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(collection_account, false),
+        AccountMeta::new(collection_authority_account, true),
+        AccountMeta::new(payer_account, true),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    for (mint_account, mint_token_account) in tokens {
+        let (index_account, _) = find_index_address(&program_id, mint_account);
+        accounts.push(AccountMeta::new_readonly(*mint_account, false));
+        accounts.push(AccountMeta::new_readonly(*mint_token_account, false));
+        accounts.push(AccountMeta::new(index_account, false));
+    }
+    accounts.push(AccountMeta::new_readonly(find_allowlist_address(&program_id, &collection_account).0, false));
+    Instruction {
+        program_id,
+        accounts,
+        data: CollectionInstruction::IncludeTokenBatch(tokens.len() as u8).try_to_vec().unwrap(),
+    }
+}
+
 /// Creates a `LightUpStarsOnce` instruction.
+#[cfg(feature = "client-builders")]
 pub fn light_up_stars_once(
     program_id: Pubkey,
     collection_account: Pubkey,
@@ -148,13 +946,37 @@ pub fn light_up_stars_once(
     Instruction {
         program_id,
         accounts: vec![
-            AccountMeta::new(collection_account, true),
+            AccountMeta::new(collection_account, false),
+        ],
+        data: CollectionInstruction::LightUpStarsOnce.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `LightUpStarsOnce` instruction lit by `signer_account`, for collections with a
+/// cooldown. It derives the cooldown account of the collection.
+#[cfg(feature = "client-builders")]
+pub fn light_up_stars_once_with_cooldown(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    signer_account: Pubkey,
+) -> Instruction {
+    let (cooldown_account, _) = find_cooldown_address(&program_id, &collection_account);
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(signer_account, true),
+            AccountMeta::new(cooldown_account, false),
         ],
         data: CollectionInstruction::LightUpStarsOnce.try_to_vec().unwrap(),
     }
 }
 
-/// Creates a `LightUpStarsHundred` instruction.
+/// Creates a `LightUpStarsHundred` instruction, `source_account` pays and is the only signer.
+///
+/// `destination_account` must be the treasury, derive it with
+/// [`find_treasury_address`](crate::utils::find_treasury_address).
+#[cfg(feature = "client-builders")]
 pub fn light_up_stars_hundred(
     program_id: Pubkey,
     collection_account: Pubkey,
@@ -173,67 +995,998 @@ pub fn light_up_stars_hundred(
     }
 }
 
-/// Creates a `CloseAccount` instruction.
-pub fn close_account(
+/// Creates a `LightUpStarsThousand` instruction.
+///
+/// `destination_account` must be the treasury, derive it with
+/// [`find_treasury_address`](crate::utils::find_treasury_address).
+#[cfg(feature = "client-builders")]
+pub fn light_up_stars_thousand(
     program_id: Pubkey,
-    state_account: Pubkey,
-    recipient_account: Pubkey,
-    authority_account: Pubkey,
-    account_type: AccountType,
+    collection_account: Pubkey,
+    source_account: Pubkey,
+    destination_account: Pubkey,
 ) -> Instruction{
     Instruction {
         program_id,
         accounts: vec![
-            AccountMeta::new(state_account, false),
-            AccountMeta::new(recipient_account, false),
-            AccountMeta::new(authority_account, true),
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new(source_account, true),
+            AccountMeta::new(destination_account, false),
+            AccountMeta::new_readonly(system_program::id(), false),
         ],
-        data: CollectionInstruction::CloseAccount(account_type).try_to_vec().unwrap(),
+        data: CollectionInstruction::LightUpStarsThousand.try_to_vec().unwrap(),
     }
 }
 
-/// Creates a `Withdraw` instruction.
-pub fn withdraw(
+/// Creates a `LightUpStarsTenThousand` instruction.
+///
+/// `destination_account` must be the treasury, derive it with
+/// [`find_treasury_address`](crate::utils::find_treasury_address).
+#[cfg(feature = "client-builders")]
+pub fn light_up_stars_ten_thousand(
     program_id: Pubkey,
-    treasury_manager_account: Pubkey,
-    treasury_account: Pubkey,
-    recipient_account: Pubkey,
-) -> Instruction {
-    Instruction {
-        program_id,
-        accounts: vec![
-            AccountMeta::new(treasury_manager_account, true),
-            AccountMeta::new(treasury_account, false),
+    collection_account: Pubkey,
+    source_account: Pubkey,
+    destination_account: Pubkey,
+) -> Instruction{
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new(source_account, true),
+            AccountMeta::new(destination_account, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CollectionInstruction::LightUpStarsTenThousand.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `LightUpStarsCustom` instruction.
+///
+/// `destination_account` must be the treasury, derive it with
+/// [`find_treasury_address`](crate::utils::find_treasury_address).
+#[cfg(feature = "client-builders")]
+pub fn light_up_stars_custom(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    source_account: Pubkey,
+    destination_account: Pubkey,
+    amount: u64,
+) -> Instruction{
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new(source_account, true),
+            AccountMeta::new(destination_account, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CollectionInstruction::LightUpStarsCustom(amount).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `LightUpStarsMulti` instruction from `(collection, amount)` pairs, all charged
+/// to `source_account`.
+///
+/// `destination_account` must be the treasury, derive it with
+/// [`find_treasury_address`](crate::utils::find_treasury_address).
+#[cfg(feature = "client-builders")]
+pub fn light_up_stars_multi(
+    program_id: Pubkey,
+    source_account: Pubkey,
+    destination_account: Pubkey,
+    collections: &[(Pubkey, u64)],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(source_account, true),
+        AccountMeta::new(destination_account, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(collections.iter().map(|(collection_account, _)| AccountMeta::new(*collection_account, false)));
+    let amounts = collections.iter().map(|(_, amount)| *amount).collect();
+    Instruction {
+        program_id,
+        accounts,
+        data: CollectionInstruction::LightUpStarsMulti(amounts).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `TransferAuthority` instruction.
+#[cfg(feature = "client-builders")]
+pub fn transfer_authority(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    new_authority: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+        ],
+        data: CollectionInstruction::TransferAuthority(new_authority).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `FreezeCollection` instruction.
+#[cfg(feature = "client-builders")]
+pub fn freeze_collection(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+        ],
+        data: CollectionInstruction::FreezeCollection.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `SetTags` instruction.
+#[cfg(feature = "client-builders")]
+pub fn set_tags(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    tags: Vec<String>,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+        ],
+        data: CollectionInstruction::SetTags(tags).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `LightUpStarsWithToken` instruction.
+///
+/// `treasury_token_account` must be a token account owned by the treasury, derive the
+/// treasury with [`find_treasury_address`](crate::utils::find_treasury_address).
+#[cfg(feature = "client-builders")]
+pub fn light_up_stars_with_token(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    source_owner_account: Pubkey,
+    source_token_account: Pubkey,
+    treasury_token_account: Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(source_owner_account, true),
+            AccountMeta::new(source_token_account, false),
+            AccountMeta::new(treasury_token_account, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(find_treasury_config_address(&program_id).0, false),
+        ],
+        data: CollectionInstruction::LightUpStarsWithToken(amount).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `SetStarPrice` instruction.
+#[cfg(feature = "client-builders")]
+pub fn set_star_price(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    star_price: u64,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+        ],
+        data: CollectionInstruction::SetStarPrice(star_price).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates an `AdjustStars` instruction.
+#[cfg(feature = "client-builders")]
+pub fn adjust_stars(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    delta: i64,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+        ],
+        data: CollectionInstruction::AdjustStars(delta).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `ValidateMetadata` instruction.
+#[cfg(feature = "client-builders")]
+pub fn validate_metadata(
+    program_id: Pubkey,
+    args: CreateCollectionAccountArgs,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![],
+        data: CollectionInstruction::ValidateMetadata(args).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `SetVerified` instruction.
+#[cfg(feature = "client-builders")]
+pub fn set_verified(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    curator_account: Pubkey,
+    verified: bool,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(curator_account, true),
+            AccountMeta::new_readonly(get_program_data_address(&program_id), false),
+        ],
+        data: CollectionInstruction::SetVerified(verified).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates an `AddTag` instruction.
+#[cfg(feature = "client-builders")]
+pub fn add_tag(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    tag: String,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+        ],
+        data: CollectionInstruction::AddTag(tag).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `RemoveTag` instruction.
+#[cfg(feature = "client-builders")]
+pub fn remove_tag(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    tag: String,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+        ],
+        data: CollectionInstruction::RemoveTag(tag).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `CloseAccount` instruction.
+#[cfg(feature = "client-builders")]
+pub fn close_account(
+    program_id: Pubkey,
+    state_account: Pubkey,
+    recipient_account: Pubkey,
+    authority_account: Pubkey,
+    account_type: AccountType,
+) -> Instruction{
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(state_account, false),
+            AccountMeta::new(recipient_account, false),
+            AccountMeta::new(authority_account, true),
+        ],
+        data: CollectionInstruction::CloseAccount(account_type).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `CloseAccount` instruction for the index account of a collection.
+#[cfg(feature = "client-builders")]
+pub fn close_index_account(
+    program_id: Pubkey,
+    index_account: Pubkey,
+    recipient_account: Pubkey,
+    collection_authority_account: Pubkey,
+    collection_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(index_account, false),
+            AccountMeta::new(recipient_account, false),
+            AccountMeta::new(collection_authority_account, true),
+            AccountMeta::new(collection_account, false),
+        ],
+        data: CollectionInstruction::CloseAccount(AccountType::CollectionIndexAccount).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `CloseAccount` instruction for the allowlist of a collection.
+#[cfg(feature = "client-builders")]
+pub fn close_allowlist_account(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    recipient_account: Pubkey,
+    collection_authority_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(find_allowlist_address(&program_id, &collection_account).0, false),
+            AccountMeta::new(recipient_account, false),
+            AccountMeta::new(collection_authority_account, true),
+            AccountMeta::new(collection_account, false),
+        ],
+        data: CollectionInstruction::CloseAccount(AccountType::Allowlist).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `CloseAccount` instruction for the cooldown account of a collection.
+#[cfg(feature = "client-builders")]
+pub fn close_cooldown_account(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    recipient_account: Pubkey,
+    collection_authority_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(find_cooldown_address(&program_id, &collection_account).0, false),
+            AccountMeta::new(recipient_account, false),
+            AccountMeta::new(collection_authority_account, true),
+            AccountMeta::new(collection_account, false),
+        ],
+        data: CollectionInstruction::CloseAccount(AccountType::Cooldown).try_to_vec().unwrap(),
+    }
+}
+
+/// Accounts of `Withdraw` and `WithdrawAmount`, which share one layout.
+#[cfg(feature = "client-builders")]
+fn withdraw_accounts(
+    program_id: Pubkey,
+    treasury_manager_account: Pubkey,
+    treasury_account: Pubkey,
+    recipient_account: Pubkey,
+) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(treasury_manager_account, true),
+        AccountMeta::new(treasury_account, false),
+        AccountMeta::new(recipient_account, false),
+        AccountMeta::new_readonly(get_program_data_address(&program_id), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(find_treasury_config_address(&program_id).0, false),
+    ]
+}
+
+/// Creates a `Withdraw` instruction.
+///
+/// `treasury_account` must be the treasury, derive it with
+/// [`find_treasury_address`](crate::utils::find_treasury_address).
+#[cfg(feature = "client-builders")]
+pub fn withdraw(
+    program_id: Pubkey,
+    treasury_manager_account: Pubkey,
+    treasury_account: Pubkey,
+    recipient_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: withdraw_accounts(program_id, treasury_manager_account, treasury_account, recipient_account),
+        data: CollectionInstruction::Withdraw.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `WithdrawAmount` instruction.
+///
+/// `treasury_account` must be the treasury, derive it with
+/// [`find_treasury_address`](crate::utils::find_treasury_address).
+#[cfg(feature = "client-builders")]
+pub fn withdraw_amount(
+    program_id: Pubkey,
+    treasury_manager_account: Pubkey,
+    treasury_account: Pubkey,
+    recipient_account: Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: withdraw_accounts(program_id, treasury_manager_account, treasury_account, recipient_account),
+        data: CollectionInstruction::WithdrawAmount(amount).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `SetTreasuryRecipient` instruction, deriving the treasury config account.
+#[cfg(feature = "client-builders")]
+pub fn set_treasury_recipient(
+    program_id: Pubkey,
+    treasury_manager_account: Pubkey,
+    payer_account: Pubkey,
+    treasury_recipient: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(treasury_manager_account, true),
+            AccountMeta::new(find_treasury_config_address(&program_id).0, false),
+            AccountMeta::new(payer_account, true),
+            AccountMeta::new_readonly(get_program_data_address(&program_id), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CollectionInstruction::SetTreasuryRecipient(treasury_recipient).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `WithdrawToken` instruction sending `amount` base units from
+/// `treasury_token_account` to `recipient_token_account`.
+///
+/// `treasury_account` must be the treasury, derive it with
+/// [`find_treasury_address`](crate::utils::find_treasury_address).
+#[cfg(feature = "client-builders")]
+pub fn withdraw_token(
+    program_id: Pubkey,
+    treasury_manager_account: Pubkey,
+    treasury_account: Pubkey,
+    treasury_token_account: Pubkey,
+    recipient_token_account: Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(treasury_manager_account, true),
+            AccountMeta::new_readonly(treasury_account, false),
+            AccountMeta::new(treasury_token_account, false),
+            AccountMeta::new(recipient_token_account, false),
+            AccountMeta::new_readonly(get_program_data_address(&program_id), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(find_treasury_config_address(&program_id).0, false),
+        ],
+        data: CollectionInstruction::WithdrawToken(amount).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `SetStarToken` instruction, the payer funds the rent of a grown treasury config.
+#[cfg(feature = "client-builders")]
+pub fn set_star_token(
+    program_id: Pubkey,
+    treasury_manager_account: Pubkey,
+    payer_account: Pubkey,
+    star_token: Option<(Pubkey, u64)>,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(treasury_manager_account, true),
+            AccountMeta::new(find_treasury_config_address(&program_id).0, false),
+            AccountMeta::new(payer_account, true),
+            AccountMeta::new_readonly(get_program_data_address(&program_id), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CollectionInstruction::SetStarToken(star_token).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `MigrateAccount` instruction, the payer funds the rent of a grown account.
+#[cfg(feature = "client-builders")]
+pub fn migrate_account(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    payer_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+            AccountMeta::new(payer_account, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CollectionInstruction::MigrateAccount.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `SetParent` instruction linking `collection_account` to `parent_collection_account`,
+/// signed by the authorities of both.
+#[cfg(feature = "client-builders")]
+pub fn set_parent(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    parent_collection_account: Pubkey,
+    parent_authority_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+            AccountMeta::new_readonly(parent_collection_account, false),
+            AccountMeta::new_readonly(parent_authority_account, true),
+        ],
+        data: CollectionInstruction::SetParent(Some(parent_collection_account)).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `SetParent` instruction detaching `collection_account` from its parent.
+#[cfg(feature = "client-builders")]
+pub fn clear_parent(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+        ],
+        data: CollectionInstruction::SetParent(None).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `ClearOptionalMetadata` instruction, refunding the freed rent to `recipient_account`.
+#[cfg(feature = "client-builders")]
+pub fn clear_optional_metadata(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    recipient_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
             AccountMeta::new(recipient_account, false),
         ],
-        data: CollectionInstruction::Withdraw.try_to_vec().unwrap(),
+        data: CollectionInstruction::ClearOptionalMetadata.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `SetCooldown` instruction, deriving the cooldown account of the collection.
+#[cfg(feature = "client-builders")]
+pub fn set_cooldown(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    payer_account: Pubkey,
+    cooldown_slots: u64,
+) -> Instruction {
+    let (cooldown_account, _) = find_cooldown_address(&program_id, &collection_account);
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+            AccountMeta::new(cooldown_account, false),
+            AccountMeta::new(payer_account, true),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CollectionInstruction::SetCooldown(cooldown_slots).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `SetCollectionMint` instruction linking `collection_account` to the NFT
+/// `collection_mint_account`.
+#[cfg(feature = "client-builders")]
+pub fn set_collection_mint(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    collection_mint_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+            AccountMeta::new_readonly(collection_mint_account, false),
+        ],
+        data: CollectionInstruction::SetCollectionMint(Some(collection_mint_account)).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `SetCollectionMint` instruction unlinking `collection_account` from its mint.
+#[cfg(feature = "client-builders")]
+pub fn clear_collection_mint(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+        ],
+        data: CollectionInstruction::SetCollectionMint(None).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates an `UpdateCollectionMetadata` instruction.
+#[cfg(feature = "client-builders")]
+pub fn update_collection_metadata(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    args: UpdateCollectionMetadataArgs,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+        ],
+        data: CollectionInstruction::UpdateCollectionMetadata(args).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates an `UpdateCollectionMetadata` instruction that reallocates the collection account
+/// to fit, funded by `payer_account`. Collections with a secondary authority need it inserted
+/// after `collection_authority_account`.
+#[cfg(feature = "client-builders")]
+pub fn update_collection_metadata_with_realloc(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    payer_account: Pubkey,
+    args: UpdateCollectionMetadataArgs,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+            AccountMeta::new(payer_account, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CollectionInstruction::UpdateCollectionMetadata(args).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `RemoveToken` instruction.
+#[cfg(feature = "client-builders")]
+pub fn remove_token(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    mint_account: Pubkey,
+    index_account: Pubkey,
+    recipient_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+            AccountMeta::new_readonly(mint_account, false),
+            AccountMeta::new(index_account, false),
+            AccountMeta::new(recipient_account, false),
+        ],
+        data: CollectionInstruction::RemoveToken.try_to_vec().unwrap(),
+    }
+}
+
+/// Adds the secondary authority of a co-signed collection to an instruction the collection
+/// authority signs, right after the authority. Every instruction gated on the authority needs
+/// it, for `MoveToken` and `SetParent` this is the source or child collection, see
+/// `add_destination_secondary_authority` for the other one.
+#[cfg(feature = "client-builders")]
+pub fn add_secondary_authority(instruction: &mut Instruction, secondary_authority: Pubkey) {
+    let index = match CollectionInstruction::unpack(&instruction.data) {
+        Ok(CollectionInstruction::CloseAccount(AccountType::CollectionAccount)) => 3,
+        Ok(CollectionInstruction::CloseAccount(_)) => 4,
+        _ => 2,
+    };
+    instruction.accounts.insert(index, AccountMeta::new_readonly(secondary_authority, true));
+}
+
+/// Adds the secondary authority of the destination collection of a `MoveToken` or of the
+/// parent collection of a `SetParent`, right after that collection's authority. Call
+/// `add_secondary_authority` first when both collections are co-signed.
+#[cfg(feature = "client-builders")]
+pub fn add_destination_secondary_authority(
+    instruction: &mut Instruction,
+    secondary_authority: Pubkey,
+) {
+    let index = if instruction.accounts[2].is_signer { 5 } else { 4 };
+    instruction.accounts.insert(index, AccountMeta::new_readonly(secondary_authority, true));
+}
+
+/// Creates a `SetDescription` instruction.
+#[cfg(feature = "client-builders")]
+pub fn set_description(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    description: String,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+        ],
+        data: CollectionInstruction::SetDescription(description).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `SetBanner` instruction.
+#[cfg(feature = "client-builders")]
+pub fn set_banner(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    banner: Option<String>,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+        ],
+        data: CollectionInstruction::SetBanner(banner).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `SetMetadataUri` instruction.
+#[cfg(feature = "client-builders")]
+pub fn set_metadata_uri(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    metadata_uri: Option<String>,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+        ],
+        data: CollectionInstruction::SetMetadataUri(metadata_uri).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `SetCategory` instruction.
+#[cfg(feature = "client-builders")]
+pub fn set_category(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    category: Option<CollectionCategory>,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+        ],
+        data: CollectionInstruction::SetCategory(category).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `MoveToken` instruction. The index address only depends on the mint, so the
+/// same index account is rewritten for the destination collection.
+#[cfg(feature = "client-builders")]
+pub fn move_token(
+    program_id: Pubkey,
+    source_collection_account: Pubkey,
+    source_authority_account: Pubkey,
+    destination_collection_account: Pubkey,
+    destination_authority_account: Pubkey,
+    mint_account: Pubkey,
+) -> Instruction {
+    let (index_account, _) = find_index_address(&program_id, &mint_account);
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(source_collection_account, false),
+            AccountMeta::new_readonly(source_authority_account, true),
+            AccountMeta::new(destination_collection_account, false),
+            AccountMeta::new_readonly(destination_authority_account, true),
+            AccountMeta::new_readonly(mint_account, false),
+            AccountMeta::new(index_account, false),
+            AccountMeta::new_readonly(find_allowlist_address(&program_id, &destination_collection_account).0, false),
+        ],
+        data: CollectionInstruction::MoveToken.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates an `AddToAllowlist` instruction, deriving the allowlist account of the collection.
+#[cfg(feature = "client-builders")]
+pub fn add_to_allowlist(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    payer_account: Pubkey,
+    mint: Pubkey,
+) -> Instruction {
+    let (allowlist_account, _) = find_allowlist_address(&program_id, &collection_account);
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+            AccountMeta::new(allowlist_account, false),
+            AccountMeta::new(payer_account, true),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CollectionInstruction::AddToAllowlist(mint).try_to_vec().unwrap(),
+    }
+}
+
+/// Creates a `RemoveFromAllowlist` instruction, deriving the allowlist account of the collection.
+#[cfg(feature = "client-builders")]
+pub fn remove_from_allowlist(
+    program_id: Pubkey,
+    collection_account: Pubkey,
+    collection_authority_account: Pubkey,
+    mint: Pubkey,
+) -> Instruction {
+    let (allowlist_account, _) = find_allowlist_address(&program_id, &collection_account);
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(collection_account, false),
+            AccountMeta::new_readonly(collection_authority_account, true),
+            AccountMeta::new(allowlist_account, false),
+        ],
+        data: CollectionInstruction::RemoveFromAllowlist(mint).try_to_vec().unwrap(),
+    }
+}
+
+/// URI schemes accepted for collection images.
+pub const VALID_URI_SCHEMES: [&str; 3] = ["https://", "ipfs://", "ar://"];
+
+/// Returns whether `uri` uses one of the `VALID_URI_SCHEMES` and names something after it.
+pub fn is_valid_uri(uri: &str) -> bool {
+    VALID_URI_SCHEMES.iter().any(|scheme| match uri.strip_prefix(scheme) {
+        Some(rest) => !rest.trim().is_empty(),
+        None => false,
+    })
+}
+
+/// Gateway URL prefixes rewritten by `canonical_uri`, with the scheme replacing them.
+pub const URI_GATEWAYS: [(&str, &str); 6] = [
+    ("https://ipfs.io/ipfs/", "ipfs://"),
+    ("https://gateway.pinata.cloud/ipfs/", "ipfs://"),
+    ("https://cloudflare-ipfs.com/ipfs/", "ipfs://"),
+    ("https://dweb.link/ipfs/", "ipfs://"),
+    ("https://arweave.net/", "ar://"),
+    ("https://www.arweave.net/", "ar://"),
+];
+
+/// Rewrites a known IPFS or Arweave gateway URL to its `ipfs://` or `ar://` form, leaving
+/// any other URI untouched.
+pub fn canonical_uri(uri: &str) -> String {
+    for (gateway, scheme) in URI_GATEWAYS.iter() {
+        match uri.strip_prefix(gateway) {
+            Some(path) if !path.is_empty() => return format!("{}{}", scheme, path),
+            _ => {}
+        }
     }
+    uri.to_string()
 }
 
 impl CreateCollectionAccountArgs {
-    const MAX_TITLE_LENGTH: usize = 32;
+    /// Minimum title length in characters, ignoring surrounding whitespace.
+    const MIN_TITLE_LENGTH: usize = 1;
+
+    /// Maximum title length in characters.
+    pub(crate) const MAX_TITLE_LENGTH: usize = 32;
 
-    const MAX_SYMBOL_LENGTH: usize = 10;
+    /// Minimum symbol length in characters, ignoring surrounding whitespace.
+    const MIN_SYMBOL_LENGTH: usize = 1;
 
-    const MAX_URI_LENGTH: usize = 200;
+    /// Maximum symbol length in characters. Symbols may only use `A-Z` and `0-9`.
+    pub(crate) const MAX_SYMBOL_LENGTH: usize = 10;
 
-    const MAX_DESCRIPTION_LENGTH: usize = 800;
+    /// Minimum icon image length in bytes, ignoring surrounding whitespace.
+    const MIN_ICON_IMAGE_LENGTH: usize = 1;
 
-    const MAX_SHORT_DESCRIPTION_LENGTH: usize = 800;
+    /// Maximum URI length in bytes.
+    pub(crate) const MAX_URI_LENGTH: usize = 200;
 
-    const MAX_TAG_LENGTH: usize = 20;
+    /// Maximum description length in characters.
+    pub(crate) const MAX_DESCRIPTION_LENGTH: usize = 800;
 
-    const MAX_TAGS_ARRAY_LENGTH: usize = 6;
+    /// Maximum short description length in characters.
+    pub(crate) const MAX_SHORT_DESCRIPTION_LENGTH: usize = 800;
+
+    /// Maximum tag length in characters.
+    pub(crate) const MAX_TAG_LENGTH: usize = 20;
+
+    pub(crate) const MAX_TAGS_ARRAY_LENGTH: usize = 6;
+
+    /// Maximum royalty in basis points, a royalty of 100%.
+    const MAX_ROYALTY_BPS: u16 = 10_000;
+
+    /// Maximum number of creators sharing the royalty.
+    pub(crate) const MAX_CREATORS: usize = 5;
+
+    /// Sum of the creator shares in basis points, all of the royalty.
+    const CREATOR_SHARES_TOTAL: u32 = 10_000;
 
     pub fn is_valid(&self) -> bool {
-        self.title.len() <= CreateCollectionAccountArgs::MAX_TITLE_LENGTH
-        && self.symbol.len() <= CreateCollectionAccountArgs::MAX_SYMBOL_LENGTH
-        && self.description.len() <= CreateCollectionAccountArgs::MAX_DESCRIPTION_LENGTH
+        self.title.trim().chars().count() >= CreateCollectionAccountArgs::MIN_TITLE_LENGTH
+        && self.title.chars().count() <= CreateCollectionAccountArgs::MAX_TITLE_LENGTH
+        && self.symbol.trim().chars().count() >= CreateCollectionAccountArgs::MIN_SYMBOL_LENGTH
+        && self.symbol.chars().count() <= CreateCollectionAccountArgs::MAX_SYMBOL_LENGTH
+        && self.symbol.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        && self.description.chars().count() <= CreateCollectionAccountArgs::MAX_DESCRIPTION_LENGTH
+        && self.icon_image.trim().len() >= CreateCollectionAccountArgs::MIN_ICON_IMAGE_LENGTH
         && self.icon_image.len() <= CreateCollectionAccountArgs::MAX_URI_LENGTH 
+        && is_valid_uri(&self.icon_image)
         && (self.header_image.is_none() || self.header_image.as_ref().unwrap().len() <= CreateCollectionAccountArgs::MAX_URI_LENGTH)
-        && (self.short_description.is_none() || self.short_description.as_ref().unwrap().len() <= CreateCollectionAccountArgs::MAX_SHORT_DESCRIPTION_LENGTH)
+        && (self.header_image.is_none() || is_valid_uri(self.header_image.as_ref().unwrap()))
+        && (self.short_description.is_none() || self.short_description.as_ref().unwrap().chars().count() <= CreateCollectionAccountArgs::MAX_SHORT_DESCRIPTION_LENGTH)
         && (self.banner.is_none() || self.banner.as_ref().unwrap().len() <= CreateCollectionAccountArgs::MAX_URI_LENGTH)
+        && (self.banner.is_none() || is_valid_uri(self.banner.as_ref().unwrap()))
         && self.check_tags()
+        && (self.royalty_bps.is_none() || self.royalty_bps.unwrap() <= CreateCollectionAccountArgs::MAX_ROYALTY_BPS)
+        && self.star_price != Some(0)
+        && (self.metadata_uri.is_none() || self.metadata_uri.as_ref().unwrap().len() <= CreateCollectionAccountArgs::MAX_URI_LENGTH)
+        && (self.metadata_uri.is_none() || is_valid_uri(self.metadata_uri.as_ref().unwrap()))
+        && self.check_creators()
+    }
+
+    /// Bytes a collection holding exactly these args takes. The program itself reserves
+    /// `CollectionAccountData::LEN` so the metadata can later grow, this is the lower bound
+    /// for accounts sized to their current content.
+    pub fn space(&self) -> usize {
+        CollectionAccountData::new(Pubkey::default(), self, 0).try_to_vec().unwrap().len()
+    }
+
+    /// Bytes the args take in `CreateCollectionAccount` instruction data, without the tag.
+    pub fn serialized_len(&self) -> usize {
+        self.try_to_vec().unwrap().len()
+    }
+
+    /// Lamports that make a collection of `space()` bytes rent exempt.
+    pub fn rent_exempt_lamports(&self, rent: &Rent) -> u64 {
+        rent.minimum_balance(self.space())
+    }
+
+    /// Trims surrounding whitespace from every string and tag, collapses whitespace runs
+    /// inside the title to a single space, strips ASCII control characters other than
+    /// newlines from the descriptions and rewrites gateway image URLs with `canonical_uri`.
+    pub fn normalized(self) -> Self {
+        let trim = |s: String| s.trim().to_string();
+        // markdown is kept as is, only characters some renderers choke on are dropped
+        let sanitize = |s: String| trim(s.chars().filter(|c| *c == '\n' || !c.is_ascii_control()).collect());
+        let uri = |s: String| canonical_uri(s.trim());
+        CreateCollectionAccountArgs {
+            title: self.title.split_whitespace().collect::<Vec<&str>>().join(" "),
+            symbol: trim(self.symbol),
+            description: sanitize(self.description),
+            icon_image: uri(self.icon_image),
+            header_image: self.header_image.map(uri),
+            short_description: self.short_description.map(sanitize),
+            banner: self.banner.map(uri),
+            tags: self.tags.map(|tags| tags.into_iter().map(trim).collect()),
+            metadata_uri: self.metadata_uri.map(uri),
+            ..self
+        }
     }
 
     pub fn check_tags(&self) -> bool {
@@ -243,11 +1996,161 @@ impl CreateCollectionAccountArgs {
         if self.tags.as_ref().unwrap().len() > CreateCollectionAccountArgs::MAX_TAGS_ARRAY_LENGTH {
             return false;
         }
+        let mut normalized_tags: Vec<String> = Vec::new();
         for tag in self.tags.as_ref().unwrap() {
-            if tag.len() >= CreateCollectionAccountArgs::MAX_TAG_LENGTH {
+            if tag.chars().count() > CreateCollectionAccountArgs::MAX_TAG_LENGTH {
+                return false;
+            }
+            // tags are compared case-insensitively
+            let normalized_tag = tag.to_lowercase();
+            if normalized_tags.contains(&normalized_tag) {
                 return false;
             }
+            normalized_tags.push(normalized_tag);
         }
         true
     }
-}
\ No newline at end of file
+
+    /// At most `MAX_CREATORS` creators whose shares add up to exactly 10000 basis points.
+    pub fn check_creators(&self) -> bool {
+        match &self.creators {
+            None => true,
+            Some(creators) => creators.len() <= CreateCollectionAccountArgs::MAX_CREATORS
+                && creators.iter().map(|(_, share)| *share as u32).sum::<u32>() == CreateCollectionAccountArgs::CREATOR_SHARES_TOTAL,
+        }
+    }
+}
+
+impl UpdateCollectionMetadataArgs {
+    /// Returns `current` with every field present in the update applied.
+    pub fn apply(&self, current: &CreateCollectionAccountArgs) -> CreateCollectionAccountArgs {
+        CreateCollectionAccountArgs {
+            title: self.title.clone().unwrap_or_else(|| current.title.clone()),
+            symbol: self.symbol.clone().unwrap_or_else(|| current.symbol.clone()),
+            description: self.description.clone().unwrap_or_else(|| current.description.clone()),
+            icon_image: self.icon_image.clone().unwrap_or_else(|| current.icon_image.clone()),
+            header_image: self.header_image.clone().or_else(|| current.header_image.clone()),
+            short_description: self.short_description.clone().or_else(|| current.short_description.clone()),
+            banner: self.banner.clone().or_else(|| current.banner.clone()),
+            tags: self.tags.clone().or_else(|| current.tags.clone()),
+            royalty_bps: current.royalty_bps,
+            star_price: current.star_price,
+            secondary_authority: current.secondary_authority,
+            metadata_uri: current.metadata_uri.clone(),
+            category: current.category,
+            creators: current.creators.clone(),
+        }
+    }
+}
+
+impl CreateCollectionAccountArgs {
+    /// Starts a `CreateCollectionAccountArgsBuilder` with every field empty.
+    pub fn builder() -> CreateCollectionAccountArgsBuilder {
+        CreateCollectionAccountArgsBuilder::default()
+    }
+}
+
+/// Builds `CreateCollectionAccountArgs`, optional fields default to `None`.
+///
+/// ```
+/// use collection::instruction::CreateCollectionAccountArgs;
+///
+/// let args = CreateCollectionAccountArgs::builder()
+///     .title("test collection")
+///     .symbol("TC")
+///     .description("test collection description")
+///     .icon_image("https://www.solana.com/icon.png")
+///     .build()
+///     .unwrap();
+/// assert_eq!(args.banner, None);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CreateCollectionAccountArgsBuilder {
+    args: CreateCollectionAccountArgs,
+}
+
+impl CreateCollectionAccountArgsBuilder {
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.args.title = title.into();
+        self
+    }
+
+    pub fn symbol(mut self, symbol: impl Into<String>) -> Self {
+        self.args.symbol = symbol.into();
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.args.description = description.into();
+        self
+    }
+
+    pub fn icon_image(mut self, icon_image: impl Into<String>) -> Self {
+        self.args.icon_image = icon_image.into();
+        self
+    }
+
+    pub fn header_image(mut self, header_image: impl Into<String>) -> Self {
+        self.args.header_image = Some(header_image.into());
+        self
+    }
+
+    pub fn short_description(mut self, short_description: impl Into<String>) -> Self {
+        self.args.short_description = Some(short_description.into());
+        self
+    }
+
+    pub fn banner(mut self, banner: impl Into<String>) -> Self {
+        self.args.banner = Some(banner.into());
+        self
+    }
+
+    pub fn tags(mut self, tags: Vec<String>) -> Self {
+        self.args.tags = Some(tags);
+        self
+    }
+
+    pub fn royalty_bps(mut self, royalty_bps: u16) -> Self {
+        self.args.royalty_bps = Some(royalty_bps);
+        self
+    }
+
+    pub fn star_price(mut self, star_price: u64) -> Self {
+        self.args.star_price = Some(star_price);
+        self
+    }
+
+    pub fn secondary_authority(mut self, secondary_authority: Pubkey) -> Self {
+        self.args.secondary_authority = Some(secondary_authority);
+        self
+    }
+
+    pub fn metadata_uri(mut self, metadata_uri: impl Into<String>) -> Self {
+        self.args.metadata_uri = Some(metadata_uri.into());
+        self
+    }
+
+    pub fn category(mut self, category: CollectionCategory) -> Self {
+        self.args.category = Some(category);
+        self
+    }
+
+    pub fn creators(mut self, creators: Vec<(Pubkey, u16)>) -> Self {
+        self.args.creators = Some(creators);
+        self
+    }
+
+    /// Returns the `normalized` args if they pass `CreateCollectionAccountArgs::is_valid` and
+    /// fit a transaction, see `MAX_CREATE_COLLECTION_ARGS_LEN`.
+    pub fn build(self) -> Result<CreateCollectionAccountArgs, CollectionError> {
+        let args = self.args.normalized();
+        if !args.is_valid() {
+            return Err(CollectionError::InvalidMetadata);
+        }
+        // valid args can still be far too large for a single transaction
+        if args.serialized_len() > MAX_CREATE_COLLECTION_ARGS_LEN {
+            return Err(CollectionError::ArgsTooLarge);
+        }
+        Ok(args)
+    }
+}
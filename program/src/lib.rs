@@ -7,5 +7,11 @@ pub mod utils;
 pub mod instruction;
 pub mod error; 
 pub mod state;
+pub mod seeds;
+pub mod event;
+#[cfg(feature = "client-builders")]
+pub mod cpi;
+#[cfg(feature = "client")]
+pub mod client;
 
 solana_program::declare_id!("co111CrRL738X8TKrqmLcNBstgLFZjuMtZRBW2FGpbC");
\ No newline at end of file
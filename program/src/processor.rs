@@ -1,64 +1,96 @@
 use solana_program::program_pack::IsInitialized;
 use {
     crate::{
-        instruction::{CollectionInstruction, CreateCollectionAccountArgs},
-        utils::{create_new_account, create_or_allocate_account_raw, get_index_account, get_treasury_account},
-        state::{PREFIX, AccountType, CollectionAccountData, CollectionIndexAccountData},
+        instruction::{
+            CollectionInstruction, CreateCollectionAccountArgs, UpdateCollectionMetadataArgs, MAX_INCLUDE_TOKEN_BATCH,
+            MAX_LIGHT_UP_MULTI_COLLECTIONS,
+        },
+        utils::{
+            create_index_address, create_new_account, create_or_allocate_account_raw, find_allowlist_address,
+            find_cooldown_address, find_event_authority_address, find_index_address, find_treasury_config_address,
+            get_program_data_address, get_treasury_account, withdrawable_balance, TREASURY_BUMP,
+        },
+        seeds::{ALLOWLIST_SEED, COLLECTION_SEED, COOLDOWN_SEED, TREASURY_CONFIG_SEED, TREASURY_SEED},
+        state::{
+            MAX_STARS, MAX_STARS_PER_CALL, MAX_FREE_STARS_PER_SLOT, MAX_ALLOWLIST_LENGTH, COLLECTION_ACCOUNT_VERSION,
+            AccountType, AllowlistAccountData, CollectionAccountData, CollectionCategory, CollectionIndexAccountData,
+            CooldownAccountData, TreasuryConfigAccountData, read_cooldown_slots, read_free_star_counters, read_stars, stars_cost, write_free_star_counters, write_stars,
+        },
         error::CollectionError,
+        event::{emit_event, CollectionEvent},
         check_id,
     },
     solana_program::{
         account_info::{AccountInfo, next_account_info},
+        bpf_loader_upgradeable::{self, UpgradeableLoaderState},
         system_instruction::transfer,
-        entrypoint::ProgramResult, 
+        entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE}, 
         pubkey::Pubkey,
-        program_pack::Pack,
-        native_token::sol_to_lamports,
-        program::invoke,
+        program::{invoke, invoke_signed},
+        program_error::ProgramError,
         program_option::COption,
+        program_pack::Pack,
+        sysvar::{clock::Clock, rent::Rent, Sysvar},
         msg,
     },
-    borsh::{BorshDeserialize, BorshSerialize},
-    spl_token::state::Mint as spl_mint,
-    spl_token::state::Account as spl_account,
+    borsh::BorshSerialize,
+    spl_token_2022::{
+        extension::StateWithExtensions,
+        state::{Account as spl_account, Mint as spl_mint},
+    },
 };
-use std::str::FromStr;
+
+/// Prefix of the log line written whenever stars are lit, see `log_stars`.
+pub const STARS_LOG_PREFIX: &str = "COLLECTION_STARS";
 
 pub fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     input: &[u8],
 ) -> ProgramResult {
-    let instruction = CollectionInstruction::try_from_slice(input)?;
+    let instruction = CollectionInstruction::unpack(input)?;
+    msg!("Instruction: {}", instruction.variant_name());
     match instruction {
-        CollectionInstruction::CreateCollectionAccount(args) => {
-            msg!("Instruction: Create Collection Account");
-            process_create_collection_account(program_id, accounts, &args)
-        },
-        CollectionInstruction::IncludeToken => {
-            msg!("Instruction: Include Token");
-            process_include_token(program_id, accounts)
-        },
-        CollectionInstruction::LightUpStarsOnce => {
-            msg!("Instruction: Light Up Stars Once");
-            process_light_up_stars_once(program_id, accounts)
-        },
-        CollectionInstruction::LightUpStarsThousand => {
-            msg!("Instruction: Light Up Stars One Thousand");
-            process_light_up_stars_thousand(program_id, accounts)
-        },
-        CollectionInstruction::LightUpStarsHundred => {
-            msg!("Instruction: Light Up Stars One Hundred");
-            process_light_up_stars_hundred(program_id, accounts)
-        },
-        CollectionInstruction::CloseAccount(account_type) => {
-            msg!("Instruction: Close Account");
-            process_close_account(program_id, accounts, account_type)
-        },
-        CollectionInstruction::Withdraw => {
-            msg!("Instruction: Withdraw");
-            process_withdraw(program_id, accounts)
-        }
+        CollectionInstruction::CreateCollectionAccount(args) => process_create_collection_account(program_id, accounts, &args),
+        CollectionInstruction::IncludeToken => process_include_token(program_id, accounts),
+        CollectionInstruction::LightUpStarsOnce => process_light_up_stars_once(program_id, accounts),
+        CollectionInstruction::LightUpStarsThousand => process_light_up_stars_thousand(program_id, accounts),
+        CollectionInstruction::LightUpStarsHundred => process_light_up_stars_hundred(program_id, accounts),
+        CollectionInstruction::CloseAccount(account_type) => process_close_account(program_id, accounts, account_type),
+        CollectionInstruction::Withdraw => process_withdraw(program_id, accounts),
+        CollectionInstruction::UpdateCollectionMetadata(args) => process_update_collection_metadata(program_id, accounts, &args),
+        CollectionInstruction::RemoveToken => process_remove_token(program_id, accounts),
+        CollectionInstruction::LightUpStarsCustom(amount) => process_light_up_stars_custom(program_id, accounts, amount),
+        CollectionInstruction::TransferAuthority(new_authority) => process_transfer_authority(program_id, accounts, new_authority),
+        CollectionInstruction::IncludeTokenBatch(count) => process_include_token_batch(program_id, accounts, count),
+        CollectionInstruction::WithdrawAmount(amount) => process_withdraw_amount(program_id, accounts, amount),
+        CollectionInstruction::FreezeCollection => process_freeze_collection(program_id, accounts),
+        CollectionInstruction::SetTags(tags) => process_set_tags(program_id, accounts, tags),
+        CollectionInstruction::LightUpStarsWithToken(amount) => process_light_up_stars_with_token(program_id, accounts, amount),
+        CollectionInstruction::SetStarPrice(star_price) => process_set_star_price(program_id, accounts, star_price),
+        CollectionInstruction::ValidateMetadata(args) => process_validate_metadata(program_id, &args),
+        CollectionInstruction::SetVerified(verified) => process_set_verified(program_id, accounts, verified),
+        CollectionInstruction::AddTag(tag) => process_add_tag(program_id, accounts, tag),
+        CollectionInstruction::RemoveTag(tag) => process_remove_tag(program_id, accounts, tag),
+        CollectionInstruction::EmitEvent(_) => process_emit_event(program_id, accounts),
+        CollectionInstruction::MoveToken => process_move_token(program_id, accounts),
+        CollectionInstruction::SetDescription(description) => process_set_description(program_id, accounts, description),
+        CollectionInstruction::SetBanner(banner) => process_set_banner(program_id, accounts, banner),
+        CollectionInstruction::LightUpStarsTenThousand => process_light_up_stars_ten_thousand(program_id, accounts),
+        CollectionInstruction::SetMetadataUri(metadata_uri) => process_set_metadata_uri(program_id, accounts, metadata_uri),
+        CollectionInstruction::AddToAllowlist(mint) => process_add_to_allowlist(program_id, accounts, mint),
+        CollectionInstruction::RemoveFromAllowlist(mint) => process_remove_from_allowlist(program_id, accounts, mint),
+        CollectionInstruction::AdjustStars(delta) => process_adjust_stars(program_id, accounts, delta),
+        CollectionInstruction::SetCategory(category) => process_set_category(program_id, accounts, category),
+        CollectionInstruction::SetTreasuryRecipient(treasury_recipient) => process_set_treasury_recipient(program_id, accounts, treasury_recipient),
+        CollectionInstruction::MigrateAccount => process_migrate_account(program_id, accounts),
+        CollectionInstruction::SetParent(parent) => process_set_parent(program_id, accounts, parent),
+        CollectionInstruction::SetCooldown(cooldown_slots) => process_set_cooldown(program_id, accounts, cooldown_slots),
+        CollectionInstruction::ClearOptionalMetadata => process_clear_optional_metadata(program_id, accounts),
+        CollectionInstruction::LightUpStarsMulti(amounts) => process_light_up_stars_multi(program_id, accounts, amounts),
+        CollectionInstruction::SetCollectionMint(collection_mint) => process_set_collection_mint(program_id, accounts, collection_mint),
+        CollectionInstruction::SetStarToken(star_token) => process_set_star_token(program_id, accounts, star_token),
+        CollectionInstruction::WithdrawToken(amount) => process_withdraw_token(program_id, accounts, amount),
     }
 }
 
@@ -74,31 +106,50 @@ pub fn process_create_collection_account(
     let form_account_info = next_account_info(account_info_iter)?;
     let rent_account_info = next_account_info(account_info_iter)?;
 
-    let collection_account_data = CollectionAccountData {
-        account_type: AccountType::CollectionAccount,
-        title: args.title.clone(),
-        symbol: args.symbol.clone(),
-        description: args.description.clone(),
-        icon_image: args.icon_image.clone(),
-        stars: 0 as u64,
-        supply: 0 as u64,
-        authority: *form_account_info.key,
-        header_image: args.header_image.clone(),
-        short_description: args.short_description.clone(),
-        banner: args.banner.clone(),
-        tags: args.tags.clone(),
-    };
-    let mut data: Vec<u8> = Vec::new();
-    collection_account_data.serialize(&mut data)?;
-    create_new_account(
-        form_account_info,
-        collection_account_info,
-        data.len(),
-        program_id,
-        rent_account_info,
-    ).unwrap();
+    if collection_account_info.owner == program_id
+        && AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount).is_ok() {
+        return Err(CollectionError::AlreadyInitialized.into());
+    }
+
+    let collection_account_data = CollectionAccountData::new(
+        *form_account_info.key,
+        args,
+        Clock::get()?.unix_timestamp,
+    );
+    // an account below the rent exempt minimum would be purged along with the collection
+    let rent = Rent::from_account_info(rent_account_info)?;
+    if collection_account_info.owner == program_id {
+        // allocated by the caller, see `create_collection_with_account`
+        if !collection_account_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if collection_account_data.try_to_vec()?.len() > collection_account_info.data_len() {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+    } else {
+        let required_lamports = rent
+            .minimum_balance(CollectionAccountData::LEN)
+            .saturating_sub(collection_account_info.lamports());
+        if form_account_info.lamports() < required_lamports {
+            return Err(CollectionError::InsufficientRent.into());
+        }
+        create_new_account(
+            form_account_info,
+            collection_account_info,
+            CollectionAccountData::LEN,
+            program_id,
+            rent_account_info,
+        )?;
+    }
+    if !rent.is_exempt(collection_account_info.lamports(), collection_account_info.data_len()) {
+        return Err(CollectionError::InsufficientRent.into());
+    }
     
-    collection_account_data.serialize(&mut *collection_account_info.data.borrow_mut())?;
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::CollectionCreated {
+        collection: *collection_account_info.key,
+        authority: collection_account_data.authority,
+    })?;
     Ok(())
 }
 
@@ -110,22 +161,119 @@ pub fn process_include_token(
     let account_info_iter = &mut accounts.iter();
     let collection_account_info = next_account_info(account_info_iter)?;
     let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow_mut())?;
+    // check collection's authority 
+    if collection_account_data.authority != *collection_auth_account_info.key 
+        || !collection_auth_account_info.is_signer {
+        return Err(CollectionError::NotCollectionAuthority.into());
+    }
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
     let mint_account_info = next_account_info(account_info_iter)?;
     let mint_token_account_info = next_account_info(account_info_iter)?;
     let index_account_info = next_account_info(account_info_iter)?;
     let payer_account_info = next_account_info(account_info_iter)?;
     let rent_sysvar_info = next_account_info(account_info_iter)?;
     let system_program_info = next_account_info(account_info_iter)?;
+    assert_mint_allowed(program_id, accounts, collection_account_info, &collection_account_data, mint_account_info.key)?;
+    include_token(
+        program_id,
+        collection_account_info,
+        &mut collection_account_data,
+        collection_auth_account_info,
+        mint_account_info,
+        mint_token_account_info,
+        index_account_info,
+        payer_account_info,
+        rent_sysvar_info,
+        system_program_info,
+    )?;
+    emit_event(program_id, accounts, CollectionEvent::TokenIncluded {
+        collection: *collection_account_info.key,
+        mint: *mint_account_info.key,
+        index: collection_account_data.index_seq - 1,
+    })?;
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    Ok(())
+}
+
+pub fn process_include_token_batch(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    count: u8,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
 
+    if count == 0 || count as usize > MAX_INCLUDE_TOKEN_BATCH {
+        return Err(CollectionError::InvalidInstructionArguments.into());
+    }
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
     let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
         &collection_account_info.data.borrow_mut())?;
-    if !collection_account_data.is_initialized() {
-        return Err(CollectionError::Uninitialized.into());
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let rent_sysvar_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    // the event accounts may trail the tokens
+    if account_info_iter.len() < count as usize * 3 {
+        return Err(CollectionError::InvalidInstructionArguments.into());
     }
-    // check collection's authority 
-    if collection_account_data.authority != *collection_auth_account_info.key 
-        || !collection_auth_account_info.is_signer {
-        return Err(CollectionError::NotCollectionAuthority.into());
+
+    for _ in 0..count {
+        let mint_account_info = next_account_info(account_info_iter)?;
+        let mint_token_account_info = next_account_info(account_info_iter)?;
+        let index_account_info = next_account_info(account_info_iter)?;
+        assert_mint_allowed(program_id, accounts, collection_account_info, &collection_account_data, mint_account_info.key)?;
+        include_token(
+            program_id,
+            collection_account_info,
+            &mut collection_account_data,
+            collection_auth_account_info,
+            mint_account_info,
+            mint_token_account_info,
+            index_account_info,
+            payer_account_info,
+            rent_sysvar_info,
+            system_program_info,
+        )?;
+        emit_event(program_id, accounts, CollectionEvent::TokenIncluded {
+            collection: *collection_account_info.key,
+            mint: *mint_account_info.key,
+            index: collection_account_data.index_seq - 1,
+        })?;
+    }
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Creates the index account of a single mint and counts it in `collection_account_data`,
+/// the caller is responsible for writing the collection back.
+#[allow(clippy::too_many_arguments)]
+fn include_token<'a>(
+    program_id: &Pubkey,
+    collection_account_info: &AccountInfo<'a>,
+    collection_account_data: &mut CollectionAccountData,
+    collection_auth_account_info: &AccountInfo<'a>,
+    mint_account_info: &AccountInfo<'a>,
+    mint_token_account_info: &AccountInfo<'a>,
+    index_account_info: &AccountInfo<'a>,
+    payer_account_info: &AccountInfo<'a>,
+    rent_sysvar_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    // the collection pays nothing, funding the index from it would skew its lamports
+    if payer_account_info.key == collection_account_info.key {
+        return Err(CollectionError::InvalidAccountArguments.into());
+    }
+    // the NFT standing for the collection can not be a member of it as well
+    if collection_account_data.collection_mint == Some(*mint_account_info.key) {
+        return Err(CollectionError::CannotIncludeCollectionMint.into());
     }
     assert_mint_authority(
         mint_account_info,
@@ -133,14 +281,17 @@ pub fn process_include_token(
         collection_auth_account_info,
     )?;
     
-    let (index_account, bump_seed) = get_index_account(
-        mint_account_info.key, 
-    );
+    // Only the canonical bump is ever used to sign for the index account, so an account
+    // derived from any other bump fails this comparison.
+    let (index_account, bump_seed) = find_index_address(program_id, mint_account_info.key);
     if index_account != *index_account_info.key {
         return Err(CollectionError::CollectionIndexAccountMismatch.into());
     }
+    if index_account_info.owner == program_id && !index_account_info.data_is_empty() {
+        return Err(CollectionError::TokenAlreadyIncluded.into());
+    }
     let signer_seeds = &[
-        PREFIX.as_bytes(),
+        COLLECTION_SEED,
         program_id.as_ref(),
         mint_account_info.key.as_ref(),
         &[bump_seed],
@@ -157,29 +308,102 @@ pub fn process_include_token(
     let index_account_data = CollectionIndexAccountData::new(
         *collection_account_info.key, 
         *mint_account_info.key, 
-        collection_account_data.supply,
+        collection_account_data.index_seq,
+        bump_seed,
     );
 
     index_account_data.serialize(&mut *index_account_info.data.borrow_mut())?;
-    collection_account_data.supply += 1;
-    collection_account_data.serialize(&mut *collection_account_info.data.borrow_mut())?;
+    collection_account_data.supply = collection_account_data.supply
+        .checked_add(1)
+        .ok_or(CollectionError::Overflow)?;
+    collection_account_data.index_seq = collection_account_data.index_seq
+        .checked_add(1)
+        .ok_or(CollectionError::Overflow)?;
     Ok(())
 }
 
-pub fn process_light_up_stars_once(
+pub fn process_remove_token(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     assert_program_id(program_id)?;
     let account_info_iter = &mut accounts.iter();
     let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
     let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
         &collection_account_info.data.borrow_mut())?;
-    if !collection_account_data.is_initialized() {
-        return Err(CollectionError::Uninitialized.into());
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+    let mint_account_info = next_account_info(account_info_iter)?;
+    let index_account_info = next_account_info(account_info_iter)?;
+    let recipient_account_info = next_account_info(account_info_iter)?;
+
+    load_index_account(program_id, collection_account_info, mint_account_info, index_account_info)?;
+
+    drain_lamports(index_account_info, recipient_account_info)?;
+    index_account_info.data.borrow_mut().fill(0);
+
+    collection_account_data.supply = collection_account_data.supply.saturating_sub(1);
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::TokenRemoved {
+        collection: *collection_account_info.key,
+        mint: *mint_account_info.key,
+    })?;
+    Ok(())
+}
+
+pub fn process_light_up_stars_once(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let slot = Clock::get()?.slot;
+
+    let cooldown_slots = read_cooldown_slots(&collection_account_info.data.borrow())?;
+    if cooldown_slots > 0 {
+        let signer_account_info = next_account_info(account_info_iter)?;
+        let cooldown_account_info = next_account_info(account_info_iter)?;
+        assert_signer(signer_account_info)?;
+        if find_cooldown_address(program_id, collection_account_info.key).0 != *cooldown_account_info.key
+            || cooldown_account_info.owner != program_id {
+            return Err(CollectionError::CooldownAccountMismatch.into());
+        }
+        let mut cooldown_data = load_cooldown(collection_account_info, cooldown_account_info)?;
+        cooldown_data.record(signer_account_info.key, slot, cooldown_slots)?;
+        cooldown_data.serialize(&mut *cooldown_account_info.data.borrow_mut())?;
     }
-    collection_account_data.stars += 1;
-    collection_account_data.serialize(&mut *collection_account_info.data.borrow_mut())?;
+
+    // the most frequent instruction only rewrites the star count and the free star counters in
+    // place, accounts older than the counters are decoded in full to be upgraded
+    let counters = read_free_star_counters(&collection_account_info.data.borrow())?;
+    let stars = match counters {
+        Some((last_star_slot, stars_this_slot)) => {
+            let stars = checked_stars(read_stars(&collection_account_info.data.borrow())?, 1)?;
+            let stars_this_slot = count_free_star(last_star_slot, stars_this_slot, slot)?;
+            let mut data = collection_account_info.data.borrow_mut();
+            write_stars(&mut data, stars)?;
+            write_free_star_counters(&mut data, slot, stars_this_slot)?;
+            stars
+        }
+        None => {
+            let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+                &collection_account_info.data.borrow())?;
+            add_stars(&mut collection_account_data, 1)?;
+            throttle_free_stars(&mut collection_account_data, collection_account_info.data_len(), slot)?;
+            collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+            collection_account_data.stars
+        }
+    };
+    log_stars(collection_account_info.key, 1, stars);
+    emit_event(program_id, accounts, CollectionEvent::StarsLit {
+        collection: *collection_account_info.key,
+        delta: 1,
+        total: stars,
+    })?;
     Ok(())
 }
 
@@ -193,8 +417,14 @@ pub fn process_light_up_stars_hundred(
     let source_account_info = next_account_info(account_info_iter)?;
     let destination_account_info = next_account_info(account_info_iter)?;
 
+    // the transfer would refuse an unsigned source as well, this names the missing signature up front
+    assert_signer(source_account_info)?;
     assert_treasury_account(destination_account_info)?;
-    let lamports = sol_to_lamports(0.01);
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow_mut())?;
+    add_stars(&mut collection_account_data, 100)?;
+    let lamports = star_cost(&collection_account_data, 100)?;
     invoke(
         &transfer(
             source_account_info.key,
@@ -206,13 +436,13 @@ pub fn process_light_up_stars_hundred(
             destination_account_info.clone(),
         ],
     )?;
-    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
-        &collection_account_info.data.borrow_mut())?;
-    if !collection_account_data.is_initialized() {
-        return Err(CollectionError::Uninitialized.into());
-    }
-    collection_account_data.stars += 100;
-    collection_account_data.serialize(&mut *collection_account_info.data.borrow_mut())?;
+    log_stars(collection_account_info.key, 100, collection_account_data.stars);
+    write_stars(&mut collection_account_info.data.borrow_mut(), collection_account_data.stars)?;
+    emit_event(program_id, accounts, CollectionEvent::StarsLit {
+        collection: *collection_account_info.key,
+        delta: 100,
+        total: collection_account_data.stars,
+    })?;
 
     Ok(())
 }
@@ -227,8 +457,14 @@ pub fn process_light_up_stars_thousand(
     let source_account_info = next_account_info(account_info_iter)?;
     let destination_account_info = next_account_info(account_info_iter)?;
 
+    // the transfer would refuse an unsigned source as well, this names the missing signature up front
+    assert_signer(source_account_info)?;
     assert_treasury_account(destination_account_info)?;
-    let lamports = sol_to_lamports(1 as f64);
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow_mut())?;
+    add_stars(&mut collection_account_data, 1000)?;
+    let lamports = star_cost(&collection_account_data, 1000)?;
     invoke(
         &transfer(
             source_account_info.key,
@@ -240,138 +476,1780 @@ pub fn process_light_up_stars_thousand(
             destination_account_info.clone(),
         ],
     )?;
+    log_stars(collection_account_info.key, 1000, collection_account_data.stars);
+    write_stars(&mut collection_account_info.data.borrow_mut(), collection_account_data.stars)?;
+    emit_event(program_id, accounts, CollectionEvent::StarsLit {
+        collection: *collection_account_info.key,
+        delta: 1000,
+        total: collection_account_data.stars,
+    })?;
+
+    Ok(())
+}
+
+pub fn process_light_up_stars_ten_thousand(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let source_account_info = next_account_info(account_info_iter)?;
+    let destination_account_info = next_account_info(account_info_iter)?;
+
+    // the transfer would refuse an unsigned source as well, this names the missing signature up front
+    assert_signer(source_account_info)?;
+    assert_treasury_account(destination_account_info)?;
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
     let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
         &collection_account_info.data.borrow_mut())?;
-    if !collection_account_data.is_initialized() {
-        return Err(CollectionError::Uninitialized.into());
-    }
-    collection_account_data.stars += 1000;
-    collection_account_data.serialize(&mut *collection_account_info.data.borrow_mut())?;
+    add_stars(&mut collection_account_data, 10000)?;
+    let lamports = star_cost(&collection_account_data, 10000)?;
+    invoke(
+        &transfer(
+            source_account_info.key,
+            destination_account_info.key,
+            lamports,
+        ), 
+        &[
+            source_account_info.clone(),
+            destination_account_info.clone(),
+        ],
+    )?;
+    log_stars(collection_account_info.key, 10000, collection_account_data.stars);
+    write_stars(&mut collection_account_info.data.borrow_mut(), collection_account_data.stars)?;
+    emit_event(program_id, accounts, CollectionEvent::StarsLit {
+        collection: *collection_account_info.key,
+        delta: 10000,
+        total: collection_account_data.stars,
+    })?;
 
     Ok(())
 }
 
-pub fn process_withdraw(
+pub fn process_light_up_stars_custom(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
+    amount: u64,
 ) -> ProgramResult {
     assert_program_id(program_id)?;
     let account_info_iter = &mut accounts.iter();
-    let treasury_manager_account_info = next_account_info(account_info_iter)?;
-    let treasury_account_info = next_account_info(account_info_iter)?;
-    let recipient_account_info = next_account_info(account_info_iter)?;
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let source_account_info = next_account_info(account_info_iter)?;
+    let destination_account_info = next_account_info(account_info_iter)?;
 
-    assert_treasury_manager(treasury_manager_account_info)?;
-    assert_treasury_account(treasury_account_info)?;
-    let lamports = treasury_account_info.lamports();
-    if lamports == 0 {
-        return Err(CollectionError::InsufficientFunds.into());
+    // the transfer would refuse an unsigned source as well, this names the missing signature up front
+    assert_signer(source_account_info)?;
+    if amount == 0 {
+        return Err(CollectionError::InvalidInstructionArguments.into());
     }
-    let recipient_starting_lamports = recipient_account_info.lamports();
-    **recipient_account_info.lamports.borrow_mut() = recipient_starting_lamports.checked_add(treasury_account_info.lamports()).unwrap();
-    **treasury_account_info.lamports.borrow_mut() = 0;
+    assert_treasury_account(destination_account_info)?;
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow_mut())?;
+    add_stars(&mut collection_account_data, amount)?;
+    let lamports = star_cost(&collection_account_data, amount)?;
+    invoke(
+        &transfer(
+            source_account_info.key,
+            destination_account_info.key,
+            lamports,
+        ), 
+        &[
+            source_account_info.clone(),
+            destination_account_info.clone(),
+        ],
+    )?;
+    log_stars(collection_account_info.key, amount, collection_account_data.stars);
+    write_stars(&mut collection_account_info.data.borrow_mut(), collection_account_data.stars)?;
+    emit_event(program_id, accounts, CollectionEvent::StarsLit {
+        collection: *collection_account_info.key,
+        delta: amount,
+        total: collection_account_data.stars,
+    })?;
+
     Ok(())
 }
 
-pub fn process_close_account(
+pub fn process_light_up_stars_multi(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    account_type: AccountType,
+    amounts: Vec<u64>,
 ) -> ProgramResult {
     assert_program_id(program_id)?;
     let account_info_iter = &mut accounts.iter();
-    let account_info = next_account_info(account_info_iter)?;
-    let recipient_account_info = next_account_info(account_info_iter)?;
-    let authority_account_info  = next_account_info(account_info_iter)?;
-    match account_type {
-        AccountType::Uninitialized => {
-            return Err(CollectionError::InvalidAccountType.into());
-        }, 
-        AccountType::CollectionAccount => {
-            msg!("close collection account: {}", account_info.key.to_string());
-            let collection_data = CollectionAccountData::try_from_slice_unchecked(&account_info.data.borrow_mut())?;
-            if collection_data.authority != *authority_account_info.key 
-                || !authority_account_info.is_signer {
-                return Err(CollectionError::NotCollectionAuthority.into());
-            }
-            let recipient_starting_lamports = recipient_account_info.lamports();
-            **recipient_account_info.lamports.borrow_mut() = recipient_starting_lamports.checked_add(account_info.lamports()).unwrap();
-            **account_info.lamports.borrow_mut() = 0;
+    let source_account_info = next_account_info(account_info_iter)?;
+    let destination_account_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
 
-            let mut account_data = account_info.data.borrow_mut();
-            account_data.fill(0);
-        },
-        AccountType::CollectionIndexAccount => {
-            CollectionIndexAccountData::try_from_slice_unchecked(&account_info.data.borrow_mut())?;
-            let recipient_starting_lamports = recipient_account_info.lamports();
-            **recipient_account_info.lamports.borrow_mut() = recipient_starting_lamports.checked_add(account_info.lamports()).unwrap();
-            **account_info.lamports.borrow_mut() = 0;
+    assert_signer(source_account_info)?;
+    if amounts.is_empty()
+        || amounts.len() > MAX_LIGHT_UP_MULTI_COLLECTIONS
+        || amounts.contains(&0)
+        // the event accounts may trail the collections
+        || account_info_iter.len() < amounts.len() {
+        return Err(CollectionError::InvalidInstructionArguments.into());
+    }
+    assert_treasury_account(destination_account_info)?;
 
-            let mut account_data = account_info.data.borrow_mut();
-            account_data.fill(0);
-        }
+    for amount in amounts {
+        let collection_account_info = next_account_info(account_info_iter)?;
+        AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+        let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+            &collection_account_info.data.borrow())?;
+        add_stars(&mut collection_account_data, amount)?;
+        let lamports = star_cost(&collection_account_data, amount)?;
+        invoke(
+            &transfer(
+                source_account_info.key,
+                destination_account_info.key,
+                lamports,
+            ),
+            &[
+                source_account_info.clone(),
+                destination_account_info.clone(),
+            ],
+        )?;
+        log_stars(collection_account_info.key, amount, collection_account_data.stars);
+        write_stars(&mut collection_account_info.data.borrow_mut(), collection_account_data.stars)?;
+        emit_event(program_id, accounts, CollectionEvent::StarsLit {
+            collection: *collection_account_info.key,
+            delta: amount,
+            total: collection_account_data.stars,
+        })?;
     }
+
     Ok(())
 }
 
-fn assert_mint_authority(
-    mint_account_info: &AccountInfo, 
-    mint_token_account: &AccountInfo, 
-    collection_auth_account_info: &AccountInfo,
+pub fn process_light_up_stars_with_token(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
 ) -> ProgramResult {
-    if *mint_account_info.owner != spl_token::id() 
-        || *mint_token_account.owner != spl_token::id() {
-        return Err(CollectionError::InvalidNFT.into());
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let source_owner_account_info = next_account_info(account_info_iter)?;
+    let source_token_account_info = next_account_info(account_info_iter)?;
+    let treasury_token_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let treasury_config_account_info = next_account_info(account_info_iter)?;
 
+    assert_signer(source_owner_account_info)?;
+    if amount == 0 {
+        return Err(CollectionError::InvalidInstructionArguments.into());
     }
-    let mint = spl_mint::unpack_unchecked(&mint_account_info.data.borrow())?;
-    let token_account = spl_account::unpack_unchecked(&mint_token_account.data.borrow())?;
-    if !mint.is_initialized() 
-        || mint.supply != 1 
-        || mint.decimals != 0 
-        || !token_account.is_initialized()
-        || token_account.mint != *mint_account_info.key
-        || token_account.amount != 1 {
-        return Err(CollectionError::InvalidNFT.into());
+    // Token-2022 is left out on purpose, see `unpack_treasury_token_account`
+    if *token_program_info.key != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
     }
+    let (star_mint, star_token_price) = get_star_token(program_id, treasury_config_account_info)?;
+    assert_treasury_token_account(treasury_token_account_info, &star_mint)?;
+    assert_star_mint(source_token_account_info, &star_mint)?;
+    // only whole stars are charged, the rest of the amount never leaves the payer
+    let delta = amount.checked_div(star_token_price).unwrap_or(0);
+    if delta == 0 {
+        return Err(CollectionError::InvalidInstructionArguments.into());
+    }
+    assert_stars_per_call(delta)?;
+    // nothing but the star count is needed, so the rest of the account is never decoded
+    let stars = checked_stars(read_stars(&collection_account_info.data.borrow())?, delta)?;
+    invoke(
+        &spl_token::instruction::transfer(
+            token_program_info.key,
+            source_token_account_info.key,
+            treasury_token_account_info.key,
+            source_owner_account_info.key,
+            &[],
+            delta * star_token_price,
+        )?,
+        &[
+            source_token_account_info.clone(),
+            treasury_token_account_info.clone(),
+            source_owner_account_info.clone(),
+            token_program_info.clone(),
+        ],
+    )?;
+    log_stars(collection_account_info.key, delta, stars);
+    write_stars(&mut collection_account_info.data.borrow_mut(), stars)?;
+    emit_event(program_id, accounts, CollectionEvent::StarsLit {
+        collection: *collection_account_info.key,
+        delta,
+        total: stars,
+    })?;
 
-    if (token_account.owner == *collection_auth_account_info.key) 
-        || (token_account.delegate.is_some() && token_account.delegate == COption::Some(*collection_auth_account_info.key) && token_account.delegated_amount == 1) {
-        return Ok(());
-    } 
-    return Err(CollectionError::NotCollectionAuthority.into());
+    Ok(())
 }
 
-fn assert_program_id(program_id: &Pubkey) -> ProgramResult {
-    if !check_id(program_id) {
-        return Err(CollectionError::InvalidProgramId.into());
+pub fn process_withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let treasury_manager_account_info = next_account_info(account_info_iter)?;
+    let treasury_account_info = next_account_info(account_info_iter)?;
+    let recipient_account_info = next_account_info(account_info_iter)?;
+    let program_data_account_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+    let treasury_config_account_info = next_account_info(account_info_iter)?;
+
+    assert_treasury_manager(program_id, treasury_manager_account_info, program_data_account_info)?;
+    assert_treasury_account(treasury_account_info)?;
+    assert_treasury_recipient(program_id, treasury_config_account_info, recipient_account_info)?;
+    // everything above the rent exempt reserve, the treasury stays alive
+    let lamports = withdrawable_balance(treasury_account_info, &Rent::get()?);
+    if lamports == 0 {
+        return Err(CollectionError::InsufficientFunds.into());
     }
+    transfer_from_treasury(program_id, treasury_account_info, recipient_account_info, lamports)?;
+    emit_event(program_id, accounts, CollectionEvent::Withdrawn { amount: lamports })?;
     Ok(())
 }
 
-fn assert_create_collection_args(args: &CreateCollectionAccountArgs) -> ProgramResult {
-    if !args.is_valid() {
+pub fn process_withdraw_amount(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let treasury_manager_account_info = next_account_info(account_info_iter)?;
+    let treasury_account_info = next_account_info(account_info_iter)?;
+    let recipient_account_info = next_account_info(account_info_iter)?;
+    let program_data_account_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+    let treasury_config_account_info = next_account_info(account_info_iter)?;
+
+    if amount == 0 {
         return Err(CollectionError::InvalidInstructionArguments.into());
     }
-    Ok(())
-}
-
-fn assert_treasury_account(treasury_account_info: &AccountInfo) -> ProgramResult {
-    let (pda, _) = get_treasury_account();
-    if *treasury_account_info.key != pda {
-        return Err(CollectionError::InvalidTreasuryAccount.into());
+    assert_treasury_manager(program_id, treasury_manager_account_info, program_data_account_info)?;
+    assert_treasury_account(treasury_account_info)?;
+    assert_treasury_recipient(program_id, treasury_config_account_info, recipient_account_info)?;
+    // the treasury must stay rent exempt after the withdrawal
+    if amount > withdrawable_balance(treasury_account_info, &Rent::get()?) {
+        return Err(CollectionError::InsufficientFunds.into());
     }
+    transfer_from_treasury(program_id, treasury_account_info, recipient_account_info, amount)?;
+    emit_event(program_id, accounts, CollectionEvent::Withdrawn { amount })?;
     Ok(())
 }
 
-fn get_treasury_manager_account() -> Pubkey {
-    Pubkey::from_str(&"Ep1P3v2rMZ2FkyPx5uuGMaTztdSdtdvaUjcahT9y3EQv".to_string()).unwrap()
-}
+pub fn process_withdraw_token(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let treasury_manager_account_info = next_account_info(account_info_iter)?;
+    let treasury_account_info = next_account_info(account_info_iter)?;
+    let treasury_token_account_info = next_account_info(account_info_iter)?;
+    let recipient_token_account_info = next_account_info(account_info_iter)?;
+    let program_data_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let treasury_config_account_info = next_account_info(account_info_iter)?;
 
-fn assert_treasury_manager(manager_account_info: &AccountInfo) -> ProgramResult {
-    if *manager_account_info.key != get_treasury_manager_account() 
-        || !manager_account_info.is_signer {
-        return Err(CollectionError::NotTreasuryManager.into());
+    if amount == 0 {
+        return Err(CollectionError::InvalidInstructionArguments.into());
     }
-    Ok(())
+    if *token_program_info.key != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    assert_treasury_manager(program_id, treasury_manager_account_info, program_data_account_info)?;
+    assert_treasury_account(treasury_account_info)?;
+    // tokens of a former star mint can still leave, so the mint is not checked here
+    let treasury_token_account = unpack_treasury_token_account(treasury_token_account_info)?;
+    let treasury_recipient = get_treasury_recipient(program_id, treasury_config_account_info)?;
+    if *recipient_token_account_info.owner != spl_token::id()
+        || spl_token::state::Account::unpack(&recipient_token_account_info.data.borrow())?.owner != treasury_recipient {
+        return Err(CollectionError::WrongRecipient.into());
+    }
+    invoke_signed(
+        &spl_token::instruction::transfer(
+            token_program_info.key,
+            treasury_token_account_info.key,
+            recipient_token_account_info.key,
+            treasury_account_info.key,
+            &[],
+            amount,
+        )?,
+        &[
+            treasury_token_account_info.clone(),
+            recipient_token_account_info.clone(),
+            treasury_account_info.clone(),
+            token_program_info.clone(),
+        ],
+        &[&[
+            COLLECTION_SEED,
+            TREASURY_SEED,
+            program_id.as_ref(),
+            &[TREASURY_BUMP],
+        ]],
+    )?;
+    emit_event(program_id, accounts, CollectionEvent::TokensWithdrawn { mint: treasury_token_account.mint, amount })?;
+    Ok(())
+}
+
+pub fn process_set_treasury_recipient(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    treasury_recipient: Pubkey,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let treasury_manager_account_info = next_account_info(account_info_iter)?;
+    let treasury_config_account_info = next_account_info(account_info_iter)?;
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let program_data_account_info = next_account_info(account_info_iter)?;
+    let rent_sysvar_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    assert_treasury_manager(program_id, treasury_manager_account_info, program_data_account_info)?;
+    let (treasury_config_account, bump_seed) = find_treasury_config_address(program_id);
+    if treasury_config_account != *treasury_config_account_info.key {
+        return Err(CollectionError::InvalidAccountArguments.into());
+    }
+    let mut treasury_config = if treasury_config_account_info.data_is_empty() {
+        let signer_seeds = &[
+            COLLECTION_SEED,
+            TREASURY_CONFIG_SEED,
+            &[bump_seed],
+        ];
+        create_or_allocate_account_raw(
+            *program_id,
+            treasury_config_account_info,
+            rent_sysvar_info,
+            system_program_info,
+            payer_account_info,
+            TreasuryConfigAccountData::LEN,
+            signer_seeds,
+        )?;
+        TreasuryConfigAccountData::new(treasury_recipient)
+    } else {
+        load_treasury_config(treasury_config_account_info, payer_account_info, system_program_info)?
+    };
+    treasury_config.treasury_recipient = treasury_recipient;
+    treasury_config.serialize(&mut *treasury_config_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::TreasuryRecipientSet { treasury_recipient })?;
+    Ok(())
+}
+
+pub fn process_set_star_token(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    star_token: Option<(Pubkey, u64)>,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let treasury_manager_account_info = next_account_info(account_info_iter)?;
+    let treasury_config_account_info = next_account_info(account_info_iter)?;
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let program_data_account_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    assert_treasury_manager(program_id, treasury_manager_account_info, program_data_account_info)?;
+    if find_treasury_config_address(program_id).0 != *treasury_config_account_info.key {
+        return Err(CollectionError::InvalidAccountArguments.into());
+    }
+    // the config only exists once `SetTreasuryRecipient` bound a recipient
+    AccountType::check_account_type(&treasury_config_account_info.data.borrow(), AccountType::TreasuryConfig)?;
+    let (star_mint, star_token_price) = match star_token {
+        Some((_, 0)) => return Err(CollectionError::InvalidInstructionArguments.into()),
+        Some((star_mint, star_token_price)) => (Some(star_mint), star_token_price),
+        None => (None, 0),
+    };
+
+    let mut treasury_config = load_treasury_config(treasury_config_account_info, payer_account_info, system_program_info)?;
+    treasury_config.star_mint = star_mint;
+    treasury_config.star_token_price = star_token_price;
+    treasury_config.serialize(&mut *treasury_config_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::StarTokenSet { star_mint, star_token_price })?;
+    Ok(())
+}
+
+/// Decodes an existing treasury config, growing those written before the star token was
+/// stored so the whole config fits again.
+fn load_treasury_config<'a>(
+    treasury_config_account_info: &AccountInfo<'a>,
+    payer_account_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+) -> Result<TreasuryConfigAccountData, ProgramError> {
+    let treasury_config = TreasuryConfigAccountData::try_from_slice_unchecked(&treasury_config_account_info.data.borrow())?;
+    if treasury_config_account_info.data_len() < TreasuryConfigAccountData::LEN {
+        resize_account(treasury_config_account_info, payer_account_info, system_program_info, TreasuryConfigAccountData::LEN)?;
+    }
+    Ok(treasury_config)
+}
+
+pub fn process_update_collection_metadata(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: &UpdateCollectionMetadataArgs,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow_mut())?;
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+    if collection_account_data.frozen {
+        return Err(CollectionError::CollectionFrozen.into());
+    }
+
+    let metadata = args.apply(&collection_account_data.metadata());
+    assert_create_collection_args(&metadata)?;
+    collection_account_data.set_metadata(&metadata);
+
+    // with a funding account the collection is sized to fit exactly, without one it is
+    // written into the space it has. The appended event authority is never a funding account.
+    let payer_account_info = account_info_iter
+        .next()
+        .filter(|info| *info.key != find_event_authority_address(program_id).0);
+    if let Some(payer_account_info) = payer_account_info {
+        let system_program_info = next_account_info(account_info_iter)?;
+        assert_signer(payer_account_info)?;
+        resize_account(
+            collection_account_info,
+            payer_account_info,
+            system_program_info,
+            collection_account_data.try_to_vec()?.len(),
+        )?;
+    }
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::MetadataUpdated { collection: *collection_account_info.key })?;
+    Ok(())
+}
+
+pub fn process_transfer_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_authority: Pubkey,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    if new_authority == Pubkey::default() {
+        return Err(CollectionError::InvalidInstructionArguments.into());
+    }
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow_mut())?;
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+    if collection_account_data.frozen {
+        return Err(CollectionError::CollectionFrozen.into());
+    }
+
+    collection_account_data.authority = new_authority;
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::AuthorityTransferred {
+        collection: *collection_account_info.key,
+        new_authority,
+    })?;
+    Ok(())
+}
+
+pub fn process_freeze_collection(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow_mut())?;
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+
+    collection_account_data.version = COLLECTION_ACCOUNT_VERSION;
+    collection_account_data.frozen = true;
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::CollectionFrozen { collection: *collection_account_info.key })?;
+    Ok(())
+}
+
+pub fn process_set_tags(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    tags: Vec<String>,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow_mut())?;
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+    if collection_account_data.frozen {
+        return Err(CollectionError::CollectionFrozen.into());
+    }
+
+    let mut metadata = collection_account_data.metadata();
+    metadata.tags = Some(tags);
+    if !metadata.check_tags() {
+        return Err(CollectionError::InvalidMetadata.into());
+    }
+    collection_account_data.set_metadata(&metadata);
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::MetadataUpdated { collection: *collection_account_info.key })?;
+    Ok(())
+}
+
+pub fn process_set_description(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    description: String,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow_mut())?;
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+    if collection_account_data.frozen {
+        return Err(CollectionError::CollectionFrozen.into());
+    }
+
+    let mut metadata = collection_account_data.metadata();
+    metadata.description = description;
+    assert_create_collection_args(&metadata)?;
+    collection_account_data.set_metadata(&metadata);
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::MetadataUpdated { collection: *collection_account_info.key })?;
+    Ok(())
+}
+
+pub fn process_set_banner(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    banner: Option<String>,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow_mut())?;
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+    if collection_account_data.frozen {
+        return Err(CollectionError::CollectionFrozen.into());
+    }
+
+    let mut metadata = collection_account_data.metadata();
+    metadata.banner = banner;
+    assert_create_collection_args(&metadata)?;
+    collection_account_data.set_metadata(&metadata);
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::MetadataUpdated { collection: *collection_account_info.key })?;
+    Ok(())
+}
+
+pub fn process_set_metadata_uri(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    metadata_uri: Option<String>,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow_mut())?;
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+    if collection_account_data.frozen {
+        return Err(CollectionError::CollectionFrozen.into());
+    }
+
+    let mut metadata = collection_account_data.metadata();
+    metadata.metadata_uri = metadata_uri;
+    assert_create_collection_args(&metadata)?;
+    collection_account_data.version = COLLECTION_ACCOUNT_VERSION;
+    collection_account_data.set_metadata(&metadata);
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::MetadataUpdated { collection: *collection_account_info.key })?;
+    Ok(())
+}
+
+pub fn process_set_category(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    category: Option<CollectionCategory>,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow_mut())?;
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+    if collection_account_data.frozen {
+        return Err(CollectionError::CollectionFrozen.into());
+    }
+
+    collection_account_data.version = COLLECTION_ACCOUNT_VERSION;
+    collection_account_data.category = category;
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::MetadataUpdated { collection: *collection_account_info.key })?;
+    Ok(())
+}
+
+pub fn process_set_star_price(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    star_price: u64,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    if star_price == 0 {
+        return Err(CollectionError::InvalidInstructionArguments.into());
+    }
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow_mut())?;
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+
+    collection_account_data.version = COLLECTION_ACCOUNT_VERSION;
+    collection_account_data.star_price = star_price;
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::StarPriceSet {
+        collection: *collection_account_info.key,
+        star_price,
+    })?;
+    Ok(())
+}
+
+pub fn process_set_verified(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    verified: bool,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let curator_account_info = next_account_info(account_info_iter)?;
+    let program_data_account_info = next_account_info(account_info_iter)?;
+
+    assert_upgrade_authority(program_id, curator_account_info, program_data_account_info, CollectionError::NotCurator)?;
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow_mut())?;
+
+    collection_account_data.version = COLLECTION_ACCOUNT_VERSION;
+    collection_account_data.verified = verified;
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::VerifiedSet {
+        collection: *collection_account_info.key,
+        verified,
+    })?;
+    Ok(())
+}
+
+pub fn process_validate_metadata(
+    program_id: &Pubkey,
+    args: &CreateCollectionAccountArgs,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    assert_create_collection_args(args)
+}
+
+pub fn process_add_tag(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    tag: String,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow_mut())?;
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+    if collection_account_data.frozen {
+        return Err(CollectionError::CollectionFrozen.into());
+    }
+
+    let mut metadata = collection_account_data.metadata();
+    metadata.tags.get_or_insert_with(Vec::new).push(tag);
+    if !metadata.check_tags() {
+        return Err(CollectionError::InvalidMetadata.into());
+    }
+    collection_account_data.set_metadata(&metadata);
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::MetadataUpdated { collection: *collection_account_info.key })?;
+    Ok(())
+}
+
+pub fn process_remove_tag(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    tag: String,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow_mut())?;
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+    if collection_account_data.frozen {
+        return Err(CollectionError::CollectionFrozen.into());
+    }
+
+    let mut metadata = collection_account_data.metadata();
+    let tags = metadata.tags.as_mut().ok_or(CollectionError::TagNotFound)?;
+    let normalized_tag = tag.to_lowercase();
+    let position = tags
+        .iter()
+        .position(|current| current.to_lowercase() == normalized_tag)
+        .ok_or(CollectionError::TagNotFound)?;
+    tags.remove(position);
+    collection_account_data.set_metadata(&metadata);
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::MetadataUpdated { collection: *collection_account_info.key })?;
+    Ok(())
+}
+
+pub fn process_add_to_allowlist(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint: Pubkey,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow())?;
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+    let allowlist_account_info = next_account_info(account_info_iter)?;
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let rent_sysvar_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    let (allowlist_account, bump_seed) = find_allowlist_address(program_id, collection_account_info.key);
+    if allowlist_account != *allowlist_account_info.key {
+        return Err(CollectionError::AllowlistAccountMismatch.into());
+    }
+    let mut allowlist_data = if allowlist_account_info.owner == program_id && !allowlist_account_info.data_is_empty() {
+        load_allowlist(collection_account_info, allowlist_account_info)?
+    } else {
+        let signer_seeds = &[
+            COLLECTION_SEED,
+            ALLOWLIST_SEED,
+            collection_account_info.key.as_ref(),
+            &[bump_seed],
+        ];
+        create_or_allocate_account_raw(
+            *program_id,
+            allowlist_account_info,
+            rent_sysvar_info,
+            system_program_info,
+            payer_account_info,
+            AllowlistAccountData::LEN,
+            signer_seeds,
+        )?;
+        collection_account_data.version = COLLECTION_ACCOUNT_VERSION;
+        collection_account_data.has_allowlist = true;
+        collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+        AllowlistAccountData::new(*collection_account_info.key)
+    };
+    if allowlist_data.is_allowed(&mint) || allowlist_data.mints.len() >= MAX_ALLOWLIST_LENGTH {
+        return Err(CollectionError::InvalidInstructionArguments.into());
+    }
+    allowlist_data.mints.push(mint);
+    allowlist_data.serialize(&mut *allowlist_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::AllowlistUpdated {
+        collection: *collection_account_info.key,
+        mint,
+        allowed: true,
+    })?;
+    Ok(())
+}
+
+pub fn process_remove_from_allowlist(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint: Pubkey,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow())?;
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+    let allowlist_account_info = next_account_info(account_info_iter)?;
+    if find_allowlist_address(program_id, collection_account_info.key).0 != *allowlist_account_info.key
+        || allowlist_account_info.owner != program_id {
+        return Err(CollectionError::AllowlistAccountMismatch.into());
+    }
+
+    let mut allowlist_data = load_allowlist(collection_account_info, allowlist_account_info)?;
+    let position = allowlist_data.mints
+        .iter()
+        .position(|allowed| *allowed == mint)
+        .ok_or(CollectionError::MintNotAllowed)?;
+    allowlist_data.mints.remove(position);
+    allowlist_data.serialize(&mut *allowlist_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::AllowlistUpdated {
+        collection: *collection_account_info.key,
+        mint,
+        allowed: false,
+    })?;
+    Ok(())
+}
+
+pub fn process_adjust_stars(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    delta: i64,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow())?;
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+
+    // a correction may take away more stars than were lit, the count then floors at zero
+    let stars = if delta >= 0 {
+        checked_stars(collection_account_data.stars, delta as u64)?
+    } else {
+        collection_account_data.stars.saturating_sub(delta.unsigned_abs())
+    };
+    msg!("Adjusted stars of {} by {}, from {} to {}", collection_account_info.key, delta, collection_account_data.stars, stars);
+    write_stars(&mut collection_account_info.data.borrow_mut(), stars)?;
+    emit_event(program_id, accounts, CollectionEvent::StarsAdjusted {
+        collection: *collection_account_info.key,
+        delta,
+        total: stars,
+    })?;
+    Ok(())
+}
+
+pub fn process_migrate_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow())?;
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    if collection_account_data.version == COLLECTION_ACCOUNT_VERSION
+        && collection_account_info.data_len() >= CollectionAccountData::LEN {
+        msg!("Collection account is already at version {}", COLLECTION_ACCOUNT_VERSION);
+        return Ok(());
+    }
+
+    // decoding already filled the fields the account predates with their defaults, accounts
+    // shrunk by `ClearOptionalMetadata` only get their room back
+    if collection_account_info.data_len() < CollectionAccountData::LEN {
+        resize_account(collection_account_info, payer_account_info, system_program_info, CollectionAccountData::LEN)?;
+    }
+    msg!("Migrated collection account from version {} to {}", collection_account_data.version, COLLECTION_ACCOUNT_VERSION);
+    collection_account_data.version = COLLECTION_ACCOUNT_VERSION;
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::MetadataUpdated { collection: *collection_account_info.key })?;
+    Ok(())
+}
+
+pub fn process_clear_optional_metadata(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow())?;
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+    let recipient_account_info = next_account_info(account_info_iter)?;
+    if recipient_account_info.key == collection_account_info.key {
+        return Err(CollectionError::InvalidAccountArguments.into());
+    }
+    if collection_account_data.frozen {
+        return Err(CollectionError::CollectionFrozen.into());
+    }
+
+    let mut metadata = collection_account_data.metadata();
+    metadata.header_image = None;
+    metadata.short_description = None;
+    metadata.banner = None;
+    metadata.tags = None;
+    collection_account_data.set_metadata(&metadata);
+
+    // the cleared fields only need their option tags, older and smaller accounts keep their size
+    if collection_account_info.data_len() > CollectionAccountData::CLEARED_LEN {
+        shrink_account(collection_account_info, recipient_account_info, CollectionAccountData::CLEARED_LEN)?;
+    }
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::MetadataUpdated { collection: *collection_account_info.key })?;
+    Ok(())
+}
+
+pub fn process_set_cooldown(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    cooldown_slots: u64,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow())?;
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+    let cooldown_account_info = next_account_info(account_info_iter)?;
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let rent_sysvar_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    let (cooldown_account, bump_seed) = find_cooldown_address(program_id, collection_account_info.key);
+    if cooldown_account != *cooldown_account_info.key {
+        return Err(CollectionError::CooldownAccountMismatch.into());
+    }
+    if cooldown_account_info.owner == program_id && !cooldown_account_info.data_is_empty() {
+        load_cooldown(collection_account_info, cooldown_account_info)?;
+    } else if cooldown_slots > 0 {
+        let signer_seeds = &[
+            COLLECTION_SEED,
+            COOLDOWN_SEED,
+            collection_account_info.key.as_ref(),
+            &[bump_seed],
+        ];
+        create_or_allocate_account_raw(
+            *program_id,
+            cooldown_account_info,
+            rent_sysvar_info,
+            system_program_info,
+            payer_account_info,
+            CooldownAccountData::LEN,
+            signer_seeds,
+        )?;
+        CooldownAccountData::new(*collection_account_info.key)
+            .serialize(&mut *cooldown_account_info.data.borrow_mut())?;
+    }
+
+    collection_account_data.version = COLLECTION_ACCOUNT_VERSION;
+    collection_account_data.cooldown_slots = cooldown_slots;
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::CooldownSet {
+        collection: *collection_account_info.key,
+        cooldown_slots,
+    })?;
+    Ok(())
+}
+
+pub fn process_set_parent(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    parent: Option<Pubkey>,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow())?;
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+    if collection_account_data.frozen {
+        return Err(CollectionError::CollectionFrozen.into());
+    }
+
+    if let Some(parent) = parent {
+        let parent_collection_account_info = next_account_info(account_info_iter)?;
+        let parent_auth_account_info = next_account_info(account_info_iter)?;
+        if parent == *collection_account_info.key {
+            return Err(CollectionError::InvalidParent.into());
+        }
+        if parent != *parent_collection_account_info.key {
+            return Err(CollectionError::InvalidAccountArguments.into());
+        }
+        // the parent is only read, so nothing else guarantees this program wrote it
+        if parent_collection_account_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        AccountType::check_account_type(&parent_collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+        let parent_collection_data = CollectionAccountData::try_from_slice_unchecked(
+            &parent_collection_account_info.data.borrow())?;
+        assert_collection_authority(&parent_collection_data, parent_auth_account_info)?;
+        assert_secondary_authority(&parent_collection_data, account_info_iter)?;
+        // only a direct cycle is caught, walking further up would need every ancestor account
+        if parent_collection_data.parent == Some(*collection_account_info.key) {
+            return Err(CollectionError::InvalidParent.into());
+        }
+    }
+
+    collection_account_data.version = COLLECTION_ACCOUNT_VERSION;
+    collection_account_data.parent = parent;
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::ParentSet { collection: *collection_account_info.key, parent })?;
+    Ok(())
+}
+
+pub fn process_set_collection_mint(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    collection_mint: Option<Pubkey>,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let collection_account_info = next_account_info(account_info_iter)?;
+    let collection_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut collection_account_data = CollectionAccountData::try_from_slice_unchecked(
+        &collection_account_info.data.borrow())?;
+    assert_collection_authority(&collection_account_data, collection_auth_account_info)?;
+    assert_secondary_authority(&collection_account_data, account_info_iter)?;
+    if collection_account_data.frozen {
+        return Err(CollectionError::CollectionFrozen.into());
+    }
+
+    if let Some(collection_mint) = collection_mint {
+        let mint_account_info = next_account_info(account_info_iter)?;
+        if collection_mint != *mint_account_info.key {
+            return Err(CollectionError::InvalidAccountArguments.into());
+        }
+        assert_nft_mint(mint_account_info)?;
+    }
+
+    collection_account_data.version = COLLECTION_ACCOUNT_VERSION;
+    collection_account_data.collection_mint = collection_mint;
+    collection_account_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::CollectionMintSet {
+        collection: *collection_account_info.key,
+        collection_mint,
+    })?;
+    Ok(())
+}
+
+pub fn process_move_token(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let source_collection_account_info = next_account_info(account_info_iter)?;
+    let source_auth_account_info = next_account_info(account_info_iter)?;
+
+    AccountType::check_account_type(&source_collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut source_collection_data = CollectionAccountData::try_from_slice_unchecked(
+        &source_collection_account_info.data.borrow())?;
+    assert_collection_authority(&source_collection_data, source_auth_account_info)?;
+    assert_secondary_authority(&source_collection_data, account_info_iter)?;
+    let destination_collection_account_info = next_account_info(account_info_iter)?;
+    let destination_auth_account_info = next_account_info(account_info_iter)?;
+    if source_collection_account_info.key == destination_collection_account_info.key {
+        return Err(CollectionError::InvalidInstructionArguments.into());
+    }
+    AccountType::check_account_type(&destination_collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+    let mut destination_collection_data = CollectionAccountData::try_from_slice_unchecked(
+        &destination_collection_account_info.data.borrow())?;
+    assert_collection_authority(&destination_collection_data, destination_auth_account_info)?;
+    assert_secondary_authority(&destination_collection_data, account_info_iter)?;
+    let mint_account_info = next_account_info(account_info_iter)?;
+    let index_account_info = next_account_info(account_info_iter)?;
+    assert_mint_allowed(
+        program_id,
+        accounts,
+        destination_collection_account_info,
+        &destination_collection_data,
+        mint_account_info.key,
+    )?;
+    if destination_collection_data.collection_mint == Some(*mint_account_info.key) {
+        return Err(CollectionError::CannotIncludeCollectionMint.into());
+    }
+
+    let mut index_account_data = load_index_account(
+        program_id,
+        source_collection_account_info,
+        mint_account_info,
+        index_account_info,
+    )?;
+    // the index address only depends on the mint, so the account is rewritten in place
+    if index_account_info.data_len() < CollectionIndexAccountData::LEN {
+        msg!("legacy index accounts must be removed and included again");
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    index_account_data.collection = *destination_collection_account_info.key;
+    index_account_data.index = destination_collection_data.index_seq;
+    index_account_data.serialize(&mut *index_account_info.data.borrow_mut())?;
+
+    source_collection_data.supply = source_collection_data.supply.saturating_sub(1);
+    destination_collection_data.supply = destination_collection_data.supply
+        .checked_add(1)
+        .ok_or(CollectionError::Overflow)?;
+    destination_collection_data.index_seq = destination_collection_data.index_seq
+        .checked_add(1)
+        .ok_or(CollectionError::Overflow)?;
+    source_collection_data.write_to(&mut source_collection_account_info.data.borrow_mut())?;
+    destination_collection_data.write_to(&mut destination_collection_account_info.data.borrow_mut())?;
+    emit_event(program_id, accounts, CollectionEvent::TokenRemoved {
+        collection: *source_collection_account_info.key,
+        mint: *mint_account_info.key,
+    })?;
+    emit_event(program_id, accounts, CollectionEvent::TokenIncluded {
+        collection: *destination_collection_account_info.key,
+        mint: *mint_account_info.key,
+        index: index_account_data.index,
+    })?;
+    Ok(())
+}
+
+/// Rejects events not emitted by the program itself, the event authority only signs
+/// through `emit_event`.
+pub fn process_emit_event(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let event_authority_info = next_account_info(account_info_iter)?;
+
+    if *event_authority_info.key != find_event_authority_address(program_id).0
+        || !event_authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+pub fn process_close_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    account_type: AccountType,
+) -> ProgramResult {
+    assert_program_id(program_id)?;
+    let account_info_iter = &mut accounts.iter();
+    let account_info = next_account_info(account_info_iter)?;
+    let recipient_account_info = next_account_info(account_info_iter)?;
+    let authority_account_info  = next_account_info(account_info_iter)?;
+    // the type comes from the caller, so it has to match what the account itself stores
+    if *account_info.key == get_treasury_account().0
+        || account_info.owner != program_id
+        || account_info.data.borrow().first() != Some(&(account_type as u8)) {
+        return Err(CollectionError::AccountTypeMismatch.into());
+    }
+    match account_type {
+        // the treasury config outlives any single withdrawal recipient, it is only ever rebound
+        AccountType::Uninitialized | AccountType::TreasuryConfig => {
+            return Err(CollectionError::InvalidAccountType.into());
+        }, 
+        AccountType::CollectionAccount => {
+            msg!("close collection account: {}", account_info.key.to_string());
+            let collection_data = CollectionAccountData::try_from_slice_unchecked(&account_info.data.borrow_mut())?;
+            assert_collection_authority(&collection_data, authority_account_info)?;
+            assert_secondary_authority(&collection_data, account_info_iter)?;
+            // index, allowlist and cooldown accounts point back at the collection, close them first
+            if collection_data.supply > 0 || collection_data.has_allowlist || collection_data.cooldown_slots > 0 {
+                return Err(CollectionError::CollectionNotEmpty.into());
+            }
+            drain_lamports(account_info, recipient_account_info)?;
+
+            let mut account_data = account_info.data.borrow_mut();
+            account_data.fill(0);
+        },
+        AccountType::CollectionIndexAccount => {
+            let collection_account_info = next_account_info(account_info_iter)?;
+            let index_data = CollectionIndexAccountData::try_from_slice_unchecked(&account_info.data.borrow_mut())?;
+            let index_account = match index_data.bump {
+                0 => find_index_address(program_id, &index_data.mint).0,
+                bump => create_index_address(program_id, &index_data.mint, bump)
+                    .map_err(|_| CollectionError::AccountTypeMismatch)?,
+            };
+            if index_account != *account_info.key {
+                return Err(CollectionError::AccountTypeMismatch.into());
+            }
+            if index_data.collection != *collection_account_info.key {
+                return Err(CollectionError::TokenNotIncluded.into());
+            }
+            AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+            let mut collection_data = CollectionAccountData::try_from_slice_unchecked(
+                &collection_account_info.data.borrow_mut())?;
+            assert_collection_authority(&collection_data, authority_account_info)?;
+            assert_secondary_authority(&collection_data, account_info_iter)?;
+            collection_data.supply = collection_data.supply.saturating_sub(1);
+            collection_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+
+            drain_lamports(account_info, recipient_account_info)?;
+
+            let mut account_data = account_info.data.borrow_mut();
+            account_data.fill(0);
+        },
+        AccountType::Allowlist => {
+            let collection_account_info = next_account_info(account_info_iter)?;
+            let allowlist_data = AllowlistAccountData::try_from_slice_unchecked(&account_info.data.borrow())?;
+            if find_allowlist_address(program_id, &allowlist_data.collection).0 != *account_info.key {
+                return Err(CollectionError::AccountTypeMismatch.into());
+            }
+            if allowlist_data.collection != *collection_account_info.key {
+                return Err(CollectionError::AllowlistAccountMismatch.into());
+            }
+            AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+            let mut collection_data = CollectionAccountData::try_from_slice_unchecked(
+                &collection_account_info.data.borrow())?;
+            assert_collection_authority(&collection_data, authority_account_info)?;
+            assert_secondary_authority(&collection_data, account_info_iter)?;
+            // without its allowlist the collection accepts any mint again
+            collection_data.has_allowlist = false;
+            collection_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+
+            drain_lamports(account_info, recipient_account_info)?;
+
+            let mut account_data = account_info.data.borrow_mut();
+            account_data.fill(0);
+        }
+        AccountType::Cooldown => {
+            let collection_account_info = next_account_info(account_info_iter)?;
+            let cooldown_data = CooldownAccountData::try_from_slice_unchecked(&account_info.data.borrow())?;
+            if find_cooldown_address(program_id, &cooldown_data.collection).0 != *account_info.key {
+                return Err(CollectionError::AccountTypeMismatch.into());
+            }
+            if cooldown_data.collection != *collection_account_info.key {
+                return Err(CollectionError::CooldownAccountMismatch.into());
+            }
+            AccountType::check_account_type(&collection_account_info.data.borrow(), AccountType::CollectionAccount)?;
+            let mut collection_data = CollectionAccountData::try_from_slice_unchecked(
+                &collection_account_info.data.borrow())?;
+            assert_collection_authority(&collection_data, authority_account_info)?;
+            assert_secondary_authority(&collection_data, account_info_iter)?;
+            // free stars need no signer without the cooldown account
+            collection_data.cooldown_slots = 0;
+            collection_data.write_to(&mut collection_account_info.data.borrow_mut())?;
+
+            drain_lamports(account_info, recipient_account_info)?;
+
+            let mut account_data = account_info.data.borrow_mut();
+            account_data.fill(0);
+        }
+    }
+    emit_event(program_id, accounts, CollectionEvent::AccountClosed { account: *account_info.key })?;
+    Ok(())
+}
+
+/// Decodes the cooldown held by `cooldown_account_info`, which must belong to the collection.
+fn load_cooldown(
+    collection_account_info: &AccountInfo,
+    cooldown_account_info: &AccountInfo,
+) -> Result<CooldownAccountData, ProgramError> {
+    let cooldown_data = CooldownAccountData::try_from_slice_unchecked(&cooldown_account_info.data.borrow())
+        .map_err(|_| CollectionError::CooldownAccountMismatch)?;
+    if !cooldown_data.is_initialized() || cooldown_data.collection != *collection_account_info.key {
+        return Err(CollectionError::CooldownAccountMismatch.into());
+    }
+    Ok(cooldown_data)
+}
+
+/// Decodes the allowlist held by `allowlist_account_info`, which must belong to the collection.
+fn load_allowlist(
+    collection_account_info: &AccountInfo,
+    allowlist_account_info: &AccountInfo,
+) -> Result<AllowlistAccountData, ProgramError> {
+    let allowlist_data = AllowlistAccountData::try_from_slice_unchecked(&allowlist_account_info.data.borrow())
+        .map_err(|_| CollectionError::AllowlistAccountMismatch)?;
+    if !allowlist_data.is_initialized() || allowlist_data.collection != *collection_account_info.key {
+        return Err(CollectionError::AllowlistAccountMismatch.into());
+    }
+    Ok(allowlist_data)
+}
+
+/// Requires `mint` on the allowlist of a collection that has one. The allowlist is looked up
+/// by its address among `accounts`, so callers may pass it anywhere after the fixed accounts.
+fn assert_mint_allowed(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    collection_account_info: &AccountInfo,
+    collection_account_data: &CollectionAccountData,
+    mint: &Pubkey,
+) -> ProgramResult {
+    if !collection_account_data.has_allowlist {
+        return Ok(());
+    }
+    let (allowlist_account, _) = find_allowlist_address(program_id, collection_account_info.key);
+    let allowlist_account_info = accounts
+        .iter()
+        .find(|info| *info.key == allowlist_account && info.owner == program_id)
+        .ok_or(CollectionError::MintNotAllowed)?;
+    if !load_allowlist(collection_account_info, allowlist_account_info)?.is_allowed(mint) {
+        return Err(CollectionError::MintNotAllowed.into());
+    }
+    Ok(())
+}
+
+/// Loads the index account of `mint_account_info`, checking it sits at the index address
+/// and belongs to the collection.
+fn load_index_account(
+    program_id: &Pubkey,
+    collection_account_info: &AccountInfo,
+    mint_account_info: &AccountInfo,
+    index_account_info: &AccountInfo,
+) -> Result<CollectionIndexAccountData, ProgramError> {
+    let index_account_data = if index_account_info.owner == program_id && !index_account_info.data_is_empty() {
+        CollectionIndexAccountData::try_from_slice_unchecked(&index_account_info.data.borrow()).ok()
+    } else {
+        None
+    };
+    // index accounts created before the bump was stored fall back to the bump search
+    let index_account = match index_account_data.as_ref().filter(|data| data.bump != 0) {
+        Some(data) => create_index_address(program_id, mint_account_info.key, data.bump)
+            .map_err(|_| CollectionError::CollectionIndexAccountMismatch)?,
+        None => find_index_address(program_id, mint_account_info.key).0,
+    };
+    if index_account != *index_account_info.key {
+        return Err(CollectionError::CollectionIndexAccountMismatch.into());
+    }
+    let index_account_data = index_account_data.ok_or(CollectionError::TokenNotIncluded)?;
+    if !index_account_data.is_initialized()
+        || index_account_data.collection != *collection_account_info.key
+        || index_account_data.mint != *mint_account_info.key {
+        return Err(CollectionError::TokenNotIncluded.into());
+    }
+    Ok(index_account_data)
+}
+
+/// Sends `lamports` from the treasury to `recipient_account_info`. The treasury is a system
+/// account, so only the system program can debit it.
+fn transfer_from_treasury<'a>(
+    program_id: &Pubkey,
+    treasury_account_info: &AccountInfo<'a>,
+    recipient_account_info: &AccountInfo<'a>,
+    lamports: u64,
+) -> ProgramResult {
+    invoke_signed(
+        &transfer(
+            treasury_account_info.key,
+            recipient_account_info.key,
+            lamports,
+        ),
+        &[
+            treasury_account_info.clone(),
+            recipient_account_info.clone(),
+        ],
+        &[&[
+            COLLECTION_SEED,
+            TREASURY_SEED,
+            program_id.as_ref(),
+            &[TREASURY_BUMP],
+        ]],
+    )
+}
+
+/// Reallocates `account_info` to `len` bytes. Growing tops its rent up from `payer_account_info`,
+/// by at most `MAX_PERMITTED_DATA_INCREASE` bytes as one instruction allows, shrinking refunds
+/// the rent it no longer needs to `payer_account_info`.
+fn resize_account<'a>(
+    account_info: &AccountInfo<'a>,
+    payer_account_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    len: usize,
+) -> ProgramResult {
+    if payer_account_info.key == account_info.key {
+        return Err(CollectionError::InvalidAccountArguments.into());
+    }
+    let data_len = account_info.data_len();
+    if len > data_len {
+        if len - data_len > MAX_PERMITTED_DATA_INCREASE {
+            return Err(ProgramError::InvalidRealloc);
+        }
+        let required_lamports = Rent::get()?.minimum_balance(len).saturating_sub(account_info.lamports());
+        if required_lamports > 0 {
+            invoke(
+                &transfer(payer_account_info.key, account_info.key, required_lamports),
+                &[
+                    payer_account_info.clone(),
+                    account_info.clone(),
+                    system_program_info.clone(),
+                ],
+            )?;
+        }
+        account_info.realloc(len, false)?;
+    } else if len < data_len {
+        shrink_account(account_info, payer_account_info, len)?;
+    }
+    Ok(())
+}
+
+/// Reallocates `account_info` down to `len` bytes, refunding the rent it no longer needs to
+/// `recipient_account_info`.
+fn shrink_account(account_info: &AccountInfo, recipient_account_info: &AccountInfo, len: usize) -> ProgramResult {
+    account_info.realloc(len, false)?;
+    let refund = account_info.lamports().saturating_sub(Rent::get()?.minimum_balance(len));
+    let recipient_lamports = recipient_account_info.lamports()
+        .checked_add(refund)
+        .ok_or(CollectionError::Overflow)?;
+    **account_info.lamports.borrow_mut() -= refund;
+    **recipient_account_info.lamports.borrow_mut() = recipient_lamports;
+    Ok(())
+}
+
+/// Moves every lamport of `source` to `destination`, failing instead of wrapping
+/// if the destination balance would overflow.
+fn drain_lamports(source: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+    let lamports = destination.lamports()
+        .checked_add(source.lamports())
+        .ok_or(CollectionError::Overflow)?;
+    **destination.lamports.borrow_mut() = lamports;
+    **source.lamports.borrow_mut() = 0;
+    Ok(())
+}
+
+/// Counts a free star against the `MAX_FREE_STARS_PER_SLOT` of `slot`. Accounts older than
+/// the counters are upgraded when `space` has room for them and have to be migrated otherwise.
+fn throttle_free_stars(collection_account_data: &mut CollectionAccountData, space: usize, slot: u64) -> ProgramResult {
+    if collection_account_data.version < COLLECTION_ACCOUNT_VERSION {
+        let version = collection_account_data.version;
+        collection_account_data.version = COLLECTION_ACCOUNT_VERSION;
+        if collection_account_data.try_to_vec()?.len() > space {
+            collection_account_data.version = version;
+            return Err(CollectionError::MigrationRequired.into());
+        }
+    }
+    collection_account_data.stars_this_slot = count_free_star(
+        collection_account_data.last_star_slot,
+        collection_account_data.stars_this_slot,
+        slot,
+    )?;
+    collection_account_data.last_star_slot = slot;
+    Ok(())
+}
+
+/// Free stars lit during `slot` once this one is counted, the count restarts whenever
+/// `last_star_slot` is an earlier slot.
+fn count_free_star(last_star_slot: u64, stars_this_slot: u32, slot: u64) -> Result<u32, ProgramError> {
+    let stars_this_slot = if last_star_slot == slot { stars_this_slot } else { 0 };
+    if stars_this_slot >= MAX_FREE_STARS_PER_SLOT {
+        return Err(CollectionError::RateLimited.into());
+    }
+    Ok(stars_this_slot + 1)
+}
+
+/// Lamports charged for `amount` stars at the collection's star price.
+fn star_cost(collection_account_data: &CollectionAccountData, amount: u64) -> Result<u64, ProgramError> {
+    stars_cost(amount, collection_account_data.star_price).ok_or_else(|| CollectionError::Overflow.into())
+}
+
+/// Adds `amount` stars to the collection, refusing more than `MAX_STARS_PER_CALL` at once
+/// and totals above `MAX_STARS`.
+fn add_stars(collection_account_data: &mut CollectionAccountData, amount: u64) -> ProgramResult {
+    assert_stars_per_call(amount)?;
+    collection_account_data.stars = checked_stars(collection_account_data.stars, amount)?;
+    Ok(())
+}
+
+/// `stars` plus `amount`, refusing totals above `MAX_STARS`.
+fn checked_stars(stars: u64, amount: u64) -> Result<u64, ProgramError> {
+    let stars = stars
+        .checked_add(amount)
+        .filter(|stars| *stars <= MAX_STARS)
+        .ok_or(CollectionError::StarCeilingReached)?;
+    Ok(stars)
+}
+
+fn assert_stars_per_call(amount: u64) -> ProgramResult {
+    if amount > MAX_STARS_PER_CALL {
+        return Err(CollectionError::AmountTooLarge.into());
+    }
+    Ok(())
+}
+
+/// Logs `COLLECTION_STARS <collection> <delta> <total>` so indexers can follow star counts
+/// without diffing account state. The format is stable, new fields will only be appended.
+fn log_stars(collection: &Pubkey, delta: u64, total: u64) {
+    msg!("{} {} {} {}", STARS_LOG_PREFIX, collection, delta, total);
+}
+
+fn is_token_program(program_id: &Pubkey) -> bool {
+    *program_id == spl_token::id() || *program_id == spl_token_2022::id()
+}
+
+fn assert_mint_authority(
+    mint_account_info: &AccountInfo, 
+    mint_token_account: &AccountInfo, 
+    collection_auth_account_info: &AccountInfo,
+) -> ProgramResult {
+    // both token programs share the base mint and account layouts, extensions follow them
+    if !is_token_program(mint_account_info.owner)
+        || mint_token_account.owner != mint_account_info.owner {
+        return Err(CollectionError::InvalidNFT.into());
+    }
+    let mint = StateWithExtensions::<spl_mint>::unpack(&mint_account_info.data.borrow())
+        .map_err(|_| CollectionError::InvalidNFT)?
+        .base;
+    let token_account = StateWithExtensions::<spl_account>::unpack(&mint_token_account.data.borrow())
+        .map_err(|_| CollectionError::InvalidNFT)?
+        .base;
+    if mint.supply != 1 || mint.decimals != 0 {
+        return Err(CollectionError::NotAnNft.into());
+    }
+    if !token_account.is_initialized()
+        || token_account.mint != *mint_account_info.key
+        || token_account.amount != 1 {
+        return Err(CollectionError::InvalidNFT.into());
+    }
+
+    if (token_account.owner == *collection_auth_account_info.key) 
+        || (token_account.delegate.is_some() && token_account.delegate == COption::Some(*collection_auth_account_info.key) && token_account.delegated_amount == 1) {
+        return Ok(());
+    } 
+    return Err(CollectionError::NotCollectionAuthority.into());
+}
+
+/// Requires `mint_account_info` to be the mint of an NFT, owned by either token program with a
+/// supply of 1 and no decimals.
+fn assert_nft_mint(mint_account_info: &AccountInfo) -> ProgramResult {
+    if !is_token_program(mint_account_info.owner) {
+        return Err(CollectionError::InvalidNFT.into());
+    }
+    let mint = StateWithExtensions::<spl_mint>::unpack(&mint_account_info.data.borrow())
+        .map_err(|_| CollectionError::InvalidNFT)?
+        .base;
+    if mint.supply != 1 || mint.decimals != 0 {
+        return Err(CollectionError::NotAnNft.into());
+    }
+    Ok(())
+}
+
+fn assert_collection_authority(
+    collection_account_data: &CollectionAccountData,
+    collection_auth_account_info: &AccountInfo,
+) -> ProgramResult {
+    if collection_account_data.authority != *collection_auth_account_info.key 
+        || !collection_auth_account_info.is_signer {
+        return Err(CollectionError::NotCollectionAuthority.into());
+    }
+    Ok(())
+}
+
+/// Requires the secondary authority of a co-signed collection to sign as the next account;
+/// collections without one only need their authority.
+fn assert_secondary_authority<'a, 'b: 'a, I: Iterator<Item = &'a AccountInfo<'b>>>(
+    collection_account_data: &CollectionAccountData,
+    account_info_iter: &mut I,
+) -> ProgramResult {
+    if let Some(secondary_authority) = collection_account_data.secondary_authority {
+        let secondary_auth_account_info = next_account_info(account_info_iter)
+            .map_err(|_| CollectionError::NotCollectionAuthority)?;
+        if *secondary_auth_account_info.key != secondary_authority || !secondary_auth_account_info.is_signer {
+            return Err(CollectionError::NotCollectionAuthority.into());
+        }
+    }
+    Ok(())
+}
+
+/// Requires a funding account to sign, like that of a paid light-up where the collection
+/// itself never does.
+fn assert_signer(account_info: &AccountInfo) -> ProgramResult {
+    if !account_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+fn assert_program_id(program_id: &Pubkey) -> ProgramResult {
+    if !check_id(program_id) {
+        return Err(CollectionError::InvalidProgramId.into());
+    }
+    Ok(())
+}
+
+fn assert_create_collection_args(args: &CreateCollectionAccountArgs) -> ProgramResult {
+    if !args.is_valid() {
+        return Err(CollectionError::InvalidMetadata.into());
+    }
+    Ok(())
+}
+
+fn assert_treasury_account(treasury_account_info: &AccountInfo) -> ProgramResult {
+    let (pda, _) = get_treasury_account();
+    if *treasury_account_info.key != pda {
+        return Err(CollectionError::InvalidTreasuryAccount.into());
+    }
+    Ok(())
+}
+
+/// Token tips must land in a token account of the star mint the treasury owns, or anyone
+/// could light stars by paying themselves.
+fn assert_treasury_token_account(treasury_token_account_info: &AccountInfo, star_mint: &Pubkey) -> ProgramResult {
+    if unpack_treasury_token_account(treasury_token_account_info)?.mint != *star_mint {
+        return Err(CollectionError::WrongStarMint.into());
+    }
+    Ok(())
+}
+
+/// Decodes a token account of the classic token program the treasury owns. Token-2022 is not
+/// accepted, its transfer fees would leave the treasury short of the star price.
+fn unpack_treasury_token_account(treasury_token_account_info: &AccountInfo) -> Result<spl_token::state::Account, ProgramError> {
+    if *treasury_token_account_info.owner != spl_token::id() {
+        return Err(CollectionError::InvalidTreasuryAccount.into());
+    }
+    let token_account = spl_token::state::Account::unpack(&treasury_token_account_info.data.borrow())?;
+    let (treasury, _) = get_treasury_account();
+    if token_account.owner != treasury {
+        return Err(CollectionError::InvalidTreasuryAccount.into());
+    }
+    Ok(token_account)
+}
+
+/// Tokens of any other mint than the star mint cost nothing to print, so they light no stars.
+fn assert_star_mint(token_account_info: &AccountInfo, star_mint: &Pubkey) -> ProgramResult {
+    if *token_account_info.owner != spl_token::id() {
+        return Err(CollectionError::InvalidAccountArguments.into());
+    }
+    let token_account = spl_token::state::Account::unpack(&token_account_info.data.borrow())?;
+    if token_account.mint != *star_mint {
+        return Err(CollectionError::WrongStarMint.into());
+    }
+    Ok(())
+}
+
+/// The star mint and price the treasury manager set with `SetStarToken`, tokens light no
+/// stars before that.
+fn get_star_token(program_id: &Pubkey, treasury_config_account_info: &AccountInfo) -> Result<(Pubkey, u64), ProgramError> {
+    if find_treasury_config_address(program_id).0 != *treasury_config_account_info.key {
+        return Err(CollectionError::InvalidAccountArguments.into());
+    }
+    if treasury_config_account_info.owner != program_id
+        || AccountType::check_account_type(&treasury_config_account_info.data.borrow(), AccountType::TreasuryConfig).is_err() {
+        return Err(CollectionError::WrongStarMint.into());
+    }
+    let treasury_config = TreasuryConfigAccountData::try_from_slice_unchecked(&treasury_config_account_info.data.borrow())?;
+    treasury_config.star_mint
+        .map(|star_mint| (star_mint, treasury_config.star_token_price))
+        .ok_or_else(|| CollectionError::WrongStarMint.into())
+}
+
+/// The treasury manager is the upgrade authority of the program, see `assert_upgrade_authority`.
+/// Withdrawals only go to the recipient the upgrade authority bound with
+/// `SetTreasuryRecipient`, none at all before it bound one.
+fn assert_treasury_recipient(
+    program_id: &Pubkey,
+    treasury_config_account_info: &AccountInfo,
+    recipient_account_info: &AccountInfo,
+) -> ProgramResult {
+    if get_treasury_recipient(program_id, treasury_config_account_info)? != *recipient_account_info.key {
+        return Err(CollectionError::WrongRecipient.into());
+    }
+    Ok(())
+}
+
+/// The recipient bound with `SetTreasuryRecipient`, `WrongRecipient` before one was bound.
+fn get_treasury_recipient(program_id: &Pubkey, treasury_config_account_info: &AccountInfo) -> Result<Pubkey, ProgramError> {
+    if find_treasury_config_address(program_id).0 != *treasury_config_account_info.key {
+        return Err(CollectionError::InvalidAccountArguments.into());
+    }
+    if treasury_config_account_info.owner != program_id
+        || AccountType::check_account_type(&treasury_config_account_info.data.borrow(), AccountType::TreasuryConfig).is_err() {
+        return Err(CollectionError::WrongRecipient.into());
+    }
+    let treasury_config = TreasuryConfigAccountData::try_from_slice_unchecked(&treasury_config_account_info.data.borrow())?;
+    Ok(treasury_config.treasury_recipient)
+}
+
+fn assert_treasury_manager(
+    program_id: &Pubkey,
+    manager_account_info: &AccountInfo,
+    program_data_account_info: &AccountInfo,
+) -> ProgramResult {
+    assert_upgrade_authority(program_id, manager_account_info, program_data_account_info, CollectionError::NotTreasuryManager)
+}
+
+/// Checks that `authority_account_info` signed and is the upgrade authority of the program,
+/// read from the `ProgramData` account the upgradeable loader keeps at
+/// `get_program_data_address(program_id)`. Fails with `error` otherwise.
+fn assert_upgrade_authority(
+    program_id: &Pubkey,
+    authority_account_info: &AccountInfo,
+    program_data_account_info: &AccountInfo,
+    error: CollectionError,
+) -> ProgramResult {
+    if *program_data_account_info.key != get_program_data_address(program_id)
+        || *program_data_account_info.owner != bpf_loader_upgradeable::id() {
+        return Err(error.into());
+    }
+    let upgrade_authority = get_upgrade_authority(&program_data_account_info.data.borrow());
+    if upgrade_authority != Some(*authority_account_info.key)
+        || !authority_account_info.is_signer {
+        return Err(error.into());
+    }
+    Ok(())
+}
+
+/// Reads the upgrade authority from `ProgramData` account data, laid out by bincode as a
+/// `u32` variant tag, the `u64` deploy slot and an `Option<Pubkey>`.
+fn get_upgrade_authority(data: &[u8]) -> Option<Pubkey> {
+    const PROGRAM_DATA_TAG: [u8; 4] = [3, 0, 0, 0];
+    if data.len() < UpgradeableLoaderState::size_of_programdata_metadata()
+        || data[..4] != PROGRAM_DATA_TAG
+        || data[12] != 1 {
+        return None;
+    }
+    let mut authority = [0u8; 32];
+    authority.copy_from_slice(&data[13..45]);
+    Some(Pubkey::new_from_array(authority))
 }
\ No newline at end of file
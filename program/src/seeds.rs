@@ -0,0 +1,20 @@
+//! Seeds of the program derived addresses. Every derivation and every `invoke_signed`
+//! starts with `COLLECTION_SEED`; the kind of address follows it.
+
+/// First seed of every address of the program.
+pub const COLLECTION_SEED: &[u8] = b"collection";
+
+/// Seed of the treasury address, after `COLLECTION_SEED`.
+pub const TREASURY_SEED: &[u8] = b"treasury";
+
+/// Seed of the treasury config address, after `COLLECTION_SEED`.
+pub const TREASURY_CONFIG_SEED: &[u8] = b"treasury_config";
+
+/// Seed of the event authority address, after `COLLECTION_SEED`.
+pub const EVENT_AUTHORITY_SEED: &[u8] = b"event_authority";
+
+/// Seed of the allowlist address of a collection, after `COLLECTION_SEED`.
+pub const ALLOWLIST_SEED: &[u8] = b"allowlist";
+
+/// Seed of the cooldown address of a collection, after `COLLECTION_SEED`.
+pub const COOLDOWN_SEED: &[u8] = b"cooldown";
@@ -1,24 +1,113 @@
 use {
     solana_program::{
         borsh::try_from_slice_unchecked,
+        hash::hashv,
         program_error::ProgramError,
         pubkey::Pubkey,
     },
     borsh::{BorshDeserialize, BorshSerialize},
+    crate::{error::CollectionError, instruction::CreateCollectionAccountArgs},
 };
 
+/// Most mints a single allowlist can permit.
+pub const MAX_ALLOWLIST_LENGTH: usize = 64;
+
+/// Signers a cooldown account remembers, once full the oldest one is forgotten.
+pub const MAX_COOLDOWN_SIGNERS: usize = 32;
+
+// The seeds used to live here as strings, they are kept for one release.
+
+#[deprecated(since = "0.1.2", note = "use `seeds::COLLECTION_SEED`")]
 pub const PREFIX: &str = "collection";
 
+#[deprecated(since = "0.1.2", note = "use `seeds::TREASURY_SEED`")]
+pub const TREASURY: &str = "treasury";
+
+#[deprecated(since = "0.1.2", note = "use `seeds::EVENT_AUTHORITY_SEED`")]
+pub const EVENT_AUTHORITY: &str = "event_authority";
+
+#[deprecated(since = "0.1.2", note = "use `seeds::ALLOWLIST_SEED`")]
+pub const ALLOWLIST: &str = "allowlist";
+
+#[deprecated(since = "0.1.2", note = "use `seeds::TREASURY_CONFIG_SEED`")]
+pub const TREASURY_CONFIG: &str = "treasury_config";
+
+#[deprecated(since = "0.1.2", note = "use `seeds::COOLDOWN_SEED`")]
+pub const COOLDOWN: &str = "cooldown";
+
+/// Layout version written by `CreateCollectionAccount`. Version 0 accounts end after the
+/// tags, later versions store this byte right after them followed by the newer fields:
+/// `created_at` since version 1, `frozen` since version 2, `royalty_bps` since version 3,
+/// `star_price` since version 4, `index_seq` since version 5, `verified` since version 6 and
+/// `last_star_slot` with `stars_this_slot` since version 7, `secondary_authority` since
+/// version 8, `metadata_uri` since version 9, `has_allowlist` since version 10, `category`
+/// since version 11, `creators` since version 12, `parent` since version 13,
+/// `cooldown_slots` since version 14 and `collection_mint` since version 15.
+///
+/// The byte follows the tags rather than the account type because version 0 accounts put
+/// the authority right after the account type, and no byte there tells the two apart. They
+/// were created exactly as long as their fields and `write_to` zeroes what a shorter write
+/// leaves behind, so their tags are followed by nothing or by a zero, read as version 0.
+pub const COLLECTION_ACCOUNT_VERSION: u8 = 15;
+
+/// Default price of a single star in lamports, for collections created without a
+/// `star_price` and those created before it was stored (0.01 SOL per 100 stars).
+pub const LAMPORTS_PER_STAR: u64 = 100_000;
+
+/// Most stars a collection can hold. At the default `LAMPORTS_PER_STAR` that is more SOL than exists,
+/// so only the free `LightUpStarsOnce` tier could ever get close.
+pub const MAX_STARS: u64 = 10_000_000_000_000;
+
+/// Most stars a single light-up may add, whatever room `MAX_STARS` leaves.
+pub const MAX_STARS_PER_CALL: u64 = 1_000_000;
+
+/// Free `LightUpStarsOnce` calls a collection accepts within a single slot.
+pub const MAX_FREE_STARS_PER_SLOT: u32 = 10;
+
+/// Characters of the description used as the short description when none is set.
+pub const DERIVED_SHORT_DESCRIPTION_LENGTH: usize = 120;
+
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy)]
 pub enum AccountType {
     Uninitialized,
     CollectionAccount,
     CollectionIndexAccount,
+    Allowlist,
+    TreasuryConfig,
+    Cooldown,
+}
+
+impl AccountType {
+    /// Checks the leading discriminant of `data` before the rest of it is trusted.
+    pub fn check_account_type(data: &[u8], expected: AccountType) -> Result<(), ProgramError> {
+        match data.first() {
+            None => Err(CollectionError::Uninitialized.into()),
+            Some(account_type) if *account_type == AccountType::Uninitialized as u8 => {
+                Err(CollectionError::Uninitialized.into())
+            },
+            Some(account_type) if *account_type != expected as u8 => {
+                Err(CollectionError::InvalidAccountType.into())
+            },
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+/// Fixed category of a collection for filtering, unlike the free-form tags. Encoded as a
+/// single byte, bytes past the last variant do not decode.
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy)]
+pub enum CollectionCategory {
+    Art,
+    Music,
+    Gaming,
+    Pfp,
+    Photography,
+    Other,
 }
 
 #[repr(C)]
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[derive(Debug, Clone)]
 pub struct CollectionAccountData {
     pub account_type: AccountType,
     pub authority: Pubkey,
@@ -32,30 +121,667 @@ pub struct CollectionAccountData {
     pub short_description: Option<String>,
     pub banner: Option<String>,
     pub tags: Option<Vec<String>>,
+    pub version: u8,
+    /// Unix timestamp of the `CreateCollectionAccount`, zero for accounts created before it was recorded
+    pub created_at: i64,
+    /// Set by `FreezeCollection`, a frozen collection rejects metadata and authority changes
+    pub frozen: bool,
+    /// Royalty hint for marketplaces in basis points
+    pub royalty_bps: Option<u16>,
+    /// Lamports charged per star by the paid light-up instructions
+    pub star_price: u64,
+    /// Sequence number handed to the next included token, it never goes down
+    pub index_seq: u64,
+    /// Set by the curator through `SetVerified`, the creator can not change it
+    pub verified: bool,
+    /// Slot of the latest free `LightUpStarsOnce`
+    pub last_star_slot: u64,
+    /// Free stars lit during `last_star_slot`, capped at `MAX_FREE_STARS_PER_SLOT`
+    pub stars_this_slot: u32,
+    /// Co-signer of every change the authority makes, set at creation
+    pub secondary_authority: Option<Pubkey>,
+    /// Off-chain JSON with the extended metadata of the collection
+    pub metadata_uri: Option<String>,
+    /// Set once the collection has an allowlist, includes then need its mint on it
+    pub has_allowlist: bool,
+    /// Set at creation or through `SetCategory`
+    pub category: Option<CollectionCategory>,
+    /// Royalty split set at creation, see `CreateCollectionAccountArgs::creators`
+    pub creators: Option<Vec<(Pubkey, u16)>>,
+    /// Collection this one is a sub-collection of, linked through `SetParent`
+    pub parent: Option<Pubkey>,
+    /// Slots a signer waits between two free stars, set through `SetCooldown`, zero when off
+    pub cooldown_slots: u64,
+    /// Collection NFT mint, like a Metaplex collection's, linked through `SetCollectionMint`
+    pub collection_mint: Option<Pubkey>,
+}
+
+impl BorshSerialize for CollectionAccountData {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.account_type.serialize(writer)?;
+        self.authority.serialize(writer)?;
+        self.title.serialize(writer)?;
+        self.symbol.serialize(writer)?;
+        self.description.serialize(writer)?;
+        self.icon_image.serialize(writer)?;
+        self.supply.serialize(writer)?;
+        self.stars.serialize(writer)?;
+        self.header_image.serialize(writer)?;
+        self.short_description.serialize(writer)?;
+        self.banner.serialize(writer)?;
+        // `LEN` only has room for `MAX_TAGS_ARRAY_LENGTH` tags, refuse more instead of
+        // writing past the space every account is sized for
+        if self.tags.as_ref().map_or(0, Vec::len) > CreateCollectionAccountArgs::MAX_TAGS_ARRAY_LENGTH {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Too many collection tags",
+            ));
+        }
+        self.tags.serialize(writer)?;
+        // only the fields of the account's own version are written, so older accounts
+        // keep fitting into the space they were created with
+        if self.version == 0 {
+            return Ok(());
+        }
+        self.version.serialize(writer)?;
+        self.created_at.serialize(writer)?;
+        if self.version >= 2 {
+            self.frozen.serialize(writer)?;
+        }
+        if self.version >= 3 {
+            self.royalty_bps.serialize(writer)?;
+        }
+        if self.version >= 4 {
+            self.star_price.serialize(writer)?;
+        }
+        if self.version >= 5 {
+            self.index_seq.serialize(writer)?;
+        }
+        if self.version >= 6 {
+            self.verified.serialize(writer)?;
+        }
+        if self.version >= 7 {
+            self.last_star_slot.serialize(writer)?;
+            self.stars_this_slot.serialize(writer)?;
+        }
+        if self.version >= 8 {
+            self.secondary_authority.serialize(writer)?;
+        }
+        if self.version >= 9 {
+            self.metadata_uri.serialize(writer)?;
+        }
+        if self.version >= 10 {
+            self.has_allowlist.serialize(writer)?;
+        }
+        if self.version >= 11 {
+            self.category.serialize(writer)?;
+        }
+        if self.version >= 12 {
+            self.creators.serialize(writer)?;
+        }
+        if self.version >= 13 {
+            self.parent.serialize(writer)?;
+        }
+        if self.version >= 14 {
+            self.cooldown_slots.serialize(writer)?;
+        }
+        if self.version >= 15 {
+            self.collection_mint.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Walks the length-prefixed strings of a collection account, from the title to the tags,
+/// and rejects any declared length running past the end of `data` before Borsh reads it.
+/// Data ending before a length prefix is left for the deserializer to report.
+fn check_string_lengths(data: &[u8]) -> Result<(), CollectionError> {
+    let mut offset = 1 + 32;
+    let skip_string = |offset: &mut usize| -> Result<bool, CollectionError> {
+        let prefix = match data.get(*offset..*offset + 4) {
+            Some(prefix) => prefix,
+            None => return Ok(false),
+        };
+        let len = u32::from_le_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as usize;
+        if len > data.len() - *offset - 4 {
+            return Err(CollectionError::InvalidAccountData);
+        }
+        *offset += 4 + len;
+        Ok(true)
+    };
+    let is_some = |offset: &mut usize| -> bool {
+        *offset += 1;
+        data.get(*offset - 1) == Some(&1)
+    };
+
+    for _ in 0..4 {
+        if !skip_string(&mut offset)? {
+            return Ok(());
+        }
+    }
+    // supply and stars
+    offset += 8 + 8;
+    for _ in 0..3 {
+        if is_some(&mut offset) && !skip_string(&mut offset)? {
+            return Ok(());
+        }
+    }
+    if is_some(&mut offset) {
+        let count = match data.get(offset..offset + 4) {
+            Some(prefix) => u32::from_le_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]),
+            None => return Ok(()),
+        };
+        offset += 4;
+        for _ in 0..count {
+            if !skip_string(&mut offset)? {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Offset of `stars` in a collection account: it follows the account type, the authority,
+/// the four leading strings and the supply, so only the string prefixes need reading.
+fn stars_offset(data: &[u8]) -> Result<usize, ProgramError> {
+    AccountType::check_account_type(data, AccountType::CollectionAccount)?;
+    let mut offset = 1 + 32;
+    for _ in 0..4 {
+        let prefix = data.get(offset..offset + 4).ok_or(ProgramError::InvalidAccountData)?;
+        let len = u32::from_le_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as usize;
+        offset = offset
+            .checked_add(4 + len)
+            .ok_or(ProgramError::InvalidAccountData)?;
+    }
+    let offset = offset + 8;
+    if data.len() < offset + 8 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(offset)
+}
+
+/// Reads the star count of a collection account without decoding the rest of it.
+pub fn read_stars(data: &[u8]) -> Result<u64, ProgramError> {
+    let offset = stars_offset(data)?;
+    let mut stars = [0; 8];
+    stars.copy_from_slice(&data[offset..offset + 8]);
+    Ok(u64::from_le_bytes(stars))
+}
+
+/// Overwrites the star count of a collection account, leaving every other byte untouched.
+pub fn write_stars(data: &mut [u8], stars: u64) -> Result<(), ProgramError> {
+    let offset = stars_offset(data)?;
+    data[offset..offset + 8].copy_from_slice(&stars.to_le_bytes());
+    Ok(())
+}
+
+/// Lamports the paid light-ups charge for `stars` stars at `star_price`, `None` when that
+/// does not fit a `u64`.
+pub fn stars_cost(stars: u64, star_price: u64) -> Option<u64> {
+    stars.checked_mul(star_price)
+}
+
+/// Offset of the version byte in a collection account, `None` for version 0 accounts that
+/// end after the tags. Like `stars_offset` it only reads length prefixes and option tags, the
+/// fields between the stars and the version are never decoded.
+fn version_offset(data: &[u8]) -> Result<Option<usize>, ProgramError> {
+    let byte = |offset: usize| data.get(offset).copied().ok_or(ProgramError::InvalidAccountData);
+    let skip_string = |offset: usize| -> Result<usize, ProgramError> {
+        let prefix = data.get(offset..offset + 4).ok_or(ProgramError::InvalidAccountData)?;
+        let len = u32::from_le_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as usize;
+        offset.checked_add(4 + len).ok_or(ProgramError::InvalidAccountData)
+    };
+
+    let mut offset = stars_offset(data)? + 8;
+    // header_image, short_description and banner
+    for _ in 0..3 {
+        offset = if byte(offset)? == 1 { skip_string(offset + 1)? } else { offset + 1 };
+    }
+    if byte(offset)? == 1 {
+        let prefix = data.get(offset + 1..offset + 5).ok_or(ProgramError::InvalidAccountData)?;
+        let count = u32::from_le_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]);
+        offset += 5;
+        for _ in 0..count {
+            offset = skip_string(offset)?;
+        }
+    } else {
+        offset += 1;
+    }
+    // legacy accounts end after the tags
+    if offset == data.len() {
+        return Ok(None);
+    }
+    byte(offset)?;
+    Ok(Some(offset))
+}
+
+/// Offset of `last_star_slot` in a collection account, `None` for accounts older than version 7
+/// that do not store the free star counters.
+fn free_star_counters_offset(data: &[u8]) -> Result<Option<usize>, ProgramError> {
+    let mut offset = match version_offset(data)? {
+        Some(offset) if data[offset] >= 7 => offset,
+        _ => return Ok(None),
+    };
+    let byte = |offset: usize| data.get(offset).copied().ok_or(ProgramError::InvalidAccountData);
+    // version, created_at and frozen
+    offset += 1 + 8 + 1;
+    // royalty_bps
+    offset += if byte(offset)? == 1 { 3 } else { 1 };
+    // star_price, index_seq and verified
+    offset += 8 + 8 + 1;
+    if data.len() < offset + 8 + 4 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(Some(offset))
+}
+
+/// Reads `cooldown_slots` without decoding the rest of the account, zero for accounts older
+/// than version 14 as `CollectionAccountData` decodes them.
+pub fn read_cooldown_slots(data: &[u8]) -> Result<u64, ProgramError> {
+    match version_offset(data)? {
+        Some(offset) if data[offset] >= 14 => {}
+        _ => return Ok(0),
+    }
+    let mut offset = free_star_counters_offset(data)?.ok_or(ProgramError::InvalidAccountData)?;
+    let byte = |offset: usize| data.get(offset).copied().ok_or(ProgramError::InvalidAccountData);
+    let len_prefix = |offset: usize| -> Result<usize, ProgramError> {
+        let prefix = data.get(offset..offset + 4).ok_or(ProgramError::InvalidAccountData)?;
+        Ok(u32::from_le_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as usize)
+    };
+    // last_star_slot and stars_this_slot
+    offset += 8 + 4;
+    // secondary_authority
+    offset += if byte(offset)? == 1 { 1 + 32 } else { 1 };
+    // metadata_uri
+    offset += if byte(offset)? == 1 { 1 + 4 + len_prefix(offset + 1)? } else { 1 };
+    // has_allowlist
+    offset += 1;
+    // category
+    offset += if byte(offset)? == 1 { 2 } else { 1 };
+    // creators
+    offset += if byte(offset)? == 1 {
+        len_prefix(offset + 1)?
+            .checked_mul(32 + 2)
+            .and_then(|len| len.checked_add(1 + 4))
+            .ok_or(ProgramError::InvalidAccountData)?
+    } else {
+        1
+    };
+    // parent
+    offset += if byte(offset)? == 1 { 1 + 32 } else { 1 };
+    let cooldown_slots = offset
+        .checked_add(8)
+        .and_then(|end| data.get(offset..end))
+        .ok_or(ProgramError::InvalidAccountData)?;
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(cooldown_slots);
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Reads `last_star_slot` and `stars_this_slot` without decoding the rest of the account, `None`
+/// for accounts older than the counters.
+pub fn read_free_star_counters(data: &[u8]) -> Result<Option<(u64, u32)>, ProgramError> {
+    let offset = match free_star_counters_offset(data)? {
+        Some(offset) => offset,
+        None => return Ok(None),
+    };
+    let mut last_star_slot = [0; 8];
+    last_star_slot.copy_from_slice(&data[offset..offset + 8]);
+    let mut stars_this_slot = [0; 4];
+    stars_this_slot.copy_from_slice(&data[offset + 8..offset + 12]);
+    Ok(Some((u64::from_le_bytes(last_star_slot), u32::from_le_bytes(stars_this_slot))))
+}
+
+/// Overwrites the free star counters of an account that stores them, leaving every other
+/// byte untouched.
+pub fn write_free_star_counters(data: &mut [u8], last_star_slot: u64, stars_this_slot: u32) -> Result<(), ProgramError> {
+    let offset = free_star_counters_offset(data)?.ok_or(ProgramError::InvalidAccountData)?;
+    data[offset..offset + 8].copy_from_slice(&last_star_slot.to_le_bytes());
+    data[offset + 8..offset + 12].copy_from_slice(&stars_this_slot.to_le_bytes());
+    Ok(())
+}
+
+impl BorshDeserialize for CollectionAccountData {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let account_type = AccountType::deserialize(buf)?;
+        let authority = Pubkey::deserialize(buf)?;
+        let title = String::deserialize(buf)?;
+        let symbol = String::deserialize(buf)?;
+        let description = String::deserialize(buf)?;
+        let icon_image = String::deserialize(buf)?;
+        let supply = u64::deserialize(buf)?;
+        let stars = u64::deserialize(buf)?;
+        let header_image = Option::<String>::deserialize(buf)?;
+        let short_description = Option::<String>::deserialize(buf)?;
+        let banner = Option::<String>::deserialize(buf)?;
+        let tags = Option::<Vec<String>>::deserialize(buf)?;
+        // legacy accounts end after the tags, or continue with the zeroes of a shorter write
+        let version = if buf.is_empty() { 0 } else { u8::deserialize(buf)? };
+        if version > COLLECTION_ACCOUNT_VERSION {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Unsupported collection account version",
+            ));
+        }
+        let created_at = if version >= 1 { i64::deserialize(buf)? } else { 0 };
+        let frozen = if version >= 2 { bool::deserialize(buf)? } else { false };
+        let royalty_bps = if version >= 3 { Option::<u16>::deserialize(buf)? } else { None };
+        let star_price = if version >= 4 { u64::deserialize(buf)? } else { LAMPORTS_PER_STAR };
+        // older accounts numbered their tokens by supply
+        let index_seq = if version >= 5 { u64::deserialize(buf)? } else { supply };
+        let verified = if version >= 6 { bool::deserialize(buf)? } else { false };
+        let (last_star_slot, stars_this_slot) = if version >= 7 {
+            (u64::deserialize(buf)?, u32::deserialize(buf)?)
+        } else {
+            (0, 0)
+        };
+        let secondary_authority = if version >= 8 { Option::<Pubkey>::deserialize(buf)? } else { None };
+        let metadata_uri = if version >= 9 { Option::<String>::deserialize(buf)? } else { None };
+        let has_allowlist = if version >= 10 { bool::deserialize(buf)? } else { false };
+        let category = if version >= 11 { Option::<CollectionCategory>::deserialize(buf)? } else { None };
+        let creators = if version >= 12 { Option::<Vec<(Pubkey, u16)>>::deserialize(buf)? } else { None };
+        let parent = if version >= 13 { Option::<Pubkey>::deserialize(buf)? } else { None };
+        let cooldown_slots = if version >= 14 { u64::deserialize(buf)? } else { 0 };
+        let collection_mint = if version >= 15 { Option::<Pubkey>::deserialize(buf)? } else { None };
+        Ok(CollectionAccountData {
+            account_type,
+            authority,
+            title,
+            symbol,
+            description,
+            icon_image,
+            supply,
+            stars,
+            header_image,
+            short_description,
+            banner,
+            tags,
+            version,
+            created_at,
+            frozen,
+            royalty_bps,
+            star_price,
+            index_seq,
+            verified,
+            last_star_slot,
+            stars_this_slot,
+            secondary_authority,
+            metadata_uri,
+            has_allowlist,
+            category,
+            creators,
+            parent,
+            cooldown_slots,
+            collection_mint,
+        })
+    }
+}
+
+/// Bytes a string of at most `max_chars` characters can take, UTF-8 uses up to 4 per character.
+const fn max_string_len(max_chars: usize) -> usize {
+    4 + max_chars * 4
 }
 
 impl CollectionAccountData {
+    /// Space allocated for every collection account: the current layout with every string and
+    /// tag at its maximum length, so updates can never outgrow the account.
+    pub const LEN: usize = 1 // account_type
+        + 32 // authority
+        + max_string_len(CreateCollectionAccountArgs::MAX_TITLE_LENGTH)
+        + 4 + CreateCollectionAccountArgs::MAX_SYMBOL_LENGTH // symbols are ASCII
+        + max_string_len(CreateCollectionAccountArgs::MAX_DESCRIPTION_LENGTH)
+        + 4 + CreateCollectionAccountArgs::MAX_URI_LENGTH // icon_image, URI lengths are in bytes
+        + 8 // supply
+        + 8 // stars
+        + 1 + 4 + CreateCollectionAccountArgs::MAX_URI_LENGTH // header_image
+        + 1 + max_string_len(CreateCollectionAccountArgs::MAX_SHORT_DESCRIPTION_LENGTH)
+        + 1 + 4 + CreateCollectionAccountArgs::MAX_URI_LENGTH // banner
+        + 1 + 4 + CreateCollectionAccountArgs::MAX_TAGS_ARRAY_LENGTH
+            * max_string_len(CreateCollectionAccountArgs::MAX_TAG_LENGTH)
+        + 1 // version
+        + 8 // created_at
+        + 1 // frozen
+        + 1 + 2 // royalty_bps
+        + 8 // star_price
+        + 8 // index_seq
+        + 1 // verified
+        + 8 // last_star_slot
+        + 4 // stars_this_slot
+        + 1 + 32 // secondary_authority
+        + 1 + 4 + CreateCollectionAccountArgs::MAX_URI_LENGTH // metadata_uri
+        + 1 // has_allowlist
+        + 1 + 1 // category
+        + 1 + 4 + CreateCollectionAccountArgs::MAX_CREATORS * (32 + 2) // creators
+        + 1 + 32 // parent
+        + 8 // cooldown_slots
+        + 1 + 32; // collection_mint
+
+    /// `LEN` without the room of `header_image`, `short_description`, `banner` and `tags`, which
+    /// `ClearOptionalMetadata` shrinks an account to. Every other field keeps its full room.
+    pub const CLEARED_LEN: usize = Self::LEN
+        - 4 - CreateCollectionAccountArgs::MAX_URI_LENGTH // header_image
+        - max_string_len(CreateCollectionAccountArgs::MAX_SHORT_DESCRIPTION_LENGTH)
+        - 4 - CreateCollectionAccountArgs::MAX_URI_LENGTH // banner
+        - 4 - CreateCollectionAccountArgs::MAX_TAGS_ARRAY_LENGTH
+            * max_string_len(CreateCollectionAccountArgs::MAX_TAG_LENGTH);
+
+    /// A new collection of `authority` in the current layout, as `CreateCollectionAccount`
+    /// stores it.
+    pub fn new(authority: Pubkey, args: &CreateCollectionAccountArgs, created_at: i64) -> Self {
+        CollectionAccountData {
+            account_type: AccountType::CollectionAccount,
+            authority,
+            title: args.title.clone(),
+            symbol: args.symbol.clone(),
+            description: args.description.clone(),
+            icon_image: args.icon_image.clone(),
+            supply: 0,
+            stars: 0,
+            header_image: args.header_image.clone(),
+            short_description: args.short_description.clone(),
+            banner: args.banner.clone(),
+            tags: args.tags.clone(),
+            version: COLLECTION_ACCOUNT_VERSION,
+            created_at,
+            frozen: false,
+            royalty_bps: args.royalty_bps,
+            star_price: args.star_price.unwrap_or(LAMPORTS_PER_STAR),
+            index_seq: 0,
+            verified: false,
+            last_star_slot: 0,
+            stars_this_slot: 0,
+            secondary_authority: args.secondary_authority,
+            metadata_uri: args.metadata_uri.clone(),
+            has_allowlist: false,
+            category: args.category,
+            creators: args.creators.clone(),
+            parent: None,
+            cooldown_slots: 0,
+            collection_mint: None,
+        }
+    }
+
+    /// Smallest possible encoding: the fixed fields with empty strings and every option `None`.
+    pub const MIN_LEN: usize = 1 + 32 + 4 * 4 + 8 + 8 + 4;
+
+    /// Decodes a collection account from its raw data, checking the account type first and
+    /// reporting short or truncated data as `InvalidAccountData` instead of trusting it.
+    pub fn try_from_account_data(data: &[u8]) -> Result<CollectionAccountData, ProgramError> {
+        AccountType::check_account_type(data, AccountType::CollectionAccount)?;
+        if data.len() < CollectionAccountData::MIN_LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_string_lengths(data)?;
+        try_from_slice_unchecked(data).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
     pub fn try_from_slice_unchecked(data: &[u8]) -> Result<CollectionAccountData, ProgramError> {
+        check_string_lengths(data)?;
         let result: CollectionAccountData = try_from_slice_unchecked(data)?;
         Ok(result)
     }
 
+    /// Writes the account over `data` and zeroes whatever a longer earlier encoding left past
+    /// its end, so the tags of a version 0 account are followed by nothing or a zero version.
+    /// An encoding longer than `data`, of an account allocated for an older version or shrunk
+    /// by `ClearOptionalMetadata`, fails with `MigrationRequired`.
+    pub fn write_to(&self, data: &mut [u8]) -> Result<(), ProgramError> {
+        let mut tail = data;
+        self.serialize(&mut tail).map_err(|error| match error.kind() {
+            std::io::ErrorKind::WriteZero => CollectionError::MigrationRequired.into(),
+            _ => ProgramError::from(error),
+        })?;
+        tail.fill(0);
+        Ok(())
+    }
+
     pub fn is_initialized(&self) -> bool {
         self.account_type == AccountType::CollectionAccount
     }
+
+    /// Total number of stars lit for the collection.
+    pub fn stars(&self) -> u64 {
+        self.stars
+    }
+
+    /// Royalty hint in basis points, `None` when the creator set none.
+    pub fn royalty_bps(&self) -> Option<u16> {
+        self.royalty_bps
+    }
+
+    /// Royalty split between creators in basis points, `None` when the creator set none.
+    pub fn creators(&self) -> Option<&[(Pubkey, u16)]> {
+        self.creators.as_deref()
+    }
+
+    /// Lamports charged per star by the paid light-up instructions.
+    pub fn star_price(&self) -> u64 {
+        self.star_price
+    }
+
+    /// Sequence number the next included token will get as its index.
+    pub fn index_seq(&self) -> u64 {
+        self.index_seq
+    }
+
+    /// Number of tokens currently included in the collection, kept in `supply`.
+    pub fn token_count(&self) -> u64 {
+        self.supply
+    }
+
+    /// Tags that can still be added before the collection holds `MAX_TAGS_ARRAY_LENGTH`.
+    pub fn tags_remaining(&self) -> usize {
+        CreateCollectionAccountArgs::MAX_TAGS_ARRAY_LENGTH.saturating_sub(self.tags.as_ref().map_or(0, Vec::len))
+    }
+
+    /// Whether `AddTag` has room for one more tag.
+    pub fn can_add_tag(&self) -> bool {
+        self.tags_remaining() > 0
+    }
+
+    /// Header image to display, falling back to the icon image when none is set.
+    pub fn header_image_or_icon(&self) -> &str {
+        self.header_image.as_deref().unwrap_or(&self.icon_image)
+    }
+
+    /// Short description to display, falling back to the first
+    /// `DERIVED_SHORT_DESCRIPTION_LENGTH` characters of the description when none is set.
+    pub fn short_description_or_truncated(&self) -> String {
+        match &self.short_description {
+            Some(short_description) => short_description.clone(),
+            None => self.description.chars().take(DERIVED_SHORT_DESCRIPTION_LENGTH).collect(),
+        }
+    }
+
+    /// Fingerprint of the title, symbol, description and image URIs, stable until one of them
+    /// changes. Every field is hashed behind its length so moving bytes between fields shows.
+    pub fn content_hash(&self) -> [u8; 32] {
+        let fields = [
+            self.title.as_str(),
+            self.symbol.as_str(),
+            self.description.as_str(),
+            self.icon_image.as_str(),
+            self.header_image.as_deref().unwrap_or_default(),
+            self.banner.as_deref().unwrap_or_default(),
+        ];
+        let lengths: Vec<[u8; 4]> = fields.iter().map(|field| (field.len() as u32).to_le_bytes()).collect();
+        let mut vals: Vec<&[u8]> = Vec::with_capacity(fields.len() * 2);
+        for (field, length) in fields.iter().zip(lengths.iter()) {
+            vals.push(length);
+            vals.push(field.as_bytes());
+        }
+        hashv(&vals).to_bytes()
+    }
+
+    /// Returns the editable metadata in the shape it was created with.
+    pub fn metadata(&self) -> CreateCollectionAccountArgs {
+        CreateCollectionAccountArgs {
+            title: self.title.clone(),
+            symbol: self.symbol.clone(),
+            description: self.description.clone(),
+            icon_image: self.icon_image.clone(),
+            header_image: self.header_image.clone(),
+            short_description: self.short_description.clone(),
+            banner: self.banner.clone(),
+            tags: self.tags.clone(),
+            royalty_bps: self.royalty_bps,
+            star_price: Some(self.star_price),
+            secondary_authority: self.secondary_authority,
+            metadata_uri: self.metadata_uri.clone(),
+            category: self.category,
+            creators: self.creators.clone(),
+        }
+    }
+
+    pub fn set_metadata(&mut self, metadata: &CreateCollectionAccountArgs) {
+        self.title = metadata.title.clone();
+        self.symbol = metadata.symbol.clone();
+        self.description = metadata.description.clone();
+        self.icon_image = metadata.icon_image.clone();
+        self.header_image = metadata.header_image.clone();
+        self.short_description = metadata.short_description.clone();
+        self.banner = metadata.banner.clone();
+        self.tags = metadata.tags.clone();
+        self.royalty_bps = metadata.royalty_bps;
+        self.star_price = metadata.star_price.unwrap_or(self.star_price);
+        self.metadata_uri = metadata.metadata_uri.clone();
+        self.category = metadata.category;
+        self.creators = metadata.creators.clone();
+    }
 }
 
 #[repr(C)]
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[derive(BorshSerialize, Debug, Clone)]
 pub struct CollectionIndexAccountData {
     pub account_type: AccountType,
     pub collection: Pubkey,
     pub mint: Pubkey,
+    /// Inclusion sequence number taken from the collection's `index_seq`
     pub index: u64,
+    /// Canonical bump of the index address, zero for accounts created before it was stored
+    pub bump: u8,
+}
+
+impl BorshDeserialize for CollectionIndexAccountData {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let account_type = AccountType::deserialize(buf)?;
+        let collection = Pubkey::deserialize(buf)?;
+        let mint = Pubkey::deserialize(buf)?;
+        let index = u64::deserialize(buf)?;
+        // legacy accounts end after the index
+        let bump = if buf.is_empty() { 0 } else { u8::deserialize(buf)? };
+        Ok(CollectionIndexAccountData {
+            account_type,
+            collection,
+            mint,
+            index,
+            bump,
+        })
+    }
 }
 
 impl CollectionIndexAccountData {
-    pub const LEN: usize = 1 + 32 + 32 + 8;
+    pub const LEN: usize = 1 + 32 + 32 + 8 + 1;
 
     pub fn try_from_slice_unchecked(data: &[u8]) -> Result<CollectionIndexAccountData, ProgramError> {
         let result: CollectionIndexAccountData = try_from_slice_unchecked(data)?;
@@ -66,12 +792,149 @@ impl CollectionIndexAccountData {
         self.account_type == AccountType::CollectionIndexAccount
     }
 
-    pub fn new(collection: Pubkey, mint: Pubkey, index: u64) -> CollectionIndexAccountData {
+    pub fn new(collection: Pubkey, mint: Pubkey, index: u64, bump: u8) -> CollectionIndexAccountData {
         return CollectionIndexAccountData {
             account_type: AccountType::CollectionIndexAccount,
             collection,
             mint,
             index,
+            bump,
         };
     }
-}
\ No newline at end of file
+}
+
+/// Mints a curated collection accepts, at the address of `find_allowlist_address`.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct AllowlistAccountData {
+    pub account_type: AccountType,
+    pub collection: Pubkey,
+    pub mints: Vec<Pubkey>,
+}
+
+impl AllowlistAccountData {
+    pub const LEN: usize = 1 + 32 + 4 + MAX_ALLOWLIST_LENGTH * 32;
+
+    pub fn try_from_slice_unchecked(data: &[u8]) -> Result<AllowlistAccountData, ProgramError> {
+        let result: AllowlistAccountData = try_from_slice_unchecked(data)?;
+        Ok(result)
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.account_type == AccountType::Allowlist
+    }
+
+    /// Whether `mint` may be included in the collection.
+    pub fn is_allowed(&self, mint: &Pubkey) -> bool {
+        self.mints.contains(mint)
+    }
+
+    pub fn new(collection: Pubkey) -> AllowlistAccountData {
+        AllowlistAccountData {
+            account_type: AccountType::Allowlist,
+            collection,
+            mints: Vec::new(),
+        }
+    }
+}
+
+/// Program-wide treasury settings, written by the upgrade authority with `SetTreasuryRecipient`
+/// and `SetStarToken`.
+#[repr(C)]
+#[derive(BorshSerialize, Debug, Clone)]
+pub struct TreasuryConfigAccountData {
+    pub account_type: AccountType,
+    /// The only account `Withdraw` and `WithdrawAmount` send treasury lamports to
+    pub treasury_recipient: Pubkey,
+    /// The only mint `LightUpStarsWithToken` accepts, it lights no stars without one
+    pub star_mint: Option<Pubkey>,
+    /// Base units of `star_mint` charged per star, zero without a mint
+    pub star_token_price: u64,
+}
+
+impl BorshDeserialize for TreasuryConfigAccountData {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let account_type = AccountType::deserialize(buf)?;
+        let treasury_recipient = Pubkey::deserialize(buf)?;
+        // legacy accounts end after the recipient
+        let (star_mint, star_token_price) = if buf.is_empty() {
+            (None, 0)
+        } else {
+            (Option::<Pubkey>::deserialize(buf)?, u64::deserialize(buf)?)
+        };
+        Ok(TreasuryConfigAccountData {
+            account_type,
+            treasury_recipient,
+            star_mint,
+            star_token_price,
+        })
+    }
+}
+
+impl TreasuryConfigAccountData {
+    pub const LEN: usize = 1 + 32 + 1 + 32 + 8;
+
+    pub fn try_from_slice_unchecked(data: &[u8]) -> Result<TreasuryConfigAccountData, ProgramError> {
+        let result: TreasuryConfigAccountData = try_from_slice_unchecked(data)?;
+        Ok(result)
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.account_type == AccountType::TreasuryConfig
+    }
+
+    pub fn new(treasury_recipient: Pubkey) -> TreasuryConfigAccountData {
+        TreasuryConfigAccountData {
+            account_type: AccountType::TreasuryConfig,
+            treasury_recipient,
+            star_mint: None,
+            star_token_price: 0,
+        }
+    }
+}
+
+/// Latest free stars of a collection with a cooldown, at the address of `find_cooldown_address`.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct CooldownAccountData {
+    pub account_type: AccountType,
+    pub collection: Pubkey,
+    /// Signers with the slot of their latest free star, oldest first
+    pub signers: Vec<(Pubkey, u64)>,
+}
+
+impl CooldownAccountData {
+    pub const LEN: usize = 1 + 32 + 4 + MAX_COOLDOWN_SIGNERS * (32 + 8);
+
+    pub fn try_from_slice_unchecked(data: &[u8]) -> Result<CooldownAccountData, ProgramError> {
+        let result: CooldownAccountData = try_from_slice_unchecked(data)?;
+        Ok(result)
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.account_type == AccountType::Cooldown
+    }
+
+    /// Records a free star of `signer` at `slot`, refusing it with `Cooldown` while the
+    /// signer's previous star is less than `cooldown_slots` old. Signers whose cooldown ran
+    /// out are forgotten first, a full account then forgets its oldest signer.
+    pub fn record(&mut self, signer: &Pubkey, slot: u64, cooldown_slots: u64) -> Result<(), CollectionError> {
+        self.signers.retain(|(_, last_slot)| slot.saturating_sub(*last_slot) < cooldown_slots);
+        if self.signers.iter().any(|(key, _)| key == signer) {
+            return Err(CollectionError::Cooldown);
+        }
+        if self.signers.len() >= MAX_COOLDOWN_SIGNERS {
+            self.signers.remove(0);
+        }
+        self.signers.push((*signer, slot));
+        Ok(())
+    }
+
+    pub fn new(collection: Pubkey) -> CooldownAccountData {
+        CooldownAccountData {
+            account_type: AccountType::Cooldown,
+            collection,
+            signers: Vec::new(),
+        }
+    }
+}
@@ -1,14 +1,18 @@
 use {
     crate::{
-        state::PREFIX,
+        seeds::{
+            ALLOWLIST_SEED, COLLECTION_SEED, COOLDOWN_SEED, EVENT_AUTHORITY_SEED, TREASURY_CONFIG_SEED,
+            TREASURY_SEED,
+        },
         id,
     },
     solana_program::{
         account_info::AccountInfo,
+        bpf_loader_upgradeable,
         entrypoint::ProgramResult,
         msg,
         program::{invoke, invoke_signed},
-        pubkey::Pubkey,
+        pubkey::{Pubkey, PubkeyError},
         system_instruction,
         sysvar::{rent::Rent, Sysvar},
     },
@@ -92,27 +96,116 @@ pub fn create_or_allocate_account_raw<'a>(
     Ok(())
 }
 
+/// Lamports of `treasury` above its rent exempt minimum, the most a withdrawal can take.
+pub fn withdrawable_balance(treasury: &AccountInfo, rent: &Rent) -> u64 {
+    treasury.lamports().saturating_sub(rent.minimum_balance(treasury.data_len()))
+}
+
+/// Canonical bump of the treasury address of the program deployed at `id()`. The treasury
+/// holds no state to store it in, so it is pinned here instead.
+pub const TREASURY_BUMP: u8 = 253;
+
+/// Derives the index address of `mint` for the collection program deployed at `program_id`,
+/// using the seeds `["collection", program_id, mint]`.
+pub fn find_index_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    let seeds = &[
+        COLLECTION_SEED,
+        program_id.as_ref(),
+        mint.as_ref(),
+    ];
+    Pubkey::find_program_address(seeds, program_id)
+}
+
+/// Derives the index addresses of every mint in `mints`, in order, as `find_index_address` would.
+pub fn find_index_addresses(program_id: &Pubkey, mints: &[Pubkey]) -> Vec<(Pubkey, u8)> {
+    mints.iter().map(|mint| find_index_address(program_id, mint)).collect()
+}
+
+/// Rebuilds the index address of `mint` from the bump stored in its index account,
+/// skipping the bump search of `find_index_address`.
+pub fn create_index_address(program_id: &Pubkey, mint: &Pubkey, bump_seed: u8) -> Result<Pubkey, PubkeyError> {
+    let seeds = &[
+        COLLECTION_SEED,
+        program_id.as_ref(),
+        mint.as_ref(),
+        &[bump_seed],
+    ];
+    Pubkey::create_program_address(seeds, program_id)
+}
+
 pub fn get_index_account(
     spl_token_mint_address: &Pubkey,
 ) -> (Pubkey, u8) {
-    let program_id = id();
+    find_index_address(&id(), spl_token_mint_address)
+}
+
+/// Derives the treasury address of the collection program deployed at `program_id`,
+/// using the seeds `["collection", "treasury", program_id]`.
+pub fn find_treasury_address(program_id: &Pubkey) -> (Pubkey, u8) {
     let seeds = &[
-        PREFIX.as_bytes(),
+        COLLECTION_SEED,
+        TREASURY_SEED,
         program_id.as_ref(),
-        spl_token_mint_address.as_ref(),
     ];
-    let (collection_address, bump_seed) = Pubkey::find_program_address(seeds, &program_id);
-    (collection_address, bump_seed)
+    Pubkey::find_program_address(seeds, program_id)
+}
+
+/// Derives the treasury config address of the collection program deployed at `program_id`,
+/// using the seeds `["collection", "treasury_config"]`.
+pub fn find_treasury_config_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    let seeds = &[
+        COLLECTION_SEED,
+        TREASURY_CONFIG_SEED,
+    ];
+    Pubkey::find_program_address(seeds, program_id)
+}
+
+/// Derives the allowlist address of `collection` for the collection program deployed at
+/// `program_id`, using the seeds `["collection", "allowlist", collection]`.
+pub fn find_allowlist_address(program_id: &Pubkey, collection: &Pubkey) -> (Pubkey, u8) {
+    let seeds = &[
+        COLLECTION_SEED,
+        ALLOWLIST_SEED,
+        collection.as_ref(),
+    ];
+    Pubkey::find_program_address(seeds, program_id)
+}
+
+/// Derives the cooldown address of `collection` for the collection program deployed at
+/// `program_id`, using the seeds `["collection", "cooldown", collection]`.
+pub fn find_cooldown_address(program_id: &Pubkey, collection: &Pubkey) -> (Pubkey, u8) {
+    let seeds = &[
+        COLLECTION_SEED,
+        COOLDOWN_SEED,
+        collection.as_ref(),
+    ];
+    Pubkey::find_program_address(seeds, program_id)
+}
+
+/// Derives the event authority of the collection program deployed at `program_id`, the
+/// signer of its `EmitEvent` self-invocations, using the seeds `["collection", "event_authority"]`.
+pub fn find_event_authority_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    let seeds = &[
+        COLLECTION_SEED,
+        EVENT_AUTHORITY_SEED,
+    ];
+    Pubkey::find_program_address(seeds, program_id)
 }
 
 pub fn get_treasury_account() -> (Pubkey, u8) {
-    const TREASURY: &str = "treasury";
     let program_id = id();
     let seeds = &[
-        PREFIX.as_bytes(),
-        TREASURY.as_bytes(),
+        COLLECTION_SEED,
+        TREASURY_SEED,
         program_id.as_ref(),
+        &[TREASURY_BUMP],
     ];
-    let (treasury_address, bump_seed) = Pubkey::find_program_address(seeds, &program_id);
-    (treasury_address, bump_seed)
+    let treasury_address = Pubkey::create_program_address(seeds, &program_id).unwrap();
+    (treasury_address, TREASURY_BUMP)
+}
+
+/// Address of the `ProgramData` account holding the upgrade authority of `program_id`,
+/// which doubles as the treasury manager.
+pub fn get_program_data_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id()).0
 }
@@ -0,0 +1,115 @@
+#![cfg(feature = "client")]
+//! Runs against a local validator with the program deployed:
+//! `solana-test-validator --bpf-program co111CrRL738X8TKrqmLcNBstgLFZjuMtZRBW2FGpbC target/deploy/collection.so`
+//! then `cargo test --features client -- --ignored`.
+
+use collection::client::{create_collection, get_collection, light_up_stars, projected_treasury, CollectionView};
+use collection::id;
+use collection::instruction::{create_collection_account, light_up_stars_custom, light_up_stars_hundred, CreateCollectionAccountArgs};
+use collection::processor::process_instruction;
+use collection::state::{CollectionAccountData, CollectionCategory};
+use collection::utils::get_treasury_account;
+use solana_client::rpc_client::RpcClient;
+use solana_program_test::*;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+#[test]
+fn test_collection_view_round_trip() {
+    let authority = Pubkey::new_unique();
+    let creator = Pubkey::new_unique();
+    let args = CreateCollectionAccountArgs::builder()
+        .title("test collection")
+        .symbol("TC")
+        .description("test collection description")
+        .icon_image("https://www.google.com")
+        .tags(vec!["art".to_string()])
+        .category(CollectionCategory::Music)
+        .creators(vec![(creator, 10_000)])
+        .build()
+        .unwrap();
+    let mut collection = CollectionAccountData::new(authority, &args, 1_634_000_000);
+    collection.stars = 42;
+
+    let view = CollectionView::from(&collection);
+    assert_eq!(view.authority, authority.to_string());
+    assert_eq!(view.stars, 42);
+    assert_eq!(view.category, Some("Music".to_string()));
+    assert_eq!(view.creators[0].address, creator.to_string());
+    assert_eq!(view.parent, None);
+
+    let json = serde_json::to_string(&view).unwrap();
+    assert!(json.contains(&format!("\"authority\":\"{}\"", authority)));
+    assert_eq!(serde_json::from_str::<CollectionView>(&json).unwrap(), view);
+}
+
+#[tokio::test]
+async fn test_projected_treasury_matches_light_ups() {
+    let program_test = ProgramTest::new("collection", id(), processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+    let payer_pubkey = context.payer.pubkey();
+    let (treasury_account, _) = get_treasury_account();
+
+    let collection = Keypair::new();
+    let args = CreateCollectionAccountArgs::builder()
+        .title("test collection")
+        .symbol("TC")
+        .description("test collection description")
+        .icon_image("https://www.google.com")
+        .star_price(1_000)
+        .build()
+        .unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[create_collection_account(id(), collection.pubkey(), payer_pubkey, args)],
+        Some(&payer_pubkey),
+        &[&context.payer, &collection],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let before = context.banks_client.get_balance(treasury_account).await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            light_up_stars_custom(id(), collection.pubkey(), payer_pubkey, treasury_account, 1_234),
+            light_up_stars_hundred(id(), collection.pubkey(), payer_pubkey, treasury_account),
+        ],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let after = context.banks_client.get_balance(treasury_account).await.unwrap();
+    assert_eq!(projected_treasury(1_334, 1_000), Some(after - before));
+
+    assert_eq!(projected_treasury(u64::MAX, 2), None);
+}
+
+#[test]
+#[ignore]
+fn test_create_collection_against_local_validator() {
+    let rpc = RpcClient::new_with_commitment("http://localhost:8899".to_string(), CommitmentConfig::confirmed());
+    let payer = Keypair::new();
+    let airdrop = rpc.request_airdrop(&payer.pubkey(), LAMPORTS_PER_SOL).unwrap();
+    while !rpc.confirm_transaction(&airdrop).unwrap() {}
+
+    let collection = Keypair::new();
+    let args = CreateCollectionAccountArgs::builder()
+        .title("test collection")
+        .symbol("TC")
+        .description("test collection description")
+        .icon_image("https://www.google.com")
+        .build()
+        .unwrap();
+    create_collection(&rpc, &payer, &collection, args).unwrap();
+    light_up_stars(&rpc, &payer, &collection.pubkey(), 10).unwrap();
+
+    let collection_data = get_collection(&rpc, &collection.pubkey()).unwrap();
+    assert_eq!(collection_data.title, "test collection".to_string());
+    assert_eq!(collection_data.authority, payer.pubkey());
+    assert_eq!(collection_data.stars, 10);
+}
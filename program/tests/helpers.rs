@@ -1,6 +1,11 @@
+#![cfg(feature = "client-builders")]
+use borsh::BorshSerialize;
 use solana_program_test::*;
 use solana_sdk::{
+    account::Account,
+    bpf_loader_upgradeable,
     program_pack::Pack, 
+    rent::Rent,
     pubkey::Pubkey, 
     signature::Signer,
     signer::keypair::Keypair, 
@@ -8,7 +13,115 @@ use solana_sdk::{
     transaction::Transaction, 
     transport,
 };
-use spl_associated_token_account::create_associated_token_account;
+use spl_associated_token_account::{get_associated_token_address, instruction::create_associated_token_account};
+use solana_sdk::borsh::try_from_slice_unchecked;
+use collection::id;
+use collection::utils::get_program_data_address;
+use collection::instruction::{create_collection_account, set_treasury_recipient, CreateCollectionAccountArgs};
+use collection::state::CollectionAccountData;
+
+/// Adds the `ProgramData` account the upgradeable loader would keep for the program,
+/// making `authority` its upgrade authority and so the treasury manager.
+pub fn add_program_data(program_test: &mut ProgramTest, authority: &Pubkey) {
+    let mut data = vec![3, 0, 0, 0];
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.push(1);
+    data.extend_from_slice(authority.as_ref());
+    program_test.add_account(
+        get_program_data_address(&id()),
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: bpf_loader_upgradeable::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+}
+
+pub fn collection_args() -> CreateCollectionAccountArgs {
+    CreateCollectionAccountArgs{
+        title: "test collection".to_string(),
+        symbol: "TC".to_string(),
+        description: "test collection description".to_string(),
+        icon_image: "https://www.google.com".to_string(),
+        header_image: Some("https://www.solana.com".to_string()),
+        short_description: Some("www.solana.com".to_string()),
+        banner: Some("https://www.solana.com".to_string()),
+        tags: Some(vec!["art".to_string(), "music".to_string()]),
+        royalty_bps: None,
+        star_price: None,
+        secondary_authority: None,
+        metadata_uri: None,
+        category: None,
+        creators: None,
+    }
+}
+
+pub async fn create_collection(
+    context: &mut ProgramTestContext,
+    collection: &Keypair,
+    args: CreateCollectionAccountArgs,
+) -> transport::Result<()> {
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            create_collection_account(id(), collection.pubkey(), context.payer.pubkey(), args),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, collection],
+        context.last_blockhash,
+    );
+
+    Ok(context.banks_client.process_transaction(tx).await?)
+}
+
+/// Binds `recipient` as the only destination of treasury withdrawals, the payer funds the config.
+pub async fn bind_treasury_recipient(
+    context: &mut ProgramTestContext,
+    treasury_manager: &Keypair,
+    recipient: &Pubkey,
+) -> transport::Result<()> {
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            set_treasury_recipient(id(), treasury_manager.pubkey(), context.payer.pubkey(), *recipient),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, treasury_manager],
+        context.last_blockhash,
+    );
+
+    Ok(context.banks_client.process_transaction(tx).await?)
+}
+
+pub async fn get_collection(
+    context: &mut ProgramTestContext,
+    collection: &Pubkey,
+) -> CollectionAccountData {
+    let account = context.banks_client
+        .get_account(*collection)
+        .await
+        .expect("get_account")
+        .expect("collection account not found");
+    try_from_slice_unchecked(&account.data).unwrap()
+}
+
+/// Rewrites `collection` as a version 0 account, allocated for nothing but its own fields,
+/// and returns its length.
+pub async fn make_legacy_collection(context: &mut ProgramTestContext, collection: &Pubkey) -> usize {
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let mut account = context.banks_client
+        .get_account(*collection)
+        .await
+        .expect("get_account")
+        .expect("collection account not found");
+    let mut collection_data: CollectionAccountData = try_from_slice_unchecked(&account.data).unwrap();
+    collection_data.version = 0;
+    account.data = collection_data.try_to_vec().unwrap();
+    account.lamports = rent.minimum_balance(account.data.len());
+    let legacy_len = account.data.len();
+    context.set_account(collection, &account.into());
+    legacy_len
+}
 
 pub async fn create_mint(
     context: &mut ProgramTestContext,
@@ -42,7 +155,7 @@ pub async fn create_mint(
         context.last_blockhash,
     );
 
-    context.banks_client.process_transaction(tx).await
+    Ok(context.banks_client.process_transaction(tx).await?)
 }
 
 pub async fn mint_tokens(
@@ -67,7 +180,7 @@ pub async fn mint_tokens(
         context.last_blockhash,
     );
 
-    context.banks_client.process_transaction(tx).await
+    Ok(context.banks_client.process_transaction(tx).await?)
 }
 
 pub async fn create_associated_account(
@@ -79,8 +192,9 @@ pub async fn create_associated_account(
         &[
             create_associated_token_account(
                 &context.payer.pubkey(),
-                &wallet_address,
-                &mint_address,
+                wallet_address,
+                mint_address,
+                &spl_token::id(),
             )
         ],
         Some(&context.payer.pubkey()),
@@ -88,5 +202,73 @@ pub async fn create_associated_account(
         context.last_blockhash,
     );
 
-    context.banks_client.process_transaction(tx).await
-}
\ No newline at end of file
+    Ok(context.banks_client.process_transaction(tx).await?)
+}
+
+/// Creates a mint with supply 1 held by the payer's associated token account.
+pub async fn create_nft(context: &mut ProgramTestContext) -> transport::Result<(Keypair, Pubkey)> {
+    let mint_keypair = Keypair::new();
+    let payer_pubkey = context.payer.pubkey();
+    create_mint(context, &mint_keypair, &payer_pubkey, 0, Some(&payer_pubkey)).await?;
+    create_associated_account(context, &payer_pubkey, &mint_keypair.pubkey()).await?;
+    let nft_ata = get_associated_token_address(&payer_pubkey, &mint_keypair.pubkey());
+    mint_tokens(context, &mint_keypair.pubkey(), &nft_ata, 1, &payer_pubkey, None).await?;
+    Ok((mint_keypair, nft_ata))
+}
+
+/// Creates a Token-2022 mint with supply 1 held by a fresh token account of the payer.
+pub async fn create_nft_2022(context: &mut ProgramTestContext) -> transport::Result<(Keypair, Pubkey)> {
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let mint_keypair = Keypair::new();
+    let token_keypair = Keypair::new();
+    let payer_pubkey = context.payer.pubkey();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer_pubkey,
+                &mint_keypair.pubkey(),
+                rent.minimum_balance(spl_token_2022::state::Mint::LEN),
+                spl_token_2022::state::Mint::LEN as u64,
+                &spl_token_2022::id(),
+            ),
+            spl_token_2022::instruction::initialize_mint(
+                &spl_token_2022::id(),
+                &mint_keypair.pubkey(),
+                &payer_pubkey,
+                Some(&payer_pubkey),
+                0,
+            )
+            .unwrap(),
+            system_instruction::create_account(
+                &payer_pubkey,
+                &token_keypair.pubkey(),
+                rent.minimum_balance(spl_token_2022::state::Account::LEN),
+                spl_token_2022::state::Account::LEN as u64,
+                &spl_token_2022::id(),
+            ),
+            spl_token_2022::instruction::initialize_account(
+                &spl_token_2022::id(),
+                &token_keypair.pubkey(),
+                &mint_keypair.pubkey(),
+                &payer_pubkey,
+            )
+            .unwrap(),
+            spl_token_2022::instruction::mint_to(
+                &spl_token_2022::id(),
+                &mint_keypair.pubkey(),
+                &token_keypair.pubkey(),
+                &payer_pubkey,
+                &[],
+                1,
+            )
+            .unwrap(),
+        ],
+        Some(&payer_pubkey),
+        &[&context.payer, &mint_keypair, &token_keypair],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await?;
+
+    Ok((mint_keypair, token_keypair.pubkey()))
+}
@@ -0,0 +1,819 @@
+#![cfg(feature = "client-builders")]
+use borsh::{BorshDeserialize, BorshSerialize};
+use collection::{
+    id,
+    instruction::{
+        add_tag, add_to_allowlist, canonical_uri, close_account, close_allowlist_account, close_cooldown_account, close_index_account,
+        create_collection_account,
+        create_collection_with_account, freeze_collection, include_token, include_token_batch, is_valid_uri,
+        light_up_stars_custom, light_up_stars_hundred, light_up_stars_multi, light_up_stars_once, light_up_stars_once_with_cooldown, light_up_stars_ten_thousand,
+        light_up_stars_thousand, light_up_stars_with_token, migrate_account, move_token, clear_parent, set_parent, remove_from_allowlist, remove_tag,
+        remove_token, set_banner,
+        adjust_stars, clear_collection_mint, clear_optional_metadata, set_collection_mint, set_category, set_cooldown, set_description, set_treasury_recipient, set_metadata_uri, set_star_price, set_star_token, set_tags, set_verified, transfer_authority,
+        update_collection_metadata, update_collection_metadata_with_realloc, validate_metadata, withdraw, withdraw_amount, withdraw_token, CollectionInstruction,
+        CreateCollectionAccountArgs, UpdateCollectionMetadataArgs, MAX_CREATE_COLLECTION_ARGS_LEN, MAX_LIGHT_UP_MULTI_COLLECTIONS,
+    },
+    error::CollectionError,
+    event::{append_event_accounts, event_instruction, CollectionEvent},
+    state::{AccountType, CollectionAccountData, CollectionCategory},
+    utils::{find_event_authority_address, get_program_data_address, get_treasury_account},
+};
+use proptest::prelude::*;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey, rent::Rent, system_instruction, system_program};
+use solana_sdk::{message::Message, packet::PACKET_DATA_SIZE};
+use std::collections::HashSet;
+
+fn collection_args() -> CreateCollectionAccountArgs {
+    CreateCollectionAccountArgs{
+        title: "test collection".to_string(),
+        symbol: "TC".to_string(),
+        description: "test collection description".to_string(),
+        icon_image: "https://www.google.com".to_string(),
+        header_image: None,
+        short_description: None,
+        banner: None,
+        tags: None,
+        royalty_bps: None,
+        star_price: None,
+        secondary_authority: None,
+        metadata_uri: None,
+        category: None,
+        creators: None,
+    }
+}
+
+#[test]
+fn test_is_valid_uri() {
+    assert!(is_valid_uri("https://www.solana.com/icon.png"));
+    assert!(is_valid_uri("ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi"));
+    assert!(is_valid_uri("ar://5Dr5BAwBwFwGbnA9MMyMntKUa4mKwLZa2UDS2Ct8Px8"));
+    assert!(!is_valid_uri("http://www.solana.com/icon.png"));
+    assert!(!is_valid_uri("javascript:alert(1)"));
+    assert!(!is_valid_uri("not a url"));
+    assert!(!is_valid_uri(""));
+    assert!(!is_valid_uri("https://"));
+    assert!(!is_valid_uri("ipfs://  "));
+}
+
+#[test]
+fn test_image_uris_are_validated() {
+    assert!(collection_args().is_valid());
+
+    let mut args = collection_args();
+    args.icon_image = "http://www.google.com".to_string();
+    assert!(!args.is_valid());
+
+    let mut args = collection_args();
+    args.header_image = Some("ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi".to_string());
+    args.banner = Some("ar://5Dr5BAwBwFwGbnA9MMyMntKUa4mKwLZa2UDS2Ct8Px8".to_string());
+    assert!(args.is_valid());
+
+    let mut args = collection_args();
+    args.header_image = Some("www.solana.com".to_string());
+    assert!(!args.is_valid());
+
+    let mut args = collection_args();
+    args.banner = Some("http://www.solana.com".to_string());
+    assert!(!args.is_valid());
+}
+
+#[test]
+fn test_empty_image_uris_are_rejected() {
+    for icon_image in ["", " ", "https://", "ar://"] {
+        let mut args = collection_args();
+        args.icon_image = icon_image.to_string();
+        assert!(!args.is_valid());
+    }
+
+    for image in ["", "ipfs://"] {
+        let mut args = collection_args();
+        args.header_image = Some(image.to_string());
+        assert!(!args.is_valid());
+
+        let mut args = collection_args();
+        args.banner = Some(image.to_string());
+        assert!(!args.is_valid());
+    }
+}
+
+#[test]
+fn test_lengths_count_characters() {
+    // 32 CJK characters are 96 bytes
+    let mut args = collection_args();
+    args.title = "收藏".repeat(16);
+    assert!(args.is_valid());
+    args.title.push('品');
+    assert!(!args.is_valid());
+
+    let mut args = collection_args();
+    args.description = "描述".repeat(400);
+    assert!(args.is_valid());
+    args.description.push('述');
+    assert!(!args.is_valid());
+
+    let mut args = collection_args();
+    args.tags = Some(vec!["🎵".repeat(19)]);
+    assert!(args.is_valid());
+}
+
+#[test]
+fn test_required_fields_must_not_be_empty() {
+    let mut args = collection_args();
+    args.title = "   ".to_string();
+    assert!(!args.is_valid());
+    args.title = "t".to_string();
+    assert!(args.is_valid());
+
+    let mut args = collection_args();
+    args.symbol = " ".to_string();
+    assert!(!args.is_valid());
+    args.symbol = "T".to_string();
+    assert!(args.is_valid());
+
+    let mut args = collection_args();
+    args.icon_image = "".to_string();
+    assert!(!args.is_valid());
+}
+
+#[test]
+fn test_tag_length_boundary() {
+    let mut args = collection_args();
+    args.tags = Some(vec!["a".repeat(20)]);
+    assert!(args.is_valid());
+    args.tags = Some(vec!["a".repeat(21)]);
+    assert!(!args.is_valid());
+}
+
+#[test]
+fn test_duplicate_tags_are_rejected() {
+    let mut args = collection_args();
+    args.tags = Some(vec!["art".to_string(), "music".to_string(), "Art".to_string()]);
+    assert!(!args.is_valid());
+    args.tags = Some(vec!["art".to_string(), "music".to_string(), "artist".to_string()]);
+    assert!(args.is_valid());
+}
+
+#[test]
+fn test_light_up_stars_thousand() {
+    let collection_account = Pubkey::new_unique();
+    let source_account = Pubkey::new_unique();
+    let (treasury_account, _) = get_treasury_account();
+    let ix = light_up_stars_thousand(id(), collection_account, source_account, treasury_account);
+
+    assert!(matches!(
+        CollectionInstruction::unpack(&ix.data).unwrap(),
+        CollectionInstruction::LightUpStarsThousand
+    ));
+    assert_eq!(ix.accounts.len(), 4);
+    assert_eq!(ix.accounts[0].pubkey, collection_account);
+    assert!(ix.accounts[1].is_signer);
+    assert_eq!(ix.accounts[2].pubkey, treasury_account);
+    assert!(ix.accounts[2].is_writable);
+    assert_eq!(ix.accounts[3].pubkey, system_program::id());
+    assert!(!ix.accounts[3].is_writable);
+}
+
+#[test]
+fn test_light_up_stars_ten_thousand() {
+    let collection_account = Pubkey::new_unique();
+    let source_account = Pubkey::new_unique();
+    let (treasury_account, _) = get_treasury_account();
+    let ix = light_up_stars_ten_thousand(id(), collection_account, source_account, treasury_account);
+
+    assert!(matches!(
+        CollectionInstruction::unpack(&ix.data).unwrap(),
+        CollectionInstruction::LightUpStarsTenThousand
+    ));
+    assert_eq!(ix.accounts.len(), 4);
+    assert_eq!(ix.accounts[0].pubkey, collection_account);
+    assert!(ix.accounts[1].is_signer);
+    assert_eq!(ix.accounts[2].pubkey, treasury_account);
+    assert!(ix.accounts[2].is_writable);
+    assert_eq!(ix.accounts[3].pubkey, system_program::id());
+    assert!(!ix.accounts[3].is_writable);
+}
+
+#[test]
+fn test_withdraw_amount_mirrors_withdraw_accounts() {
+    let (treasury_account, _) = get_treasury_account();
+    let (manager, recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let ix = withdraw_amount(id(), manager, treasury_account, recipient, 5);
+    assert_eq!(ix.accounts, withdraw(id(), manager, treasury_account, recipient).accounts);
+    assert_eq!(ix.accounts[3].pubkey, get_program_data_address(&id()));
+    assert_eq!(ix.accounts[4].pubkey, system_program::id());
+}
+
+#[test]
+fn test_event_instruction() {
+    let event = CollectionEvent::StarsLit { collection: Pubkey::new_unique(), delta: 100, total: 142 };
+    let ix = event_instruction(id(), event.clone());
+    let (event_authority, _) = find_event_authority_address(&id());
+
+    match CollectionInstruction::unpack(&ix.data).unwrap() {
+        CollectionInstruction::EmitEvent(emitted) => assert_eq!(emitted, event),
+        _ => panic!("expected an EmitEvent instruction"),
+    }
+    assert_eq!(ix.accounts.len(), 1);
+    assert_eq!(ix.accounts[0].pubkey, event_authority);
+    assert!(ix.accounts[0].is_signer);
+
+    let collection_account = Pubkey::new_unique();
+    let mut ix = light_up_stars_thousand(id(), collection_account, Pubkey::new_unique(), get_treasury_account().0);
+    append_event_accounts(&mut ix);
+    assert_eq!(ix.accounts.len(), 6);
+    assert_eq!(ix.accounts[4].pubkey, event_authority);
+    assert!(!ix.accounts[4].is_signer);
+    assert_eq!(ix.accounts[5].pubkey, id());
+}
+
+#[test]
+fn test_unpack_malformed_instruction() {
+    assert_eq!(CollectionInstruction::unpack(&[]).err(), Some(ProgramError::InvalidInstructionData));
+    assert_eq!(CollectionInstruction::unpack(&[255]).err(), Some(ProgramError::InvalidInstructionData));
+    // LightUpStarsCustom with a truncated amount
+    assert_eq!(CollectionInstruction::unpack(&[9, 1, 2]).err(), Some(ProgramError::InvalidInstructionData));
+}
+
+#[test]
+fn test_instruction_tags() {
+    let instructions = vec![
+        (CollectionInstruction::CreateCollectionAccount(collection_args()), 0),
+        (CollectionInstruction::IncludeToken, 1),
+        (CollectionInstruction::LightUpStarsOnce, 2),
+        (CollectionInstruction::LightUpStarsHundred, 3),
+        (CollectionInstruction::LightUpStarsThousand, 4),
+        (CollectionInstruction::CloseAccount(AccountType::CollectionAccount), 5),
+        (CollectionInstruction::Withdraw, 6),
+        (CollectionInstruction::UpdateCollectionMetadata(UpdateCollectionMetadataArgs::default()), 7),
+        (CollectionInstruction::RemoveToken, 8),
+        (CollectionInstruction::LightUpStarsCustom(5), 9),
+        (CollectionInstruction::TransferAuthority(Pubkey::new_unique()), 10),
+        (CollectionInstruction::IncludeTokenBatch(2), 11),
+        (CollectionInstruction::WithdrawAmount(5), 12),
+        (CollectionInstruction::FreezeCollection, 13),
+        (CollectionInstruction::SetTags(vec!["art".to_string()]), 14),
+        (CollectionInstruction::LightUpStarsWithToken(5), 15),
+        (CollectionInstruction::SetStarPrice(5), 16),
+        (CollectionInstruction::ValidateMetadata(collection_args()), 17),
+        (CollectionInstruction::SetVerified(true), 18),
+        (CollectionInstruction::AddTag("art".to_string()), 19),
+        (CollectionInstruction::RemoveTag("art".to_string()), 20),
+        (CollectionInstruction::EmitEvent(CollectionEvent::Withdrawn { amount: 5 }), 21),
+        (CollectionInstruction::MoveToken, 22),
+        (CollectionInstruction::SetDescription("description".to_string()), 23),
+        (CollectionInstruction::SetBanner(None), 24),
+        (CollectionInstruction::LightUpStarsTenThousand, 25),
+        (CollectionInstruction::SetMetadataUri(None), 26),
+        (CollectionInstruction::AddToAllowlist(Pubkey::new_unique()), 27),
+        (CollectionInstruction::RemoveFromAllowlist(Pubkey::new_unique()), 28),
+        (CollectionInstruction::AdjustStars(-1), 29),
+        (CollectionInstruction::SetCategory(Some(CollectionCategory::Art)), 30),
+        (CollectionInstruction::SetTreasuryRecipient(Pubkey::new_unique()), 31),
+        (CollectionInstruction::MigrateAccount, 32),
+        (CollectionInstruction::SetParent(Some(Pubkey::new_unique())), 33),
+        (CollectionInstruction::SetCooldown(10), 34),
+        (CollectionInstruction::ClearOptionalMetadata, 35),
+        (CollectionInstruction::LightUpStarsMulti(vec![1, 2]), 36),
+        (CollectionInstruction::SetCollectionMint(Some(Pubkey::new_unique())), 37),
+        (CollectionInstruction::SetStarToken(Some((Pubkey::new_unique(), 1_000))), 38),
+        (CollectionInstruction::WithdrawToken(200), 39),
+    ];
+    for (instruction, tag) in instructions {
+        let data = instruction.pack();
+        assert_eq!(data[0], tag);
+        assert_eq!(instruction.tag(), tag);
+        assert_eq!(CollectionInstruction::unpack(&data).unwrap().pack(), data);
+    }
+    assert_eq!(CollectionInstruction::LightUpStarsCustom(5).pack(), vec![9, 5, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(CollectionInstruction::unpack(&[40]).err(), Some(ProgramError::InvalidInstructionData));
+}
+
+#[test]
+fn test_variant_names() {
+    let instructions = vec![
+        (CollectionInstruction::CreateCollectionAccount(collection_args()), "Create Collection Account"),
+        (CollectionInstruction::IncludeToken, "Include Token"),
+        (CollectionInstruction::LightUpStarsOnce, "Light Up Stars Once"),
+        (CollectionInstruction::LightUpStarsHundred, "Light Up Stars One Hundred"),
+        (CollectionInstruction::LightUpStarsThousand, "Light Up Stars One Thousand"),
+        (CollectionInstruction::CloseAccount(AccountType::CollectionAccount), "Close Account"),
+        (CollectionInstruction::Withdraw, "Withdraw"),
+        (CollectionInstruction::UpdateCollectionMetadata(UpdateCollectionMetadataArgs::default()), "Update Collection Metadata"),
+        (CollectionInstruction::RemoveToken, "Remove Token"),
+        (CollectionInstruction::LightUpStarsCustom(5), "Light Up Stars Custom"),
+        (CollectionInstruction::TransferAuthority(Pubkey::new_unique()), "Transfer Authority"),
+        (CollectionInstruction::IncludeTokenBatch(2), "Include Token Batch"),
+        (CollectionInstruction::WithdrawAmount(5), "Withdraw Amount"),
+        (CollectionInstruction::FreezeCollection, "Freeze Collection"),
+        (CollectionInstruction::SetTags(vec!["art".to_string()]), "Set Tags"),
+        (CollectionInstruction::LightUpStarsWithToken(5), "Light Up Stars With Token"),
+        (CollectionInstruction::SetStarPrice(5), "Set Star Price"),
+        (CollectionInstruction::ValidateMetadata(collection_args()), "Validate Metadata"),
+        (CollectionInstruction::SetVerified(true), "Set Verified"),
+        (CollectionInstruction::AddTag("art".to_string()), "Add Tag"),
+        (CollectionInstruction::RemoveTag("art".to_string()), "Remove Tag"),
+        (CollectionInstruction::EmitEvent(CollectionEvent::Withdrawn { amount: 5 }), "Emit Event"),
+        (CollectionInstruction::MoveToken, "Move Token"),
+        (CollectionInstruction::SetDescription("description".to_string()), "Set Description"),
+        (CollectionInstruction::SetBanner(None), "Set Banner"),
+        (CollectionInstruction::LightUpStarsTenThousand, "Light Up Stars Ten Thousand"),
+        (CollectionInstruction::SetMetadataUri(None), "Set Metadata Uri"),
+        (CollectionInstruction::AddToAllowlist(Pubkey::new_unique()), "Add To Allowlist"),
+        (CollectionInstruction::RemoveFromAllowlist(Pubkey::new_unique()), "Remove From Allowlist"),
+        (CollectionInstruction::AdjustStars(-1), "Adjust Stars"),
+        (CollectionInstruction::SetCategory(Some(CollectionCategory::Art)), "Set Category"),
+        (CollectionInstruction::SetTreasuryRecipient(Pubkey::new_unique()), "Set Treasury Recipient"),
+        (CollectionInstruction::MigrateAccount, "Migrate Account"),
+        (CollectionInstruction::SetParent(None), "Set Parent"),
+        (CollectionInstruction::SetCooldown(0), "Set Cooldown"),
+        (CollectionInstruction::ClearOptionalMetadata, "Clear Optional Metadata"),
+        (CollectionInstruction::LightUpStarsMulti(vec![1]), "Light Up Stars Multi"),
+        (CollectionInstruction::SetCollectionMint(None), "Set Collection Mint"),
+        (CollectionInstruction::SetStarToken(None), "Set Star Token"),
+        (CollectionInstruction::WithdrawToken(1), "Withdraw Token"),
+    ];
+    for (instruction, name) in instructions {
+        assert_eq!(instruction.variant_name(), name);
+    }
+}
+
+/// Every builder behind the `client-builders` feature targets the program with its own tag.
+#[test]
+fn test_instruction_builders() {
+    let key = Pubkey::new_unique;
+    let builders = vec![
+        (create_collection_account(id(), key(), key(), collection_args()), 0),
+        (include_token(id(), key(), key(), key(), key(), key()), 1),
+        (light_up_stars_once(id(), key()), 2),
+        (light_up_stars_hundred(id(), key(), key(), key()), 3),
+        (light_up_stars_thousand(id(), key(), key(), key()), 4),
+        (close_account(id(), key(), key(), key(), AccountType::CollectionAccount), 5),
+        (close_index_account(id(), key(), key(), key(), key()), 5),
+        (withdraw(id(), key(), key(), key()), 6),
+        (update_collection_metadata(id(), key(), key(), UpdateCollectionMetadataArgs::default()), 7),
+        (update_collection_metadata_with_realloc(id(), key(), key(), key(), UpdateCollectionMetadataArgs::default()), 7),
+        (remove_token(id(), key(), key(), key(), key(), key()), 8),
+        (light_up_stars_custom(id(), key(), key(), key(), 5), 9),
+        (transfer_authority(id(), key(), key(), key()), 10),
+        (include_token_batch(id(), key(), key(), &[(key(), key())], key()), 11),
+        (withdraw_amount(id(), key(), key(), key(), 5), 12),
+        (freeze_collection(id(), key(), key()), 13),
+        (set_tags(id(), key(), key(), vec!["art".to_string()]), 14),
+        (light_up_stars_with_token(id(), key(), key(), key(), key(), 5), 15),
+        (set_star_price(id(), key(), key(), 5), 16),
+        (validate_metadata(id(), collection_args()), 17),
+        (set_verified(id(), key(), key(), true), 18),
+        (add_tag(id(), key(), key(), "art".to_string()), 19),
+        (remove_tag(id(), key(), key(), "art".to_string()), 20),
+        (move_token(id(), key(), key(), key(), key(), key()), 22),
+        (set_description(id(), key(), key(), "description".to_string()), 23),
+        (set_banner(id(), key(), key(), None), 24),
+        (light_up_stars_ten_thousand(id(), key(), key(), key()), 25),
+        (set_metadata_uri(id(), key(), key(), None), 26),
+        (add_to_allowlist(id(), key(), key(), key(), key()), 27),
+        (remove_from_allowlist(id(), key(), key(), key()), 28),
+        (adjust_stars(id(), key(), key(), -1), 29),
+        (set_category(id(), key(), key(), None), 30),
+        (set_treasury_recipient(id(), key(), key(), key()), 31),
+        (migrate_account(id(), key(), key(), key()), 32),
+        (set_parent(id(), key(), key(), key(), key()), 33),
+        (clear_parent(id(), key(), key()), 33),
+        (set_cooldown(id(), key(), key(), key(), 10), 34),
+        (clear_optional_metadata(id(), key(), key(), key()), 35),
+        (light_up_stars_multi(id(), key(), key(), &[(key(), 1), (key(), 2)]), 36),
+        (set_collection_mint(id(), key(), key(), key()), 37),
+        (clear_collection_mint(id(), key(), key()), 37),
+        (set_star_token(id(), key(), key(), Some((key(), 1_000))), 38),
+        (withdraw_token(id(), key(), key(), key(), key(), 200), 39),
+        (light_up_stars_once_with_cooldown(id(), key(), key()), 2),
+        (close_allowlist_account(id(), key(), key(), key()), 5),
+        (close_cooldown_account(id(), key(), key(), key()), 5),
+    ];
+    for (ix, tag) in builders {
+        assert_eq!(ix.program_id, id());
+        assert_eq!(CollectionInstruction::unpack(&ix.data).unwrap().tag(), tag);
+    }
+}
+
+#[test]
+fn test_create_collection_account_args_builder() {
+    let args = CreateCollectionAccountArgs::builder()
+        .title("test collection")
+        .symbol("TC")
+        .description("test collection description")
+        .icon_image("https://www.google.com")
+        .build()
+        .unwrap();
+    assert_eq!(args, collection_args());
+
+    let args = CreateCollectionAccountArgs::builder()
+        .title("test collection")
+        .symbol("TC")
+        .icon_image("https://www.google.com")
+        .banner("https://www.solana.com")
+        .tags(vec!["art".to_string()])
+        .build()
+        .unwrap();
+    assert_eq!(args.banner, Some("https://www.solana.com".to_string()));
+    assert_eq!(args.tags, Some(vec!["art".to_string()]));
+
+    // the title is required
+    assert_eq!(
+        CreateCollectionAccountArgs::builder()
+            .symbol("TC")
+            .icon_image("https://www.google.com")
+            .build(),
+        Err(CollectionError::InvalidMetadata)
+    );
+    // more tags than an account has room for
+    assert_eq!(
+        CreateCollectionAccountArgs::builder()
+            .title("test collection")
+            .symbol("TC")
+            .icon_image("https://www.google.com")
+            .tags((0..7).map(|i| format!("tag{}", i)).collect())
+            .build(),
+        Err(CollectionError::InvalidMetadata)
+    );
+}
+
+/// Deployed programs decode these exact bytes, any change here breaks existing clients.
+#[test]
+fn test_create_collection_account_args_wire_format() {
+    let args = CreateCollectionAccountArgs {
+        title: "T".to_string(),
+        symbol: "S".to_string(),
+        description: "".to_string(),
+        icon_image: "ar://i".to_string(),
+        ..CreateCollectionAccountArgs::default()
+    };
+    let expected: Vec<u8> = [
+        &[1, 0, 0, 0][..], b"T",
+        &[1, 0, 0, 0], b"S",
+        &[0, 0, 0, 0],
+        &[6, 0, 0, 0], b"ar://i",
+        // header_image, short_description, banner, tags, royalty_bps, star_price, secondary_authority,
+        // metadata_uri, category, creators
+        &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    ].concat();
+    assert_eq!(args.try_to_vec().unwrap(), expected);
+    assert_eq!(CreateCollectionAccountArgs::try_from_slice(&expected).unwrap(), args);
+
+    let args = CreateCollectionAccountArgs {
+        title: "T".to_string(),
+        symbol: "S".to_string(),
+        description: "d".to_string(),
+        icon_image: "ar://i".to_string(),
+        header_image: Some("ar://h".to_string()),
+        short_description: Some("s".to_string()),
+        banner: Some("ar://b".to_string()),
+        tags: Some(vec!["t".to_string()]),
+        royalty_bps: Some(500),
+        star_price: Some(7),
+        secondary_authority: Some(Pubkey::new_from_array([9; 32])),
+        metadata_uri: Some("ar://m".to_string()),
+        category: Some(CollectionCategory::Pfp),
+        creators: Some(vec![(Pubkey::new_from_array([8; 32]), 10_000)]),
+    };
+    let expected: Vec<u8> = [
+        &[1, 0, 0, 0][..], b"T",
+        &[1, 0, 0, 0], b"S",
+        &[1, 0, 0, 0], b"d",
+        &[6, 0, 0, 0], b"ar://i",
+        &[1, 6, 0, 0, 0], b"ar://h",
+        &[1, 1, 0, 0, 0], b"s",
+        &[1, 6, 0, 0, 0], b"ar://b",
+        &[1, 1, 0, 0, 0, 1, 0, 0, 0], b"t",
+        &[1, 0xf4, 0x01],
+        &[1, 7, 0, 0, 0, 0, 0, 0, 0],
+        &[1], &[9; 32],
+        &[1, 6, 0, 0, 0], b"ar://m",
+        &[1, 3],
+        &[1, 1, 0, 0, 0], &[8; 32], &[0x10, 0x27],
+    ].concat();
+    assert_eq!(args.try_to_vec().unwrap(), expected);
+    assert_eq!(CreateCollectionAccountArgs::try_from_slice(&expected).unwrap(), args);
+}
+
+#[test]
+fn test_royalty_bps() {
+    let mut args = collection_args();
+    args.royalty_bps = Some(500);
+    assert!(args.is_valid());
+    args.royalty_bps = Some(10_000);
+    assert!(args.is_valid());
+    args.royalty_bps = Some(10_001);
+    assert!(!args.is_valid());
+}
+
+#[test]
+fn test_creators() {
+    let mut args = collection_args();
+    args.creators = Some(vec![(Pubkey::new_unique(), 6_000), (Pubkey::new_unique(), 4_000)]);
+    assert!(args.is_valid());
+    args.creators = Some(vec![(Pubkey::new_unique(), 10_000)]);
+    assert!(args.is_valid());
+
+    // the shares must cover exactly 100%
+    args.creators = Some(vec![(Pubkey::new_unique(), 6_000), (Pubkey::new_unique(), 3_999)]);
+    assert!(!args.check_creators());
+    args.creators = Some(vec![(Pubkey::new_unique(), 6_000), (Pubkey::new_unique(), 4_001)]);
+    assert!(!args.check_creators());
+    args.creators = Some(vec![(Pubkey::new_unique(), u16::MAX), (Pubkey::new_unique(), u16::MAX)]);
+    assert!(!args.check_creators());
+    args.creators = Some(vec![]);
+    assert!(!args.is_valid());
+
+    // at most five creators
+    args.creators = Some((0..5).map(|_| (Pubkey::new_unique(), 2_000)).collect());
+    assert!(args.is_valid());
+    args.creators = Some((0..6).map(|i| (Pubkey::new_unique(), if i == 0 { 5_000 } else { 1_000 })).collect());
+    assert!(!args.is_valid());
+    assert_eq!(
+        CreateCollectionAccountArgs::builder()
+            .title("test collection")
+            .symbol("TC")
+            .icon_image("https://www.google.com")
+            .creators((0..6).map(|i| (Pubkey::new_unique(), if i == 0 { 5_000 } else { 1_000 })).collect())
+            .build(),
+        Err(CollectionError::InvalidMetadata)
+    );
+}
+
+#[test]
+fn test_args_without_royalty_bps() {
+    let mut args = collection_args();
+    args.royalty_bps = Some(500);
+    let data = args.try_to_vec().unwrap();
+    assert_eq!(CreateCollectionAccountArgs::try_from_slice(&data).unwrap(), args);
+
+    // args encoded before royalties existed end after the tags
+    let legacy = &data[..data.len() - 8];
+    assert_eq!(CreateCollectionAccountArgs::try_from_slice(legacy).unwrap().royalty_bps, None);
+}
+
+#[test]
+fn test_star_price() {
+    let mut args = collection_args();
+    args.star_price = Some(1);
+    assert!(args.is_valid());
+    args.star_price = Some(0);
+    assert!(!args.is_valid());
+
+    // args encoded before star prices existed end after the royalty
+    args.star_price = Some(42);
+    let data = args.try_to_vec().unwrap();
+    assert_eq!(CreateCollectionAccountArgs::try_from_slice(&data).unwrap().star_price, Some(42));
+    let legacy = &data[..data.len() - 13];
+    assert_eq!(CreateCollectionAccountArgs::try_from_slice(legacy).unwrap().star_price, None);
+}
+
+#[test]
+fn test_symbol_is_uppercase_alphanumeric() {
+    let mut args = collection_args();
+    args.symbol = "ABC1".to_string();
+    assert!(args.is_valid());
+    args.symbol = "A".repeat(10);
+    assert!(args.is_valid());
+    args.symbol.push('A');
+    assert!(!args.is_valid());
+
+    for symbol in ["abc", "A-B", "A B", "ÄB", "🎨"] {
+        args.symbol = symbol.to_string();
+        assert!(!args.is_valid(), "{}", symbol);
+    }
+}
+
+#[test]
+fn test_normalized() {
+    let mut args = collection_args();
+    args.title = "  My   NFTs \t".to_string();
+    args.description = "\n description ".to_string();
+    args.banner = Some(" https://www.solana.com ".to_string());
+    args.tags = Some(vec![" art".to_string(), "music  ".to_string()]);
+    let args = args.normalized();
+    assert_eq!(args.title, "My NFTs".to_string());
+    assert_eq!(args.description, "description".to_string());
+    assert_eq!(args.banner, Some("https://www.solana.com".to_string()));
+    assert_eq!(args.tags, Some(vec!["art".to_string(), "music".to_string()]));
+    assert_eq!(args.symbol, "TC".to_string());
+
+    // the builder normalizes before validating
+    let args = CreateCollectionAccountArgs::builder()
+        .title("  My NFTs  ")
+        .symbol(" TC ")
+        .icon_image(" https://www.google.com")
+        .build()
+        .unwrap();
+    assert_eq!(args.title, "My NFTs".to_string());
+    assert_eq!(args.symbol, "TC".to_string());
+    assert_eq!(args.icon_image, "https://www.google.com".to_string());
+}
+
+#[test]
+fn test_normalized_strips_control_characters() {
+    let mut args = collection_args();
+    args.description = "# Drops\0\n\n**new** pieces\x07 every `week`\r\n".to_string();
+    args.short_description = Some("\x1b[1mbold\x1b[0m".to_string());
+    let args = args.normalized();
+    assert_eq!(args.description, "# Drops\n\n**new** pieces every `week`".to_string());
+    assert_eq!(args.short_description, Some("[1mbold[0m".to_string()));
+}
+
+#[test]
+fn test_normalized_gateway_uris() {
+    let mut args = collection_args();
+    args.icon_image = " https://ipfs.io/ipfs/bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi".to_string();
+    args.header_image = Some("https://arweave.net/bNbA3TEQVL60xlgCcqdz4ZPHFZ711cZ3hmkpGttDt_U".to_string());
+    args.banner = Some("https://www.solana.com/ipfs/QmHash".to_string());
+    let args = args.normalized();
+    assert_eq!(args.icon_image, "ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi".to_string());
+    assert_eq!(args.header_image, Some("ar://bNbA3TEQVL60xlgCcqdz4ZPHFZ711cZ3hmkpGttDt_U".to_string()));
+    // unknown hosts are left alone
+    assert_eq!(args.banner, Some("https://www.solana.com/ipfs/QmHash".to_string()));
+
+    assert_eq!(canonical_uri("https://ipfs.io/ipfs/"), "https://ipfs.io/ipfs/".to_string());
+    assert_eq!(canonical_uri("ipfs://QmHash"), "ipfs://QmHash".to_string());
+}
+
+#[test]
+fn test_rent_exempt_lamports() {
+    let rent = Rent::default();
+    let mut args = collection_args();
+    let without_banner = args.rent_exempt_lamports(&rent);
+    assert_eq!(
+        without_banner,
+        rent.minimum_balance(CollectionAccountData::new(Pubkey::default(), &args, 0).try_to_vec().unwrap().len())
+    );
+
+    args.banner = Some("https://www.solana.com".to_string());
+    let with_banner = args.rent_exempt_lamports(&rent);
+    // the banner adds its length prefix and 22 bytes of URI
+    assert_eq!(with_banner - without_banner, (4 + 22) * rent.lamports_per_byte_year * rent.exemption_threshold as u64);
+    assert!(with_banner < rent.minimum_balance(CollectionAccountData::LEN));
+}
+
+#[test]
+fn test_serialized_len() {
+    // every field at its limit, as large as any args `is_valid` accepts
+    let uri = format!("https://{}", "a".repeat(192));
+    let args = CreateCollectionAccountArgs {
+        title: "🎵".repeat(32),
+        symbol: "A".repeat(10),
+        description: "🎵".repeat(800),
+        icon_image: uri.clone(),
+        header_image: Some(uri.clone()),
+        short_description: Some("🎵".repeat(800)),
+        banner: Some(uri.clone()),
+        tags: Some(["🎵", "🎨", "🎸", "🎹", "🎺", "🎻"].iter().map(|c| c.repeat(20)).collect()),
+        royalty_bps: Some(10_000),
+        star_price: Some(1),
+        secondary_authority: Some(Pubkey::new_unique()),
+        metadata_uri: Some(uri),
+        category: Some(CollectionCategory::Other),
+        creators: Some((0..5).map(|_| (Pubkey::new_unique(), 2_000)).collect()),
+    };
+    assert!(args.is_valid());
+    assert_eq!(args.serialized_len(), 8105);
+    assert_eq!(args.serialized_len() + 1, CollectionInstruction::CreateCollectionAccount(args.clone()).pack().len());
+
+    assert_eq!(
+        CreateCollectionAccountArgs::builder()
+            .title(args.title)
+            .symbol(args.symbol)
+            .description(args.description)
+            .icon_image(args.icon_image)
+            .build(),
+        Err(CollectionError::ArgsTooLarge)
+    );
+
+    // the largest accepted args exactly fill the packet of `create_collection_with_account`
+    let builder = CreateCollectionAccountArgs::builder()
+        .title("T")
+        .symbol("S")
+        .description("d".repeat(800))
+        .icon_image("ar://i");
+    let args = builder.clone().short_description("ss").build().unwrap();
+    assert_eq!(args.serialized_len(), MAX_CREATE_COLLECTION_ARGS_LEN);
+    let payer = Pubkey::new_unique();
+    let ixs = create_collection_with_account(id(), payer, Pubkey::new_unique(), &Rent::default(), args.space(), args);
+    let message = Message::new(&ixs, Some(&payer));
+    let signatures = message.header.num_required_signatures as usize;
+    assert_eq!(1 + signatures * 64 + message.serialize().len(), PACKET_DATA_SIZE);
+    assert_eq!(builder.short_description("sss").build(), Err(CollectionError::ArgsTooLarge));
+}
+
+#[test]
+fn test_light_up_stars_multi_fits_packet() {
+    let payer = Pubkey::new_unique();
+    let collections: Vec<(Pubkey, u64)> = (0..MAX_LIGHT_UP_MULTI_COLLECTIONS)
+        .map(|_| (Pubkey::new_unique(), u64::MAX))
+        .collect();
+    let mut ix = light_up_stars_multi(id(), Pubkey::new_unique(), get_treasury_account().0, &collections);
+    append_event_accounts(&mut ix);
+    assert_eq!(ix.accounts.len(), 3 + MAX_LIGHT_UP_MULTI_COLLECTIONS + 2);
+    match CollectionInstruction::unpack(&ix.data).unwrap() {
+        CollectionInstruction::LightUpStarsMulti(amounts) => assert_eq!(amounts, vec![u64::MAX; MAX_LIGHT_UP_MULTI_COLLECTIONS]),
+        _ => panic!("expected a LightUpStarsMulti instruction"),
+    }
+    let message = Message::new(&[ix], Some(&payer));
+    let signatures = message.header.num_required_signatures as usize;
+    assert!(1 + signatures * 64 + message.serialize().len() <= PACKET_DATA_SIZE);
+}
+
+#[test]
+fn test_create_collection_with_account() {
+    let rent = Rent::default();
+    let payer = Pubkey::new_unique();
+    let new_account = Pubkey::new_unique();
+    let args = collection_args();
+    let ixs = create_collection_with_account(id(), payer, new_account, &rent, args.space(), args.clone());
+
+    assert_eq!(ixs.len(), 2);
+    assert_eq!(
+        ixs[0],
+        system_instruction::create_account(&payer, &new_account, args.rent_exempt_lamports(&rent), args.space() as u64, &id())
+    );
+    assert_eq!(ixs[1].program_id, id());
+    assert_eq!(ixs[1].accounts[0].pubkey, new_account);
+    assert_eq!(ixs[1].accounts[1].pubkey, payer);
+    match CollectionInstruction::unpack(&ixs[1].data).unwrap() {
+        CollectionInstruction::CreateCollectionAccount(unpacked) => assert_eq!(unpacked, args),
+        _ => panic!("expected a CreateCollectionAccount instruction"),
+    }
+}
+
+/// Restates the documented limits of `CreateCollectionAccountArgs::is_valid` from scratch.
+fn reference_is_valid(args: &CreateCollectionAccountArgs) -> bool {
+    let chars = |s: &String| s.chars().count();
+    let uri = |s: &String| {
+        s.len() <= 200
+            && ["https://", "ipfs://", "ar://"]
+                .iter()
+                .any(|scheme| matches!(s.strip_prefix(scheme), Some(rest) if !rest.trim().is_empty()))
+    };
+    let tags = |tags: &Vec<String>| {
+        let distinct: HashSet<String> = tags.iter().map(|tag| tag.to_lowercase()).collect();
+        tags.len() <= 6 && distinct.len() == tags.len() && tags.iter().all(|tag| chars(tag) <= 20)
+    };
+    !args.title.trim().is_empty()
+        && chars(&args.title) <= 32
+        && !args.symbol.is_empty()
+        && chars(&args.symbol) <= 10
+        && args.symbol.chars().all(|c| matches!(c, 'A'..='Z' | '0'..='9'))
+        && chars(&args.description) <= 800
+        && !args.icon_image.trim().is_empty()
+        && uri(&args.icon_image)
+        && args.header_image.iter().all(uri)
+        && args.short_description.iter().all(|s| chars(s) <= 800)
+        && args.banner.iter().all(uri)
+        && args.tags.iter().all(tags)
+        && args.royalty_bps.iter().all(|royalty_bps| *royalty_bps <= 10_000)
+        && args.star_price != Some(0)
+}
+
+/// URIs straddling the byte limit, with both accepted and rejected schemes.
+fn uri_strategy() -> impl Strategy<Value = String> {
+    (prop_oneof![Just("https://"), Just("ipfs://"), Just("ar://"), Just("http://"), Just("")], "[a-z收]{0,200}")
+        .prop_map(|(scheme, rest)| format!("{}{}", scheme, rest))
+}
+
+fn args_strategy() -> impl Strategy<Value = CreateCollectionAccountArgs> {
+    (
+        "\\PC{0,40}",
+        prop_oneof![3 => "[A-Z0-9]{0,11}", 1 => "[A-Za-z0-9 ]{1,12}"],
+        "[a-z 描]{790,810}",
+        uri_strategy(),
+        proptest::option::of(uri_strategy()),
+        proptest::option::of("[a-z 描]{790,810}"),
+        proptest::option::of(uri_strategy()),
+        proptest::option::of(proptest::collection::vec("[aA🎵]{0,22}", 0..8)),
+        proptest::option::of(9_000u16..11_000),
+        proptest::option::of(0u64..3),
+    )
+        .prop_map(|(title, symbol, description, icon_image, header_image, short_description, banner, tags, royalty_bps, star_price)| {
+            CreateCollectionAccountArgs {
+                title,
+                symbol,
+                description,
+                icon_image,
+                header_image,
+                short_description,
+                banner,
+                tags,
+                royalty_bps,
+                star_price,
+                secondary_authority: None,
+                metadata_uri: None,
+                category: None,
+                creators: None,
+            }
+        })
+}
+
+proptest! {
+    #[test]
+    fn test_is_valid_matches_reference(args in args_strategy()) {
+        prop_assert_eq!(args.is_valid(), reference_is_valid(&args));
+    }
+}
@@ -1,23 +1,88 @@
+#![cfg(feature = "client-builders")]
+use borsh::BorshSerialize;
 use solana_program_test::*;
 use collection::id;
-use collection::processor::process_instruction;
+use collection::processor::{process_instruction, STARS_LOG_PREFIX};
 use collection::instruction::{
     create_collection_account, 
     CreateCollectionAccountArgs, 
     include_token,
     light_up_stars_hundred,
     close_account,
+    update_collection_metadata,
+    update_collection_metadata_with_realloc,
+    UpdateCollectionMetadataArgs,
+    remove_token,
+    light_up_stars_custom,
+    transfer_authority,
+    include_token_batch,
+    withdraw,
+    withdraw_amount,
+    freeze_collection,
+    close_index_account,
+    set_tags,
+    light_up_stars_with_token,
+    set_star_token,
+    withdraw_token,
+    set_star_price,
+    validate_metadata,
+    set_verified,
+    add_tag,
+    remove_tag,
+    move_token,
+    light_up_stars_once,
+    create_collection_with_account,
+    set_description,
+    set_banner,
+    add_secondary_authority,
+    add_destination_secondary_authority,
+    CollectionInstruction,
+    light_up_stars_ten_thousand,
+    set_metadata_uri,
+    add_to_allowlist,
+    remove_from_allowlist,
+    close_allowlist_account,
+    adjust_stars,
+    set_category,
+    set_treasury_recipient,
+    migrate_account,
+    set_parent,
+    clear_parent,
+    set_cooldown,
+    light_up_stars_once_with_cooldown,
+    close_cooldown_account,
+    clear_optional_metadata,
+    light_up_stars_multi,
+    set_collection_mint,
+    clear_collection_mint,
+};
+use collection::state::{
+    AllowlistAccountData, CollectionAccountData, CollectionCategory, CollectionIndexAccountData, AccountType,
+    CooldownAccountData, TreasuryConfigAccountData, LAMPORTS_PER_STAR, MAX_STARS, MAX_STARS_PER_CALL, MAX_FREE_STARS_PER_SLOT, COLLECTION_ACCOUNT_VERSION,
+};
+use collection::utils::{find_allowlist_address, find_cooldown_address, find_treasury_config_address, find_index_address, get_index_account, get_treasury_account};
+use collection::error::CollectionError;
+use collection::cpi::{invoke_include_token, IncludeTokenAccounts};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
 };
-use collection::state::{CollectionAccountData, AccountType};
-use collection::utils::{get_index_account, get_treasury_account};
 use solana_sdk::{
+    account::AccountSharedData,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_instruction,
+    system_program,
     signature::{Keypair, Signer},
-    transaction::Transaction,
+    transaction::{Transaction, TransactionError},
     borsh::try_from_slice_unchecked,
     native_token::sol_to_lamports,
+    sysvar::clock::Clock,
 };
 mod helpers;
-use helpers::{create_mint, create_associated_account, mint_tokens};
+use helpers::{create_mint, create_associated_account, mint_tokens, collection_args, create_collection, get_collection, create_nft, create_nft_2022, add_program_data,
+    bind_treasury_recipient, make_legacy_collection};
 use spl_associated_token_account::get_associated_token_address;
 
 #[tokio::test]
@@ -30,13 +95,19 @@ async fn test_create_collection_account() {
     let collection_account = collection_keypair.pubkey();
     let args = CreateCollectionAccountArgs{
         title: "test collection".to_string(),
-        symbol: "tc".to_string(),
+        symbol: "TC".to_string(),
         description: "test collection description".to_string(),
         icon_image: "https://www.google.com".to_string(),
-        header_image: Some("www.solana.com".to_string()),
+        header_image: Some("https://www.solana.com".to_string()),
         short_description: Some("www.solana.com".to_string()),
-        banner: Some("www.solana.com".to_string()),
+        banner: Some("https://www.solana.com".to_string()),
         tags: Some(vec!["art".to_string(), "music".to_string()]),
+        royalty_bps: None,
+        star_price: None,
+        secondary_authority: None,
+        metadata_uri: None,
+        category: None,
+        creators: None,
     };
     let ix = create_collection_account(program_id, collection_account, payer.pubkey(), args);
     let mut transaction = Transaction::new_with_payer(
@@ -54,13 +125,145 @@ async fn test_create_collection_account() {
     let account_data: CollectionAccountData = try_from_slice_unchecked(&collection_account.data).unwrap();
     assert_eq!(account_data.is_initialized(), true);
     assert_eq!(account_data.title, "test collection".clone());
-    assert_eq!(account_data.symbol, "tc".clone());
+    assert_eq!(account_data.symbol, "TC".clone());
     assert_eq!(account_data.description, "test collection description".clone());
     assert_eq!(account_data.icon_image, "https://www.google.com".clone());
     assert_eq!(account_data.short_description, Some("www.solana.com".to_string()));
     assert_eq!(account_data.short_description, Some("www.solana.com".to_string()));
-    assert_eq!(account_data.banner, Some("www.solana.com".to_string()));
+    assert_eq!(account_data.banner, Some("https://www.solana.com".to_string()));
     assert_eq!(account_data.tags, Some(vec!["art".to_string(), "music".to_string()]));
+    assert_eq!(account_data.version, COLLECTION_ACCOUNT_VERSION);
+    assert!(account_data.created_at > 0);
+}
+
+#[tokio::test]
+async fn test_create_collection_with_account() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    let payer_pubkey = context.payer.pubkey();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let args = collection_args();
+    let ixs = create_collection_with_account(
+        program_id,
+        payer_pubkey,
+        collection_keypair.pubkey(),
+        &rent,
+        args.space(),
+        args.clone(),
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &ixs,
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &collection_keypair], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let account = context.banks_client.get_account(collection_keypair.pubkey()).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), args.space());
+    let collection = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    assert_eq!(collection.title, args.title);
+    assert_eq!(collection.authority, payer_pubkey);
+
+    // an allocation too small for the args is rejected
+    let collection_keypair = Keypair::new();
+    let ixs = create_collection_with_account(
+        program_id,
+        payer_pubkey,
+        collection_keypair.pubkey(),
+        &rent,
+        args.space() - 1,
+        args,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &ixs,
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &collection_keypair], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(1, InstructionError::AccountDataTooSmall),
+    );
+}
+
+#[tokio::test]
+async fn test_create_collection_account_twice() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+
+    let mut args = collection_args();
+    args.title = "second collection".to_string();
+    assert_eq!(
+        create_collection(&mut context, &collection_keypair, args).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::AlreadyInitialized as u32),
+        ),
+    );
+    let collection = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    assert_eq!(collection.title, "test collection".to_string());
+}
+
+#[tokio::test]
+async fn test_create_collection_account_requires_rent() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+    let payer_pubkey = context.payer.pubkey();
+
+    // the funder can pay for a small account but not for a collection
+    let funder = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(&payer_pubkey, &funder.pubkey(), sol_to_lamports(0.01))],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let collection_keypair = Keypair::new();
+    let ix = create_collection_account(program_id, collection_keypair.pubkey(), funder.pubkey(), collection_args());
+    let mut transaction = Transaction::new_with_payer(
+        std::slice::from_ref(&ix),
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &funder, &collection_keypair], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::InsufficientRent as u32),
+        ),
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(&payer_pubkey, &funder.pubkey(), sol_to_lamports(1.0))],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    let blockhash = context.banks_client.get_new_latest_blockhash(&context.last_blockhash).await.unwrap();
+    transaction.sign(&[&context.payer, &funder, &collection_keypair], blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let account = context.banks_client
+        .get_account(collection_keypair.pubkey())
+        .await
+        .expect("get_account")
+        .unwrap();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    assert!(rent.is_exempt(account.lamports, account.data.len()));
 }
 
 #[tokio::test]
@@ -73,13 +276,19 @@ async fn test_include_token() {
     let collection_account = collection_keypair.pubkey();
     let args = CreateCollectionAccountArgs{
         title: "test collection".to_string(),
-        symbol: "tc".to_string(),
+        symbol: "TC".to_string(),
         description: "test collection description".to_string(),
         icon_image: "https://www.google.com".to_string(),
-        header_image: Some("www.solana.com".to_string()),
+        header_image: Some("https://www.solana.com".to_string()),
         short_description: Some("www.solana.com".to_string()),
-        banner: Some("www.solana.com".to_string()),
+        banner: Some("https://www.solana.com".to_string()),
         tags: Some(vec!["art".to_string(), "music".to_string()]),
+        royalty_bps: None,
+        star_price: None,
+        secondary_authority: None,
+        metadata_uri: None,
+        category: None,
+        creators: None,
     };
     let ix = create_collection_account(program_id, collection_account, context.payer.pubkey(), args);
     let mut transaction = Transaction::new_with_payer(
@@ -112,7 +321,6 @@ async fn test_include_token() {
         payer_pubkey,
         mint_keypair.pubkey(),
         nft_ata,
-        index_account,
         payer_pubkey,
     );
     let mut transaction = Transaction::new_with_payer(
@@ -121,6 +329,162 @@ async fn test_include_token() {
     );
     transaction.sign(&[&context.payer], context.last_blockhash);
     context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let index = context.banks_client.get_account(index_account).await.expect("get_account").unwrap();
+    assert_eq!(index.owner, program_id);
+    let index_data = CollectionIndexAccountData::try_from_slice_unchecked(&index.data).unwrap();
+    assert_eq!(index_data.account_type, AccountType::CollectionIndexAccount);
+    assert_eq!(index_data.collection, collection_account);
+    assert_eq!(index_data.mint, mint_keypair.pubkey());
+    assert_eq!(index_data.bump, find_index_address(&program_id, &mint_keypair.pubkey()).1);
+    assert_eq!(get_collection(&mut context, &collection_account).await.supply, 1);
+
+    // the same mint can only be included once
+    let ix = include_token(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        mint_keypair.pubkey(),
+        nft_ata,
+        payer_pubkey,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&context.payer.pubkey()),
+    );
+    let blockhash = context.banks_client.get_new_latest_blockhash(&context.last_blockhash).await.unwrap();
+    transaction.sign(&[&context.payer], blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::TokenAlreadyIncluded as u32),
+        ),
+    );
+    assert_eq!(get_collection(&mut context, &collection_account).await.supply, 1);
+}
+
+#[tokio::test]
+async fn test_include_token_2022() {
+    let program_id = id();
+    let mut program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    // Token-2022 runs natively, so the test does not depend on the build program-test bundles
+    program_test.add_program(
+        "spl_token_2022",
+        spl_token_2022::id(),
+        processor!(spl_token_2022::processor::Processor::process),
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let (mint_keypair, token_account) = create_nft_2022(&mut context).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+
+    let ix = include_token(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        mint_keypair.pubkey(),
+        token_account,
+        payer_pubkey,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let (index_account, _) = get_index_account(&mint_keypair.pubkey());
+    let index = context.banks_client.get_account(index_account).await.expect("get_account").unwrap();
+    assert_eq!(index.owner, program_id);
+    let index_data = CollectionIndexAccountData::try_from_slice_unchecked(&index.data).unwrap();
+    assert_eq!(index_data.mint, mint_keypair.pubkey());
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.supply, 1);
+}
+
+#[tokio::test]
+async fn test_include_token_not_an_nft() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+
+    // (decimals, supply) of a fungible mint and of a multi-supply mint
+    for (decimals, supply) in [(2, 1), (0, 2)] {
+        let mint_keypair = Keypair::new();
+        create_mint(&mut context, &mint_keypair, &payer_pubkey, decimals, None).await.unwrap();
+        create_associated_account(&mut context, &payer_pubkey, &mint_keypair.pubkey()).await.unwrap();
+        let token_account = get_associated_token_address(&payer_pubkey, &mint_keypair.pubkey());
+        mint_tokens(&mut context, &mint_keypair.pubkey(), &token_account, supply, &payer_pubkey, None).await.unwrap();
+
+        let ix = include_token(
+            program_id,
+            collection_keypair.pubkey(),
+            payer_pubkey,
+            mint_keypair.pubkey(),
+            token_account,
+            payer_pubkey,
+        );
+        let mut transaction = Transaction::new_with_payer(
+            &[ix],
+            Some(&payer_pubkey),
+        );
+        transaction.sign(&[&context.payer], context.last_blockhash);
+        assert_eq!(
+            context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(CollectionError::NotAnNft as u32),
+            ),
+        );
+    }
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.supply, 0);
+}
+
+#[tokio::test]
+async fn test_include_token_with_forged_index_account() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let (mint_keypair, nft_ata) = create_nft(&mut context).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+
+    let ix = include_token(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        mint_keypair.pubkey(),
+        nft_ata,
+        payer_pubkey,
+    );
+    // neither an arbitrary account nor the index PDA of another mint is accepted
+    let other_mint_index = find_index_address(&program_id, &Keypair::new().pubkey()).0;
+    for index_account in [Keypair::new().pubkey(), other_mint_index] {
+        let mut ix = ix.clone();
+        ix.accounts[4].pubkey = index_account;
+        let mut transaction = Transaction::new_with_payer(
+            &[ix],
+            Some(&payer_pubkey),
+        );
+        transaction.sign(&[&context.payer], context.last_blockhash);
+        assert_eq!(
+            context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(CollectionError::CollectionIndexAccountMismatch as u32),
+            ),
+        );
+        assert!(context.banks_client.get_account(index_account).await.unwrap().is_none());
+    }
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.supply, 0);
 }
 
 #[tokio::test]
@@ -133,13 +497,19 @@ async fn test_light_up_stars_hundred() {
     let collection_account = collection_keypair.pubkey();
     let args = CreateCollectionAccountArgs{
         title: "test collection".to_string(),
-        symbol: "tc".to_string(),
+        symbol: "TC".to_string(),
         description: "test collection description".to_string(),
         icon_image: "https://www.google.com".to_string(),
-        header_image: Some("www.solana.com".to_string()),
+        header_image: Some("https://www.solana.com".to_string()),
         short_description: Some("www.solana.com".to_string()),
-        banner: Some("www.solana.com".to_string()),
+        banner: Some("https://www.solana.com".to_string()),
         tags: Some(vec!["art".to_string(), "music".to_string()]),
+        royalty_bps: None,
+        star_price: None,
+        secondary_authority: None,
+        metadata_uri: None,
+        category: None,
+        creators: None,
     };
     let ix = create_collection_account(program_id, collection_account, context.payer.pubkey(), args);
     let mut transaction = Transaction::new_with_payer(
@@ -193,6 +563,51 @@ async fn test_light_up_stars_hundred() {
 
 }
 
+#[tokio::test]
+async fn test_light_up_stars_hundred_requires_source_signature() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+    let payer_pubkey = context.payer.pubkey();
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+
+    let source = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(&payer_pubkey, &source.pubkey(), sol_to_lamports(1.0))],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    // the payer only covers the fee, nobody signs for the funding account
+    let (treasury_account, _) = get_treasury_account();
+    let mut ix = light_up_stars_hundred(program_id, collection_keypair.pubkey(), source.pubkey(), treasury_account);
+    ix.accounts[1].is_signer = false;
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature),
+    );
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, 0);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[light_up_stars_hundred(program_id, collection_keypair.pubkey(), source.pubkey(), treasury_account)],
+        Some(&payer_pubkey),
+        &[&context.payer, &source],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, 100);
+}
+
 #[tokio::test]
 async fn test_close_account() {
     let program_id = id();
@@ -203,13 +618,19 @@ async fn test_close_account() {
     let collection_account = collection_keypair.pubkey();
     let args = CreateCollectionAccountArgs{
         title: "test collection".to_string(),
-        symbol: "tc".to_string(),
+        symbol: "TC".to_string(),
         description: "test collection description".to_string(),
         icon_image: "https://www.google.com".to_string(),
-        header_image: Some("www.solana.com".to_string()),
+        header_image: Some("https://www.solana.com".to_string()),
         short_description: Some("www.solana.com".to_string()),
-        banner: Some("www.solana.com".to_string()),
+        banner: Some("https://www.solana.com".to_string()),
         tags: Some(vec!["art".to_string(), "music".to_string()]),
+        royalty_bps: None,
+        star_price: None,
+        secondary_authority: None,
+        metadata_uri: None,
+        category: None,
+        creators: None,
     };
     let ix = create_collection_account(program_id, collection_account, payer.pubkey(), args);
     let mut transaction = Transaction::new_with_payer(
@@ -240,4 +661,3609 @@ async fn test_close_account() {
     );
     transaction.sign(&[&payer], recent_blockhash);
     banks_client.process_transaction(transaction).await.unwrap();
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_update_collection_metadata() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+
+    let ix = update_collection_metadata(
+        program_id,
+        collection_keypair.pubkey(),
+        context.payer.pubkey(),
+        UpdateCollectionMetadataArgs {
+            description: Some("updated description".to_string()),
+            ..Default::default()
+        },
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&context.payer.pubkey()),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let account_data = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    assert_eq!(account_data.description, "updated description".to_string());
+    assert_eq!(account_data.title, "test collection".to_string());
+    assert_eq!(account_data.tags, Some(vec!["art".to_string(), "music".to_string()]));
+}
+
+#[tokio::test]
+async fn test_update_collection_metadata_with_realloc() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+    let payer_pubkey = context.payer.pubkey();
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let funder = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(&payer_pubkey, &funder.pubkey(), sol_to_lamports(1.0))],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let update = |description: &str| update_collection_metadata_with_realloc(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        funder.pubkey(),
+        UpdateCollectionMetadataArgs { description: Some(description.to_string()), ..Default::default() },
+    );
+    let long_description = "d".repeat(800);
+
+    // the first update fits the account to the collection, refunding the unused rent
+    let created = context.banks_client.get_account(collection_keypair.pubkey()).await.unwrap().unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[update("short")],
+        Some(&payer_pubkey),
+        &[&context.payer, &funder],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let collection = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    let short = context.banks_client.get_account(collection_keypair.pubkey()).await.unwrap().unwrap();
+    assert_eq!(short.data.len(), collection.try_to_vec().unwrap().len());
+    assert_eq!(short.lamports, rent.minimum_balance(short.data.len()));
+    assert_eq!(
+        context.banks_client.get_balance(funder.pubkey()).await.unwrap(),
+        sol_to_lamports(1.0) + created.lamports - short.lamports,
+    );
+
+    // without a funding account a longer description no longer fits
+    let blockhash = context.banks_client.get_new_latest_blockhash(&context.last_blockhash).await.unwrap();
+    let mut ix = update(&long_description);
+    ix.accounts.truncate(2);
+    let transaction = Transaction::new_signed_with_payer(&[ix], Some(&payer_pubkey), &[&context.payer], blockhash);
+    assert!(context.banks_client.process_transaction(transaction).await.is_err());
+
+    // growing is paid by the funding account
+    let transaction = Transaction::new_signed_with_payer(
+        &[update(&long_description)],
+        Some(&payer_pubkey),
+        &[&context.payer, &funder],
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let collection = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    assert_eq!(collection.description, long_description);
+    let long = context.banks_client.get_account(collection_keypair.pubkey()).await.unwrap().unwrap();
+    assert_eq!(long.data.len(), short.data.len() + 800 - "short".len());
+    assert_eq!(long.lamports, rent.minimum_balance(long.data.len()));
+
+    // and shrinking back gives the rent back
+    let funder_balance = context.banks_client.get_balance(funder.pubkey()).await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[update("short")],
+        Some(&payer_pubkey),
+        &[&context.payer, &funder],
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let shrunk = context.banks_client.get_account(collection_keypair.pubkey()).await.unwrap().unwrap();
+    assert_eq!(shrunk.data.len(), short.data.len());
+    assert_eq!(shrunk.lamports, short.lamports);
+    assert_eq!(
+        context.banks_client.get_balance(funder.pubkey()).await.unwrap(),
+        funder_balance + long.lamports - shrunk.lamports,
+    );
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.title, collection_args().title);
+}
+
+#[tokio::test]
+async fn test_remove_token() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+    let (mint_keypair, nft_ata) = create_nft(&mut context).await.unwrap();
+    let (index_account, _) = get_index_account(&mint_keypair.pubkey());
+
+    // removing a token that was never included fails
+    let ix = remove_token(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        mint_keypair.pubkey(),
+        index_account,
+        Keypair::new().pubkey(),
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert!(context.banks_client.process_transaction(transaction).await.is_err());
+
+    let ix = include_token(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        mint_keypair.pubkey(),
+        nft_ata,
+        payer_pubkey,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.supply, 1);
+
+    let ix = remove_token(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        mint_keypair.pubkey(),
+        index_account,
+        payer_pubkey,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.supply, 0);
+    let index = context.banks_client.get_account(index_account).await.expect("get_account");
+    assert!(index.is_none());
+}
+
+#[tokio::test]
+async fn test_move_token() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let source_keypair = Keypair::new();
+    create_collection(&mut context, &source_keypair, collection_args()).await.unwrap();
+    let destination_keypair = Keypair::new();
+    create_collection(&mut context, &destination_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+    let (mint_keypair, nft_ata) = create_nft(&mut context).await.unwrap();
+    let (index_account, _) = get_index_account(&mint_keypair.pubkey());
+
+    let ix = include_token(
+        program_id,
+        source_keypair.pubkey(),
+        payer_pubkey,
+        mint_keypair.pubkey(),
+        nft_ata,
+        payer_pubkey,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    // the destination authority has to sign as well
+    let stranger = Keypair::new();
+    let ix = move_token(
+        program_id,
+        source_keypair.pubkey(),
+        payer_pubkey,
+        destination_keypair.pubkey(),
+        stranger.pubkey(),
+        mint_keypair.pubkey(),
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &stranger], context.last_blockhash);
+    let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::NotCollectionAuthority as u32))
+    );
+
+    let ix = move_token(
+        program_id,
+        source_keypair.pubkey(),
+        payer_pubkey,
+        destination_keypair.pubkey(),
+        payer_pubkey,
+        mint_keypair.pubkey(),
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(get_collection(&mut context, &source_keypair.pubkey()).await.supply, 0);
+    let destination = get_collection(&mut context, &destination_keypair.pubkey()).await;
+    assert_eq!(destination.supply, 1);
+    assert_eq!(destination.index_seq, 1);
+    let index = context.banks_client.get_account(index_account).await.unwrap().unwrap();
+    let index_data = CollectionIndexAccountData::try_from_slice_unchecked(&index.data).unwrap();
+    assert_eq!(index_data.collection, destination_keypair.pubkey());
+    assert_eq!(index_data.index, 0);
+
+    // the token no longer belongs to the source collection
+    let ix = move_token(
+        program_id,
+        source_keypair.pubkey(),
+        payer_pubkey,
+        destination_keypair.pubkey(),
+        payer_pubkey,
+        mint_keypair.pubkey(),
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    let blockhash = context.banks_client.get_new_latest_blockhash(&context.last_blockhash).await.unwrap();
+    transaction.sign(&[&context.payer], blockhash);
+    let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::TokenNotIncluded as u32))
+    );
+}
+
+#[tokio::test]
+async fn test_index_seq() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+
+    let mut indices = Vec::new();
+    for _ in 0..2 {
+        let (mint_keypair, nft_ata) = create_nft(&mut context).await.unwrap();
+        let ix = include_token(
+            program_id,
+            collection_keypair.pubkey(),
+            payer_pubkey,
+            mint_keypair.pubkey(),
+            nft_ata,
+            payer_pubkey,
+        );
+        let (index_account, _) = get_index_account(&mint_keypair.pubkey());
+        let mut transaction = Transaction::new_with_payer(
+            &[ix],
+            Some(&payer_pubkey),
+        );
+        transaction.sign(&[&context.payer], context.last_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+        let index = context.banks_client.get_account(index_account).await.expect("get_account").unwrap();
+        indices.push(CollectionIndexAccountData::try_from_slice_unchecked(&index.data).unwrap().index);
+
+        // removing the token frees its supply but not its sequence number
+        let ix = remove_token(
+            program_id,
+            collection_keypair.pubkey(),
+            payer_pubkey,
+            mint_keypair.pubkey(),
+            index_account,
+            payer_pubkey,
+        );
+        let mut transaction = Transaction::new_with_payer(
+            &[ix],
+            Some(&payer_pubkey),
+        );
+        transaction.sign(&[&context.payer], context.last_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    assert_eq!(indices, vec![0, 1]);
+    let collection = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    assert_eq!(collection.supply, 0);
+    assert_eq!(collection.index_seq(), 2);
+}
+
+#[tokio::test]
+async fn test_light_up_stars_custom() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let (treasury_account, _) = get_treasury_account();
+    let payer_pubkey = context.payer.pubkey();
+
+    let ix = light_up_stars_custom(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        treasury_account,
+        250,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, 250);
+    let balance = context.banks_client.get_balance(treasury_account).await.expect("get_balance");
+    assert_eq!(balance, 250 * LAMPORTS_PER_STAR);
+
+    // the lamport charge for this many stars overflows a u64
+    let ix = light_up_stars_custom(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        treasury_account,
+        u64::MAX - 250,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert!(context.banks_client.process_transaction(transaction).await.is_err());
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, 250);
+}
+
+#[tokio::test]
+async fn test_light_up_stars_multi() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypairs = [Keypair::new(), Keypair::new(), Keypair::new()];
+    for (collection_keypair, star_price) in collection_keypairs.iter().zip([None, Some(2 * LAMPORTS_PER_STAR), None]) {
+        let args = CreateCollectionAccountArgs { star_price, ..collection_args() };
+        create_collection(&mut context, collection_keypair, args).await.unwrap();
+    }
+    let collections: Vec<Pubkey> = collection_keypairs.iter().map(|keypair| keypair.pubkey()).collect();
+    let (treasury_account, _) = get_treasury_account();
+    let payer_pubkey = context.payer.pubkey();
+
+    let ix = light_up_stars_multi(
+        program_id,
+        payer_pubkey,
+        treasury_account,
+        &[(collections[0], 10), (collections[1], 20), (collections[2], 30)],
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    for (collection, stars) in collections.iter().zip([10, 20, 30]) {
+        assert_eq!(get_collection(&mut context, collection).await.stars, stars);
+    }
+    let balance = context.banks_client.get_balance(treasury_account).await.expect("get_balance");
+    assert_eq!(balance, (10 + 2 * 20 + 30) * LAMPORTS_PER_STAR);
+
+    // an account that is not a collection fails the whole batch
+    let ix = light_up_stars_multi(
+        program_id,
+        payer_pubkey,
+        treasury_account,
+        &[(collections[0], 10), (payer_pubkey, 20)],
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert!(context.banks_client.process_transaction(transaction).await.is_err());
+    assert_eq!(get_collection(&mut context, &collections[0]).await.stars, 10);
+}
+
+#[tokio::test]
+async fn test_light_up_stars_per_call_limit() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    let args = CreateCollectionAccountArgs { star_price: Some(1), ..collection_args() };
+    create_collection(&mut context, &collection_keypair, args).await.unwrap();
+    let (treasury_account, _) = get_treasury_account();
+    let payer_pubkey = context.payer.pubkey();
+
+    let ix = light_up_stars_custom(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        treasury_account,
+        MAX_STARS_PER_CALL,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, MAX_STARS_PER_CALL);
+
+    // far below the ceiling, but more than a single call may light up
+    let ix = light_up_stars_custom(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        treasury_account,
+        MAX_STARS_PER_CALL + 1,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::AmountTooLarge as u32),
+        ),
+    );
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, MAX_STARS_PER_CALL);
+}
+
+#[tokio::test]
+async fn test_set_star_price() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    let mut args = collection_args();
+    args.star_price = Some(1_000_000);
+    create_collection(&mut context, &collection_keypair, args).await.unwrap();
+    let (treasury_account, _) = get_treasury_account();
+    let payer_pubkey = context.payer.pubkey();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.star_price(), 1_000_000);
+
+    let ix = light_up_stars_custom(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        treasury_account,
+        10,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let balance = context.banks_client.get_balance(treasury_account).await.expect("get_balance");
+    assert_eq!(balance, 10 * 1_000_000);
+
+    let intruder = Keypair::new();
+    let ix = set_star_price(program_id, collection_keypair.pubkey(), intruder.pubkey(), 1);
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &intruder], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::NotCollectionAuthority as u32),
+        ),
+    );
+
+    let ix = set_star_price(program_id, collection_keypair.pubkey(), payer_pubkey, 2_000_000);
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let ix = light_up_stars_hundred(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        treasury_account,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let balance = context.banks_client.get_balance(treasury_account).await.expect("get_balance");
+    assert_eq!(balance, 10 * 1_000_000 + 100 * 2_000_000);
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, 110);
+}
+
+#[tokio::test]
+async fn test_transfer_authority() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+    let new_authority = Keypair::new();
+
+    let ix = transfer_authority(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        new_authority.pubkey(),
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.authority, new_authority.pubkey());
+
+    // the old authority can no longer update metadata
+    let ix = update_collection_metadata(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        UpdateCollectionMetadataArgs {
+            description: Some("updated description".to_string()),
+            ..Default::default()
+        },
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert!(context.banks_client.process_transaction(transaction).await.is_err());
+
+    let ix = update_collection_metadata(
+        program_id,
+        collection_keypair.pubkey(),
+        new_authority.pubkey(),
+        UpdateCollectionMetadataArgs {
+            description: Some("updated description".to_string()),
+            ..Default::default()
+        },
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &new_authority], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.description, "updated description".to_string());
+}
+
+#[tokio::test]
+async fn test_secondary_authority() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    let secondary = Keypair::new();
+    let mut args = collection_args();
+    args.secondary_authority = Some(secondary.pubkey());
+    create_collection(&mut context, &collection_keypair, args).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+    assert_eq!(
+        get_collection(&mut context, &collection_keypair.pubkey()).await.secondary_authority,
+        Some(secondary.pubkey())
+    );
+
+    let update = UpdateCollectionMetadataArgs {
+        description: Some("updated description".to_string()),
+        ..Default::default()
+    };
+    let new_authority = Keypair::new();
+    // the authority alone is rejected
+    let ixs = [
+        update_collection_metadata(program_id, collection_keypair.pubkey(), payer_pubkey, update.clone()),
+        transfer_authority(program_id, collection_keypair.pubkey(), payer_pubkey, new_authority.pubkey()),
+    ];
+    for ix in ixs.iter() {
+        let mut transaction = Transaction::new_with_payer(
+            std::slice::from_ref(ix),
+            Some(&payer_pubkey),
+        );
+        transaction.sign(&[&context.payer], context.last_blockhash);
+        assert_eq!(
+            context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(CollectionError::NotCollectionAuthority as u32),
+            ),
+        );
+    }
+
+    // so is a co-signer other than the secondary authority
+    let stranger = Keypair::new();
+    let mut ix = update_collection_metadata(program_id, collection_keypair.pubkey(), payer_pubkey, update.clone());
+    add_secondary_authority(&mut ix, stranger.pubkey());
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &stranger], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::NotCollectionAuthority as u32),
+        ),
+    );
+
+    let mut ix = update_collection_metadata(program_id, collection_keypair.pubkey(), payer_pubkey, update);
+    add_secondary_authority(&mut ix, secondary.pubkey());
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &secondary], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.description, "updated description".to_string());
+
+    let mut ix = transfer_authority(program_id, collection_keypair.pubkey(), payer_pubkey, new_authority.pubkey());
+    add_secondary_authority(&mut ix, secondary.pubkey());
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &secondary], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let collection = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    assert_eq!(collection.authority, new_authority.pubkey());
+    assert_eq!(collection.secondary_authority, Some(secondary.pubkey()));
+}
+
+#[tokio::test]
+async fn test_secondary_authority_metadata_setters() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    let secondary = Keypair::new();
+    let mut args = collection_args();
+    args.secondary_authority = Some(secondary.pubkey());
+    create_collection(&mut context, &collection_keypair, args).await.unwrap();
+    let collection_account = collection_keypair.pubkey();
+    let payer_pubkey = context.payer.pubkey();
+
+    let setters = vec![
+        set_tags(program_id, collection_account, payer_pubkey, vec!["art".to_string()]),
+        add_tag(program_id, collection_account, payer_pubkey, "film".to_string()),
+        remove_tag(program_id, collection_account, payer_pubkey, "art".to_string()),
+        set_description(program_id, collection_account, payer_pubkey, "co-signed description".to_string()),
+        set_banner(program_id, collection_account, payer_pubkey, None),
+        set_metadata_uri(program_id, collection_account, payer_pubkey, Some("https://www.solana.com/collection.json".to_string())),
+        set_category(program_id, collection_account, payer_pubkey, Some(CollectionCategory::Music)),
+        clear_optional_metadata(program_id, collection_account, payer_pubkey, payer_pubkey),
+    ];
+    for mut ix in setters {
+        // the authority alone is rejected
+        let transaction = Transaction::new_signed_with_payer(
+            std::slice::from_ref(&ix),
+            Some(&payer_pubkey),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(CollectionError::NotCollectionAuthority as u32),
+            ),
+        );
+
+        add_secondary_authority(&mut ix, secondary.pubkey());
+        let transaction = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer_pubkey),
+            &[&context.payer, &secondary],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(transaction).await.unwrap();
+    }
+    let collection = get_collection(&mut context, &collection_account).await;
+    assert_eq!(collection.tags, None);
+    assert_eq!(collection.short_description, None);
+    assert_eq!(collection.description, "co-signed description".to_string());
+    assert_eq!(collection.banner, None);
+    assert_eq!(collection.metadata_uri, Some("https://www.solana.com/collection.json".to_string()));
+    assert_eq!(collection.category, Some(CollectionCategory::Music));
+}
+
+#[tokio::test]
+async fn test_secondary_authority_gated_instructions() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let secondary = Keypair::new();
+    let co_signed_keypair = Keypair::new();
+    let mut args = collection_args();
+    args.secondary_authority = Some(secondary.pubkey());
+    create_collection(&mut context, &co_signed_keypair, args.clone()).await.unwrap();
+    let parent_keypair = Keypair::new();
+    create_collection(&mut context, &parent_keypair, args).await.unwrap();
+    let plain_keypair = Keypair::new();
+    create_collection(&mut context, &plain_keypair, collection_args()).await.unwrap();
+    let co_signed = co_signed_keypair.pubkey();
+    let plain = plain_keypair.pubkey();
+    let payer_pubkey = context.payer.pubkey();
+    let (included_mint, included_ata) = create_nft(&mut context).await.unwrap();
+    let (plain_mint, plain_ata) = create_nft(&mut context).await.unwrap();
+    let (other_mint, other_ata) = create_nft(&mut context).await.unwrap();
+
+    // give the co-signed collection a token, an allowlist and a cooldown to close
+    let mut include_ix = include_token(program_id, co_signed, payer_pubkey, included_mint.pubkey(), included_ata, payer_pubkey);
+    add_secondary_authority(&mut include_ix, secondary.pubkey());
+    let mut allowlist_ix = add_to_allowlist(program_id, co_signed, payer_pubkey, payer_pubkey, other_mint.pubkey());
+    add_secondary_authority(&mut allowlist_ix, secondary.pubkey());
+    let mut cooldown_ix = set_cooldown(program_id, co_signed, payer_pubkey, payer_pubkey, 10);
+    add_secondary_authority(&mut cooldown_ix, secondary.pubkey());
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            include_ix,
+            include_token(program_id, plain, payer_pubkey, plain_mint.pubkey(), plain_ata, payer_pubkey),
+            allowlist_ix,
+            cooldown_ix,
+        ],
+        Some(&payer_pubkey),
+        &[&context.payer, &secondary],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let (included_index, _) = get_index_account(&included_mint.pubkey());
+
+    // the authority alone is rejected everywhere
+    let instructions = vec![
+        include_token(program_id, co_signed, payer_pubkey, other_mint.pubkey(), other_ata, payer_pubkey),
+        include_token_batch(program_id, co_signed, payer_pubkey, &[(other_mint.pubkey(), other_ata)], payer_pubkey),
+        remove_token(program_id, co_signed, payer_pubkey, included_mint.pubkey(), included_index, payer_pubkey),
+        freeze_collection(program_id, co_signed, payer_pubkey),
+        set_star_price(program_id, co_signed, payer_pubkey, 5),
+        add_to_allowlist(program_id, co_signed, payer_pubkey, payer_pubkey, plain_mint.pubkey()),
+        remove_from_allowlist(program_id, co_signed, payer_pubkey, other_mint.pubkey()),
+        adjust_stars(program_id, co_signed, payer_pubkey, 1),
+        migrate_account(program_id, co_signed, payer_pubkey, payer_pubkey),
+        set_cooldown(program_id, co_signed, payer_pubkey, payer_pubkey, 20),
+        set_parent(program_id, co_signed, payer_pubkey, plain, payer_pubkey),
+        set_parent(program_id, plain, payer_pubkey, co_signed, payer_pubkey),
+        clear_parent(program_id, co_signed, payer_pubkey),
+        set_collection_mint(program_id, co_signed, payer_pubkey, other_mint.pubkey()),
+        move_token(program_id, co_signed, payer_pubkey, plain, payer_pubkey, included_mint.pubkey()),
+        move_token(program_id, plain, payer_pubkey, co_signed, payer_pubkey, plain_mint.pubkey()),
+        close_account(program_id, co_signed, payer_pubkey, payer_pubkey, AccountType::CollectionAccount),
+        close_index_account(program_id, included_index, payer_pubkey, payer_pubkey, co_signed),
+        close_allowlist_account(program_id, co_signed, payer_pubkey, payer_pubkey),
+        close_cooldown_account(program_id, co_signed, payer_pubkey, payer_pubkey),
+    ];
+    for ix in instructions {
+        let name = CollectionInstruction::unpack(&ix.data).unwrap().variant_name();
+        let transaction = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer_pubkey),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(CollectionError::NotCollectionAuthority as u32),
+            ),
+            "{}",
+            name,
+        );
+    }
+
+    // the secondary authority goes after the authority of the collection it backs
+    let mut parent_ix = set_parent(program_id, co_signed, payer_pubkey, parent_keypair.pubkey(), payer_pubkey);
+    add_secondary_authority(&mut parent_ix, secondary.pubkey());
+    add_destination_secondary_authority(&mut parent_ix, secondary.pubkey());
+    let mut close_allowlist_ix = close_allowlist_account(program_id, co_signed, payer_pubkey, payer_pubkey);
+    add_secondary_authority(&mut close_allowlist_ix, secondary.pubkey());
+    let mut move_in_ix = move_token(program_id, plain, payer_pubkey, co_signed, payer_pubkey, plain_mint.pubkey());
+    add_destination_secondary_authority(&mut move_in_ix, secondary.pubkey());
+    let mut move_out_ix = move_token(program_id, co_signed, payer_pubkey, plain, payer_pubkey, included_mint.pubkey());
+    add_secondary_authority(&mut move_out_ix, secondary.pubkey());
+    let mut close_cooldown_ix = close_cooldown_account(program_id, co_signed, payer_pubkey, payer_pubkey);
+    add_secondary_authority(&mut close_cooldown_ix, secondary.pubkey());
+    let transaction = Transaction::new_signed_with_payer(
+        &[parent_ix, close_allowlist_ix, move_in_ix, move_out_ix, close_cooldown_ix],
+        Some(&payer_pubkey),
+        &[&context.payer, &secondary],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let collection = get_collection(&mut context, &co_signed).await;
+    assert_eq!(collection.parent, Some(parent_keypair.pubkey()));
+    assert_eq!(collection.supply, 1);
+    assert!(!collection.has_allowlist);
+    assert_eq!(collection.cooldown_slots, 0);
+    assert_eq!(get_collection(&mut context, &plain).await.supply, 1);
+}
+
+#[tokio::test]
+async fn test_include_token_batch() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+    let (first_mint, first_ata) = create_nft(&mut context).await.unwrap();
+    let (second_mint, second_ata) = create_nft(&mut context).await.unwrap();
+    let tokens = vec![
+        (first_mint.pubkey(), first_ata),
+        (second_mint.pubkey(), second_ata),
+    ];
+
+    let ix = include_token_batch(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        &tokens,
+        payer_pubkey,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.supply, 2);
+    for (mint, _) in &tokens {
+        let (index_account, _) = get_index_account(mint);
+        let index = context.banks_client.get_account(index_account).await.expect("get_account");
+        assert!(index.is_some());
+    }
+
+    // a mint that is already included rejects the whole batch
+    let ix = include_token_batch(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        &tokens[1..],
+        payer_pubkey,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert!(context.banks_client.process_transaction(transaction).await.is_err());
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.supply, 2);
+}
+
+#[tokio::test]
+async fn test_create_collection_account_with_invalid_metadata() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let mut args = collection_args();
+    args.icon_image = "not a url".to_string();
+    let err = create_collection(&mut context, &Keypair::new(), args).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::InvalidMetadata as u32)),
+    );
+}
+
+#[tokio::test]
+async fn test_validate_metadata() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+    let payer_pubkey = context.payer.pubkey();
+
+    let ix = validate_metadata(program_id, collection_args());
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    let simulation = context.banks_client.simulate_transaction(transaction).await.unwrap();
+    assert_eq!(simulation.result, Some(Ok(())));
+
+    let mut args = collection_args();
+    args.icon_image = "http://www.google.com".to_string();
+    let ix = validate_metadata(program_id, args);
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    let simulation = context.banks_client.simulate_transaction(transaction).await.unwrap();
+    assert_eq!(
+        simulation.result,
+        Some(Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::InvalidMetadata as u32),
+        ))),
+    );
+}
+
+#[tokio::test]
+async fn test_light_up_stars_log() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let (treasury_account, _) = get_treasury_account();
+    let payer_pubkey = context.payer.pubkey();
+
+    let ix = light_up_stars_hundred(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        treasury_account,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    let simulation = context.banks_client.simulate_transaction(transaction).await.unwrap();
+    let logs = simulation.simulation_details.unwrap().logs;
+    let expected = format!("Program log: {} {} 100 100", STARS_LOG_PREFIX, collection_keypair.pubkey());
+    assert!(logs.contains(&expected));
+}
+
+#[cfg(feature = "emit-events")]
+#[tokio::test]
+async fn test_light_up_stars_emits_event() {
+    use collection::event::append_event_accounts;
+
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let (treasury_account, _) = get_treasury_account();
+    let payer_pubkey = context.payer.pubkey();
+
+    let mut ix = light_up_stars_hundred(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        treasury_account,
+    );
+    append_event_accounts(&mut ix);
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    let simulation = context.banks_client.simulate_transaction(transaction.clone()).await.unwrap();
+    assert_eq!(simulation.result, Some(Ok(())));
+    // banks does not expose inner instructions, the self-invocation shows up in the logs
+    let logs = simulation.simulation_details.unwrap().logs;
+    assert!(logs.contains(&format!("Program {} invoke [2]", program_id)));
+    assert!(logs.contains(&"Program log: Instruction: Emit Event".to_string()));
+
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let collection_account = context.banks_client.get_account(collection_keypair.pubkey()).await.unwrap().unwrap();
+    let collection_data = CollectionAccountData::try_from_slice_unchecked(&collection_account.data).unwrap();
+    assert_eq!(collection_data.stars, 100);
+}
+
+#[tokio::test]
+async fn test_withdraw_amount() {
+    let program_id = id();
+    let mut program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let treasury_manager = Keypair::new();
+    add_program_data(&mut program_test, &treasury_manager.pubkey());
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let (treasury_account, _) = get_treasury_account();
+    let payer_pubkey = context.payer.pubkey();
+    let recipient = Keypair::new().pubkey();
+    bind_treasury_recipient(&mut context, &treasury_manager, &recipient).await.unwrap();
+
+    let ix = light_up_stars_hundred(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        treasury_account,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let balance = context.banks_client.get_balance(treasury_account).await.expect("get_balance");
+    let rent_exempt_minimum = context.banks_client.get_rent().await.unwrap().minimum_balance(0);
+
+    let ix = withdraw_amount(
+        program_id,
+        treasury_manager.pubkey(),
+        treasury_account,
+        recipient,
+        10 * LAMPORTS_PER_STAR,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &treasury_manager], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let remaining = context.banks_client.get_balance(treasury_account).await.expect("get_balance");
+    assert_eq!(remaining, balance - 10 * LAMPORTS_PER_STAR);
+    assert!(remaining >= rent_exempt_minimum);
+    assert_eq!(context.banks_client.get_balance(recipient).await.expect("get_balance"), 10 * LAMPORTS_PER_STAR);
+
+    // the rent exempt reserve can not be withdrawn
+    let ix = withdraw_amount(
+        program_id,
+        treasury_manager.pubkey(),
+        treasury_account,
+        recipient,
+        remaining - rent_exempt_minimum + 1,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &treasury_manager], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::InsufficientFunds as u32),
+        ),
+    );
+}
+
+#[tokio::test]
+async fn test_withdraw() {
+    let program_id = id();
+    let mut program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let treasury_manager = Keypair::new();
+    add_program_data(&mut program_test, &treasury_manager.pubkey());
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let (treasury_account, _) = get_treasury_account();
+    let payer_pubkey = context.payer.pubkey();
+    let recipient = Keypair::new().pubkey();
+    bind_treasury_recipient(&mut context, &treasury_manager, &recipient).await.unwrap();
+
+    let ix = light_up_stars_hundred(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        treasury_account,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let balance = context.banks_client.get_balance(treasury_account).await.expect("get_balance");
+    let rent_exempt_minimum = context.banks_client.get_rent().await.unwrap().minimum_balance(0);
+    assert_eq!(balance, 100 * LAMPORTS_PER_STAR);
+
+    let intruder = Keypair::new();
+    let ix = withdraw(
+        program_id,
+        intruder.pubkey(),
+        treasury_account,
+        intruder.pubkey(),
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &intruder], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::NotTreasuryManager as u32),
+        ),
+    );
+    assert_eq!(context.banks_client.get_balance(treasury_account).await.expect("get_balance"), balance);
+
+    let ix = withdraw(
+        program_id,
+        treasury_manager.pubkey(),
+        treasury_account,
+        recipient,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &treasury_manager], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    // everything but the rent exempt reserve reached the recipient
+    assert_eq!(context.banks_client.get_balance(recipient).await.expect("get_balance"), balance - rent_exempt_minimum);
+    assert_eq!(context.banks_client.get_balance(treasury_account).await.expect("get_balance"), rent_exempt_minimum);
+}
+
+#[tokio::test]
+async fn test_withdraw_unauthorized() {
+    let program_id = id();
+    let mut program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    add_program_data(&mut program_test, &Keypair::new().pubkey());
+    let mut context = program_test.start_with_context().await;
+
+    let (treasury_account, _) = get_treasury_account();
+    let payer_pubkey = context.payer.pubkey();
+
+    let ix = withdraw(
+        program_id,
+        payer_pubkey,
+        treasury_account,
+        payer_pubkey,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::NotTreasuryManager as u32),
+        ),
+    );
+}
+
+#[tokio::test]
+async fn test_withdraw_amount_requires_treasury_manager() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let (treasury_account, _) = get_treasury_account();
+    let payer_pubkey = context.payer.pubkey();
+
+    let ix = light_up_stars_hundred(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        treasury_account,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let balance = context.banks_client.get_balance(treasury_account).await.expect("get_balance");
+
+    let ix = withdraw_amount(
+        program_id,
+        payer_pubkey,
+        treasury_account,
+        payer_pubkey,
+        LAMPORTS_PER_STAR,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::NotTreasuryManager as u32),
+        ),
+    );
+    assert_eq!(context.banks_client.get_balance(treasury_account).await.expect("get_balance"), balance);
+}
+
+#[tokio::test]
+async fn test_freeze_collection() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+
+    let ix = freeze_collection(program_id, collection_keypair.pubkey(), payer_pubkey);
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert!(get_collection(&mut context, &collection_keypair.pubkey()).await.frozen);
+
+    let ix = update_collection_metadata(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        UpdateCollectionMetadataArgs {
+            title: Some("new title".to_string()),
+            ..UpdateCollectionMetadataArgs::default()
+        },
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::CollectionFrozen as u32),
+        ),
+    );
+
+    let ix = transfer_authority(program_id, collection_keypair.pubkey(), payer_pubkey, Keypair::new().pubkey());
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::CollectionFrozen as u32),
+        ),
+    );
+
+    let collection = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    assert_eq!(collection.title, "test collection".to_string());
+    assert_eq!(collection.authority, payer_pubkey);
+}
+
+#[tokio::test]
+async fn test_create_collection_account_with_royalty() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    let args = CreateCollectionAccountArgs {
+        royalty_bps: Some(500),
+        star_price: None,
+        secondary_authority: None,
+        metadata_uri: None,
+        ..collection_args()
+    };
+    create_collection(&mut context, &collection_keypair, args).await.unwrap();
+    let collection = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    assert_eq!(collection.royalty_bps(), Some(500));
+    assert_eq!(collection.version, COLLECTION_ACCOUNT_VERSION);
+}
+
+#[tokio::test]
+async fn test_close_account_unauthorized() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+    let intruder = Keypair::new();
+
+    let ix = close_account(
+        program_id,
+        collection_keypair.pubkey(),
+        intruder.pubkey(),
+        intruder.pubkey(),
+        AccountType::CollectionAccount,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &intruder], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::NotCollectionAuthority as u32),
+        ),
+    );
+    assert!(get_collection(&mut context, &collection_keypair.pubkey()).await.is_initialized());
+}
+
+#[tokio::test]
+async fn test_close_account_recipient_overflow() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+
+    // a recipient this rich cannot take the collection rent without wrapping
+    let recipient = Pubkey::new_unique();
+    let account = AccountSharedData::new(u64::MAX - 1, 0, &system_program::id());
+    context.set_account(&recipient, &account);
+
+    let ix = close_account(
+        program_id,
+        collection_keypair.pubkey(),
+        recipient,
+        payer_pubkey,
+        AccountType::CollectionAccount,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::Overflow as u32),
+        ),
+    );
+    assert!(get_collection(&mut context, &collection_keypair.pubkey()).await.is_initialized());
+}
+
+#[tokio::test]
+async fn test_close_treasury_with_spoofed_type() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let (treasury_account, _) = get_treasury_account();
+    let payer_pubkey = context.payer.pubkey();
+
+    let ix = light_up_stars_hundred(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        treasury_account,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let balance = context.banks_client.get_balance(treasury_account).await.unwrap();
+
+    let ixs = [
+        close_account(program_id, treasury_account, payer_pubkey, payer_pubkey, AccountType::CollectionAccount),
+        close_index_account(program_id, treasury_account, payer_pubkey, payer_pubkey, collection_keypair.pubkey()),
+    ];
+    for ix in ixs.iter() {
+        let mut transaction = Transaction::new_with_payer(
+            std::slice::from_ref(ix),
+            Some(&payer_pubkey),
+        );
+        transaction.sign(&[&context.payer], context.last_blockhash);
+        assert_eq!(
+            context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(CollectionError::AccountTypeMismatch as u32),
+            ),
+        );
+    }
+
+    // a collection claimed to be an index account
+    let ix = close_index_account(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        payer_pubkey,
+        collection_keypair.pubkey(),
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::AccountTypeMismatch as u32),
+        ),
+    );
+    assert_eq!(context.banks_client.get_balance(treasury_account).await.unwrap(), balance);
+}
+
+#[tokio::test]
+async fn test_close_forged_index_account() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+
+    // index data at an address that is not the index of its mint
+    let forged = Pubkey::new_unique();
+    let (_, bump) = find_index_address(&program_id, &Pubkey::new_unique());
+    let index_data = CollectionIndexAccountData::new(collection_keypair.pubkey(), Pubkey::new_unique(), 0, bump);
+    let mut account = AccountSharedData::new(1_000_000, CollectionIndexAccountData::LEN, &program_id);
+    account.set_data(index_data.try_to_vec().unwrap());
+    context.set_account(&forged, &account);
+
+    let ix = close_index_account(program_id, forged, payer_pubkey, payer_pubkey, collection_keypair.pubkey());
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::AccountTypeMismatch as u32),
+        ),
+    );
+}
+
+#[tokio::test]
+async fn test_close_index_account() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let (mint_keypair, nft_ata) = create_nft(&mut context).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+
+    let ix = include_token(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        mint_keypair.pubkey(),
+        nft_ata,
+        payer_pubkey,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let (index_account, _) = get_index_account(&mint_keypair.pubkey());
+
+    let intruder = Keypair::new();
+    let ix = close_index_account(
+        program_id,
+        index_account,
+        intruder.pubkey(),
+        intruder.pubkey(),
+        collection_keypair.pubkey(),
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &intruder], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::NotCollectionAuthority as u32),
+        ),
+    );
+
+    let ix = close_index_account(
+        program_id,
+        index_account,
+        payer_pubkey,
+        payer_pubkey,
+        collection_keypair.pubkey(),
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert!(context.banks_client.get_account(index_account).await.expect("get_account").is_none());
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.supply, 0);
+}
+
+#[tokio::test]
+async fn test_close_non_empty_collection() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let (mint_keypair, nft_ata) = create_nft(&mut context).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+
+    let ix = include_token(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        mint_keypair.pubkey(),
+        nft_ata,
+        payer_pubkey,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let (index_account, _) = get_index_account(&mint_keypair.pubkey());
+
+    let ix = close_account(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        payer_pubkey,
+        AccountType::CollectionAccount,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::CollectionNotEmpty as u32),
+        ),
+    );
+
+    // closing the index first empties the collection
+    let ixs = [
+        close_index_account(program_id, index_account, payer_pubkey, payer_pubkey, collection_keypair.pubkey()),
+        close_account(program_id, collection_keypair.pubkey(), payer_pubkey, payer_pubkey, AccountType::CollectionAccount),
+    ];
+    let mut transaction = Transaction::new_with_payer(
+        &ixs,
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert!(context.banks_client.get_account(collection_keypair.pubkey()).await.expect("get_account").is_none());
+}
+
+#[tokio::test]
+async fn test_close_collection_with_allowlist() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+    let transaction = Transaction::new_signed_with_payer(
+        &[add_to_allowlist(program_id, collection_keypair.pubkey(), payer_pubkey, payer_pubkey, Pubkey::new_unique())],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    // the allowlist would keep its rent without its collection
+    let transaction = Transaction::new_signed_with_payer(
+        &[close_account(program_id, collection_keypair.pubkey(), payer_pubkey, payer_pubkey, AccountType::CollectionAccount)],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::CollectionNotEmpty as u32)),
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            close_allowlist_account(program_id, collection_keypair.pubkey(), payer_pubkey, payer_pubkey),
+            close_account(program_id, collection_keypair.pubkey(), payer_pubkey, payer_pubkey, AccountType::CollectionAccount),
+        ],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let (allowlist_account, _) = find_allowlist_address(&program_id, &collection_keypair.pubkey());
+    assert!(context.banks_client.get_account(allowlist_account).await.unwrap().is_none());
+    assert!(context.banks_client.get_account(collection_keypair.pubkey()).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_close_collection_with_cooldown() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+    let transaction = Transaction::new_signed_with_payer(
+        &[set_cooldown(program_id, collection_keypair.pubkey(), payer_pubkey, payer_pubkey, 10)],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    // the cooldown account would keep its rent without its collection
+    let transaction = Transaction::new_signed_with_payer(
+        &[close_account(program_id, collection_keypair.pubkey(), payer_pubkey, payer_pubkey, AccountType::CollectionAccount)],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::CollectionNotEmpty as u32)),
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            close_cooldown_account(program_id, collection_keypair.pubkey(), payer_pubkey, payer_pubkey),
+            close_account(program_id, collection_keypair.pubkey(), payer_pubkey, payer_pubkey, AccountType::CollectionAccount),
+        ],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let (cooldown_account, _) = find_cooldown_address(&program_id, &collection_keypair.pubkey());
+    assert!(context.banks_client.get_account(cooldown_account).await.unwrap().is_none());
+    assert!(context.banks_client.get_account(collection_keypair.pubkey()).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_include_token_paid_by_collection() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let (mint_keypair, nft_ata) = create_nft(&mut context).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+
+    let ix = include_token(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        mint_keypair.pubkey(),
+        nft_ata,
+        collection_keypair.pubkey(),
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &collection_keypair], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::InvalidAccountArguments as u32),
+        ),
+    );
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.supply, 0);
+}
+
+#[tokio::test]
+async fn test_allowlist() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let (allowed_mint, allowed_ata) = create_nft(&mut context).await.unwrap();
+    let (other_mint, other_ata) = create_nft(&mut context).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+    let (allowlist_account, _) = find_allowlist_address(&program_id, &collection_keypair.pubkey());
+
+    let ix = add_to_allowlist(program_id, collection_keypair.pubkey(), payer_pubkey, payer_pubkey, allowed_mint.pubkey());
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert!(get_collection(&mut context, &collection_keypair.pubkey()).await.has_allowlist);
+    let account = context.banks_client.get_account(allowlist_account).await.expect("get_account").unwrap();
+    let allowlist = AllowlistAccountData::try_from_slice_unchecked(&account.data).unwrap();
+    assert_eq!(allowlist.mints, vec![allowed_mint.pubkey()]);
+
+    // a mint missing from the allowlist is refused
+    let ix = include_token(program_id, collection_keypair.pubkey(), payer_pubkey, other_mint.pubkey(), other_ata, payer_pubkey);
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::MintNotAllowed as u32),
+        ),
+    );
+
+    // so is an allowed mint when the allowlist is left out
+    let mut ix = include_token(program_id, collection_keypair.pubkey(), payer_pubkey, allowed_mint.pubkey(), allowed_ata, payer_pubkey);
+    ix.accounts.pop();
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::MintNotAllowed as u32),
+        ),
+    );
+
+    let ix = include_token(program_id, collection_keypair.pubkey(), payer_pubkey, allowed_mint.pubkey(), allowed_ata, payer_pubkey);
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.supply, 1);
+
+    let ix = remove_from_allowlist(program_id, collection_keypair.pubkey(), payer_pubkey, other_mint.pubkey());
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::MintNotAllowed as u32),
+        ),
+    );
+
+    // removing the allowlist opens the collection to every mint again
+    let ixs = [
+        remove_from_allowlist(program_id, collection_keypair.pubkey(), payer_pubkey, allowed_mint.pubkey()),
+        close_allowlist_account(program_id, collection_keypair.pubkey(), payer_pubkey, payer_pubkey),
+        include_token(program_id, collection_keypair.pubkey(), payer_pubkey, other_mint.pubkey(), other_ata, payer_pubkey),
+    ];
+    let mut transaction = Transaction::new_with_payer(
+        &ixs,
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let collection = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    assert!(!collection.has_allowlist);
+    assert_eq!(collection.supply, 2);
+    assert!(context.banks_client.get_account(allowlist_account).await.expect("get_account").is_none());
+}
+
+#[tokio::test]
+async fn test_light_up_stars_once_rate_limit() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+
+    // one free star left in the current slot
+    let slot = context.banks_client.get_sysvar::<Clock>().await.unwrap().slot;
+    let mut account = context.banks_client
+        .get_account(collection_keypair.pubkey())
+        .await
+        .expect("get_account")
+        .unwrap();
+    let mut collection: CollectionAccountData = try_from_slice_unchecked(&account.data).unwrap();
+    collection.last_star_slot = slot;
+    collection.stars_this_slot = MAX_FREE_STARS_PER_SLOT - 1;
+    account.data = collection.try_to_vec().unwrap();
+    context.set_account(&collection_keypair.pubkey(), &account.into());
+
+    let ixs = [
+        light_up_stars_once(program_id, collection_keypair.pubkey()),
+        light_up_stars_once(program_id, collection_keypair.pubkey()),
+    ];
+    let mut transaction = Transaction::new_with_payer(
+        &ixs,
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(1, InstructionError::Custom(CollectionError::RateLimited as u32)),
+    );
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, 0);
+
+    // the next slot starts counting again
+    context.warp_to_slot(slot + 1).unwrap();
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(
+        &ixs,
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let collection = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    assert_eq!(collection.stars, 2);
+    assert_eq!(collection.last_star_slot, slot + 1);
+    assert_eq!(collection.stars_this_slot, 2);
+}
+
+#[tokio::test]
+async fn test_light_up_stars_once_legacy_account() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+    make_legacy_collection(&mut context, &collection_keypair.pubkey()).await;
+
+    // without room for the counters the free stars could not be throttled
+    let ixs = vec![light_up_stars_once(program_id, collection_keypair.pubkey()); MAX_FREE_STARS_PER_SLOT as usize + 1];
+    let transaction = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::MigrationRequired as u32)),
+    );
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, 0);
+
+    // once migrated the eleventh star of the slot is refused
+    let mut ixs = ixs;
+    ixs.insert(0, migrate_account(program_id, collection_keypair.pubkey(), payer_pubkey, payer_pubkey));
+    let transaction = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            MAX_FREE_STARS_PER_SLOT as u8 + 1,
+            InstructionError::Custom(CollectionError::RateLimited as u32),
+        ),
+    );
+}
+
+#[tokio::test]
+async fn test_light_up_stars_once_rewrites_in_place() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    let args = CreateCollectionAccountArgs { royalty_bps: Some(500), ..collection_args() };
+    create_collection(&mut context, &collection_keypair, args).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+    let before = context.banks_client.get_account(collection_keypair.pubkey()).await.unwrap().unwrap();
+
+    let mut transaction = Transaction::new_with_payer(
+        &[light_up_stars_once(program_id, collection_keypair.pubkey())],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let slot = context.banks_client.get_sysvar::<Clock>().await.unwrap().slot;
+
+    // re-encoding the account with only the star count and the counters changed gives the same bytes
+    let after = context.banks_client.get_account(collection_keypair.pubkey()).await.unwrap().unwrap();
+    let mut expected: CollectionAccountData = try_from_slice_unchecked(&before.data).unwrap();
+    expected.stars += 1;
+    expected.last_star_slot = slot;
+    expected.stars_this_slot = 1;
+    let expected = expected.try_to_vec().unwrap();
+    assert_eq!(after.data.len(), before.data.len());
+    assert_eq!(&after.data[..expected.len()], &expected[..]);
+    assert_eq!(&after.data[expected.len()..], &before.data[expected.len()..]);
+}
+
+#[tokio::test]
+async fn test_light_up_stars_once_without_collection_signature() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+
+    // a free star needs no signature of the collection, anyone may light it
+    let ix = light_up_stars_once(program_id, collection_keypair.pubkey());
+    assert!(!ix.accounts[0].is_signer);
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, 1);
+}
+
+#[tokio::test]
+async fn test_light_up_stars_ceiling() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let (treasury_account, _) = get_treasury_account();
+    let payer_pubkey = context.payer.pubkey();
+
+    // start a hundred stars below the ceiling
+    let mut account = context.banks_client
+        .get_account(collection_keypair.pubkey())
+        .await
+        .expect("get_account")
+        .unwrap();
+    let mut collection: CollectionAccountData = try_from_slice_unchecked(&account.data).unwrap();
+    collection.stars = MAX_STARS - 100;
+    account.data = collection.try_to_vec().unwrap();
+    context.set_account(&collection_keypair.pubkey(), &account.into());
+
+    let ix = light_up_stars_hundred(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        treasury_account,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, MAX_STARS);
+
+    let ix = light_up_stars_custom(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        treasury_account,
+        1,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::StarCeilingReached as u32),
+        ),
+    );
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, MAX_STARS);
+}
+
+#[tokio::test]
+async fn test_light_up_stars_ten_thousand() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let (treasury_account, _) = get_treasury_account();
+    let payer_pubkey = context.payer.pubkey();
+
+    let ix = light_up_stars_ten_thousand(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        treasury_account,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, 10_000);
+    let balance = context.banks_client.get_balance(treasury_account).await.expect("get_balance");
+    assert_eq!(balance, 10_000 * LAMPORTS_PER_STAR);
+
+    // a tier past the ceiling is refused as a whole
+    let mut account = context.banks_client
+        .get_account(collection_keypair.pubkey())
+        .await
+        .expect("get_account")
+        .unwrap();
+    let mut collection: CollectionAccountData = try_from_slice_unchecked(&account.data).unwrap();
+    collection.stars = MAX_STARS - 9_999;
+    account.data = collection.try_to_vec().unwrap();
+    context.set_account(&collection_keypair.pubkey(), &account.into());
+
+    let ix = light_up_stars_ten_thousand(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        treasury_account,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    let blockhash = context.banks_client.get_new_latest_blockhash(&context.last_blockhash).await.unwrap();
+    transaction.sign(&[&context.payer], blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::StarCeilingReached as u32),
+        ),
+    );
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, MAX_STARS - 9_999);
+    let balance = context.banks_client.get_balance(treasury_account).await.expect("get_balance");
+    assert_eq!(balance, 10_000 * LAMPORTS_PER_STAR);
+}
+
+#[tokio::test]
+async fn test_set_tags() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+
+    let tags = vec!["jazz".to_string(), "rock".to_string()];
+    let ix = set_tags(program_id, collection_keypair.pubkey(), payer_pubkey, tags.clone());
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let collection = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    assert_eq!(collection.tags, Some(tags.clone()));
+    assert_eq!(collection.title, "test collection".to_string());
+
+    let ix = set_tags(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        (0..7).map(|i| format!("tag{}", i)).collect(),
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::InvalidMetadata as u32),
+        ),
+    );
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.tags, Some(tags));
+}
+
+#[tokio::test]
+async fn test_add_tag() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    let mut args = collection_args();
+    args.tags = Some((0..5).map(|i| format!("tag{}", i)).collect());
+    create_collection(&mut context, &collection_keypair, args).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+
+    let ix = add_tag(program_id, collection_keypair.pubkey(), payer_pubkey, "tag5".to_string());
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let tags: Vec<String> = (0..6).map(|i| format!("tag{}", i)).collect();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.tags, Some(tags.clone()));
+
+    // a seventh tag is one more than a collection can hold
+    let ix = add_tag(program_id, collection_keypair.pubkey(), payer_pubkey, "tag6".to_string());
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::InvalidMetadata as u32),
+        ),
+    );
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.tags, Some(tags));
+}
+
+#[tokio::test]
+async fn test_set_description() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+    let before = get_collection(&mut context, &collection_keypair.pubkey()).await;
+
+    let ix = set_description(program_id, collection_keypair.pubkey(), payer_pubkey, "a new description".to_string());
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let after = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    assert_eq!(after.description, "a new description".to_string());
+    assert_eq!(after.title, before.title);
+    assert_eq!(after.symbol, before.symbol);
+    assert_eq!(after.icon_image, before.icon_image);
+    assert_eq!(after.tags, before.tags);
+    assert_eq!(after.star_price, before.star_price);
+
+    // one character past MAX_DESCRIPTION_LENGTH
+    let ix = set_description(program_id, collection_keypair.pubkey(), payer_pubkey, "a".repeat(801));
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::InvalidMetadata as u32),
+        ),
+    );
+
+    // only the authority may change it
+    let stranger = Keypair::new();
+    let ix = set_description(program_id, collection_keypair.pubkey(), stranger.pubkey(), "stolen".to_string());
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &stranger], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::NotCollectionAuthority as u32),
+        ),
+    );
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.description, "a new description".to_string());
+}
+
+#[tokio::test]
+async fn test_set_banner() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+    let banner = Some("ar://bNbA3TEQVL60xlgCcqdz4ZPHFZ711cZ3hmkpGttDt_U".to_string());
+
+    let ix = set_banner(program_id, collection_keypair.pubkey(), payer_pubkey, banner.clone());
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let collection = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    assert_eq!(collection.banner, banner);
+    assert_eq!(collection.title, collection_args().title);
+
+    // banners must use one of the accepted schemes
+    let ix = set_banner(program_id, collection_keypair.pubkey(), payer_pubkey, Some("ftp://banner".to_string()));
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::InvalidMetadata as u32),
+        ),
+    );
+
+    let ix = set_banner(program_id, collection_keypair.pubkey(), payer_pubkey, None);
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.banner, None);
+}
+
+#[tokio::test]
+async fn test_set_metadata_uri() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    let mut args = collection_args();
+    args.metadata_uri = Some("ar://5Dr5BAwBwFwGbnA9MMyMntKUa4mKwLZa2UDS2Ct8Px8".to_string());
+    create_collection(&mut context, &collection_keypair, args).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+    assert_eq!(
+        get_collection(&mut context, &collection_keypair.pubkey()).await.metadata_uri,
+        Some("ar://5Dr5BAwBwFwGbnA9MMyMntKUa4mKwLZa2UDS2Ct8Px8".to_string()),
+    );
+
+    let metadata_uri = Some("ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi".to_string());
+    let ix = set_metadata_uri(program_id, collection_keypair.pubkey(), payer_pubkey, metadata_uri.clone());
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let collection = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    assert_eq!(collection.metadata_uri, metadata_uri);
+    assert_eq!(collection.title, collection_args().title);
+
+    let ix = set_metadata_uri(program_id, collection_keypair.pubkey(), payer_pubkey, Some("ftp://metadata.json".to_string()));
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::InvalidMetadata as u32),
+        ),
+    );
+
+    let ix = set_metadata_uri(program_id, collection_keypair.pubkey(), payer_pubkey, None);
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.metadata_uri, None);
+}
+
+#[tokio::test]
+async fn test_remove_tag() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+
+    let ix = remove_tag(program_id, collection_keypair.pubkey(), payer_pubkey, "ART".to_string());
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let tags = Some(vec!["music".to_string()]);
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.tags, tags);
+
+    let ix = remove_tag(program_id, collection_keypair.pubkey(), payer_pubkey, "art".to_string());
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::TagNotFound as u32),
+        ),
+    );
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.tags, tags);
+}
+
+#[tokio::test]
+async fn test_index_account_is_not_a_collection() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let (mint_keypair, nft_ata) = create_nft(&mut context).await.unwrap();
+    let (treasury_account, _) = get_treasury_account();
+    let payer_pubkey = context.payer.pubkey();
+
+    let ix = include_token(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        mint_keypair.pubkey(),
+        nft_ata,
+        payer_pubkey,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let (index_account, _) = get_index_account(&mint_keypair.pubkey());
+
+    let ix = light_up_stars_hundred(
+        program_id,
+        index_account,
+        payer_pubkey,
+        treasury_account,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::InvalidAccountType as u32),
+        ),
+    );
+
+    let ix = close_account(
+        program_id,
+        index_account,
+        payer_pubkey,
+        payer_pubkey,
+        AccountType::CollectionAccount,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::AccountTypeMismatch as u32),
+        ),
+    );
+}
+
+#[tokio::test]
+async fn test_light_up_stars_with_token() {
+    let program_id = id();
+    let mut program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let treasury_manager = Keypair::new();
+    add_program_data(&mut program_test, &treasury_manager.pubkey());
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let (treasury_account, _) = get_treasury_account();
+    let payer_pubkey = context.payer.pubkey();
+
+    let mint_keypair = Keypair::new();
+    create_mint(&mut context, &mint_keypair, &payer_pubkey, 6, None).await.unwrap();
+    create_associated_account(&mut context, &payer_pubkey, &mint_keypair.pubkey()).await.unwrap();
+    create_associated_account(&mut context, &treasury_account, &mint_keypair.pubkey()).await.unwrap();
+    let payer_token_account = get_associated_token_address(&payer_pubkey, &mint_keypair.pubkey());
+    let treasury_token_account = get_associated_token_address(&treasury_account, &mint_keypair.pubkey());
+    mint_tokens(&mut context, &mint_keypair.pubkey(), &payer_token_account, 1000, &payer_pubkey, None).await.unwrap();
+
+    // no token lights stars before the treasury manager picked one
+    let ix = light_up_stars_with_token(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        payer_token_account,
+        treasury_token_account,
+        250,
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::WrongStarMint as u32)),
+    );
+
+    // the star token lives in the treasury config, which binding a recipient creates
+    let star_token = Some((mint_keypair.pubkey(), 100));
+    let transaction = Transaction::new_signed_with_payer(
+        &[set_star_token(program_id, treasury_manager.pubkey(), payer_pubkey, star_token)],
+        Some(&payer_pubkey),
+        &[&context.payer, &treasury_manager],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::Uninitialized as u32)),
+    );
+    bind_treasury_recipient(&mut context, &treasury_manager, &payer_pubkey).await.unwrap();
+
+    // configs bound before the star token existed are grown on the way
+    let treasury_config_address = find_treasury_config_address(&program_id).0;
+    let mut treasury_config = context.banks_client.get_account(treasury_config_address).await.unwrap().unwrap();
+    treasury_config.data.truncate(1 + 32);
+    context.set_account(&treasury_config_address, &treasury_config.into());
+
+    let intruder = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[set_star_token(program_id, intruder.pubkey(), payer_pubkey, star_token)],
+        Some(&payer_pubkey),
+        &[&context.payer, &intruder],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::NotTreasuryManager as u32)),
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[set_star_token(program_id, treasury_manager.pubkey(), payer_pubkey, Some((mint_keypair.pubkey(), 0)))],
+        Some(&payer_pubkey),
+        &[&context.payer, &treasury_manager],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::InvalidInstructionArguments as u32)),
+    );
+    let blockhash = context.banks_client.get_new_latest_blockhash(&context.last_blockhash).await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[set_star_token(program_id, treasury_manager.pubkey(), payer_pubkey, star_token)],
+        Some(&payer_pubkey),
+        &[&context.payer, &treasury_manager],
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let treasury_config = context.banks_client.get_account(treasury_config_address).await.unwrap().unwrap();
+    assert_eq!(treasury_config.data.len(), TreasuryConfigAccountData::LEN);
+    let treasury_config = TreasuryConfigAccountData::try_from_slice_unchecked(&treasury_config.data).unwrap();
+    assert_eq!(treasury_config.treasury_recipient, payer_pubkey);
+    assert_eq!(treasury_config.star_mint, Some(mint_keypair.pubkey()));
+    assert_eq!(treasury_config.star_token_price, 100);
+
+    // tipping into a token account the treasury does not own is rejected
+    let ix = light_up_stars_with_token(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        payer_token_account,
+        payer_token_account,
+        250,
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        blockhash,
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::InvalidTreasuryAccount as u32),
+        ),
+    );
+
+    // a freshly printed mint buys nothing, whether it is paid or received
+    let foreign_mint_keypair = Keypair::new();
+    create_mint(&mut context, &foreign_mint_keypair, &payer_pubkey, 0, None).await.unwrap();
+    create_associated_account(&mut context, &payer_pubkey, &foreign_mint_keypair.pubkey()).await.unwrap();
+    create_associated_account(&mut context, &treasury_account, &foreign_mint_keypair.pubkey()).await.unwrap();
+    let foreign_payer_token_account = get_associated_token_address(&payer_pubkey, &foreign_mint_keypair.pubkey());
+    let foreign_treasury_token_account = get_associated_token_address(&treasury_account, &foreign_mint_keypair.pubkey());
+    mint_tokens(&mut context, &foreign_mint_keypair.pubkey(), &foreign_payer_token_account, 1_000_000, &payer_pubkey, None).await.unwrap();
+    for (source_token_account, treasury_token_account) in [
+        (foreign_payer_token_account, foreign_treasury_token_account),
+        (foreign_payer_token_account, treasury_token_account),
+    ] {
+        let ix = light_up_stars_with_token(
+            program_id,
+            collection_keypair.pubkey(),
+            payer_pubkey,
+            source_token_account,
+            treasury_token_account,
+            1_000_000,
+        );
+        let transaction = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer_pubkey),
+            &[&context.payer],
+            blockhash,
+        );
+        assert_eq!(
+            context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::WrongStarMint as u32)),
+        );
+    }
+
+    // less than the price of a single star lights none
+    let ix = light_up_stars_with_token(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        payer_token_account,
+        treasury_token_account,
+        99,
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        blockhash,
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::InvalidInstructionArguments as u32)),
+    );
+
+    let ix = light_up_stars_with_token(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        payer_token_account,
+        treasury_token_account,
+        250,
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    // only the two whole stars are charged
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, 2);
+    let treasury_token = context.banks_client
+        .get_account(treasury_token_account)
+        .await
+        .expect("get_account")
+        .unwrap();
+    assert_eq!(spl_token::state::Account::unpack(&treasury_token.data).unwrap().amount, 200);
+    let payer_token = context.banks_client
+        .get_account(payer_token_account)
+        .await
+        .expect("get_account")
+        .unwrap();
+    assert_eq!(spl_token::state::Account::unpack(&payer_token.data).unwrap().amount, 800);
+
+    // rebinding the recipient keeps the star token
+    bind_treasury_recipient(&mut context, &treasury_manager, &treasury_manager.pubkey()).await.unwrap();
+    let treasury_config = context.banks_client.get_account(treasury_config_address).await.unwrap().unwrap();
+    let treasury_config = TreasuryConfigAccountData::try_from_slice_unchecked(&treasury_config.data).unwrap();
+    assert_eq!(treasury_config.treasury_recipient, treasury_manager.pubkey());
+    assert_eq!(treasury_config.star_mint, Some(mint_keypair.pubkey()));
+
+    // the tokens only leave through the treasury manager, to the bound recipient
+    create_associated_account(&mut context, &treasury_manager.pubkey(), &mint_keypair.pubkey()).await.unwrap();
+    let recipient_token_account = get_associated_token_address(&treasury_manager.pubkey(), &mint_keypair.pubkey());
+    let transaction = Transaction::new_signed_with_payer(
+        &[withdraw_token(program_id, intruder.pubkey(), treasury_account, treasury_token_account, recipient_token_account, 200)],
+        Some(&payer_pubkey),
+        &[&context.payer, &intruder],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::NotTreasuryManager as u32)),
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[withdraw_token(program_id, treasury_manager.pubkey(), treasury_account, treasury_token_account, payer_token_account, 200)],
+        Some(&payer_pubkey),
+        &[&context.payer, &treasury_manager],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::WrongRecipient as u32)),
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[withdraw_token(program_id, treasury_manager.pubkey(), treasury_account, treasury_token_account, recipient_token_account, 200)],
+        Some(&payer_pubkey),
+        &[&context.payer, &treasury_manager],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    for (token_account, amount) in [(treasury_token_account, 0), (recipient_token_account, 200)] {
+        let token_account = context.banks_client.get_account(token_account).await.unwrap().unwrap();
+        assert_eq!(spl_token::state::Account::unpack(&token_account.data).unwrap().amount, amount);
+    }
+}
+
+#[tokio::test]
+async fn test_set_verified() {
+    let program_id = id();
+    let curator = Keypair::new();
+    let mut program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    add_program_data(&mut program_test, &curator.pubkey());
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+    assert!(!get_collection(&mut context, &collection_keypair.pubkey()).await.verified);
+
+    // not even the collection authority can verify its own collection
+    let ix = set_verified(program_id, collection_keypair.pubkey(), payer_pubkey, true);
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::NotCurator as u32),
+        ),
+    );
+
+    for verified in [true, false] {
+        let ix = set_verified(program_id, collection_keypair.pubkey(), curator.pubkey(), verified);
+        let mut transaction = Transaction::new_with_payer(
+            &[ix],
+            Some(&payer_pubkey),
+        );
+        transaction.sign(&[&context.payer, &curator], context.last_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+        assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.verified, verified);
+    }
+}
+
+#[tokio::test]
+async fn test_adjust_stars() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+
+    let intruder = Keypair::new();
+    let ix = adjust_stars(program_id, collection_keypair.pubkey(), intruder.pubkey(), 10);
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &intruder], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::NotCollectionAuthority as u32),
+        ),
+    );
+
+    // the last correction takes away more stars than are left
+    for (delta, stars) in [(50, 50), (-20, 30), (-100, 0)] {
+        let ix = adjust_stars(program_id, collection_keypair.pubkey(), payer_pubkey, delta);
+        let mut transaction = Transaction::new_with_payer(
+            &[ix],
+            Some(&payer_pubkey),
+        );
+        transaction.sign(&[&context.payer], context.last_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+        assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, stars);
+    }
+}
+
+#[tokio::test]
+async fn test_set_category() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    let mut args = collection_args();
+    args.category = Some(CollectionCategory::Music);
+    create_collection(&mut context, &collection_keypair, args).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.category, Some(CollectionCategory::Music));
+
+    let intruder = Keypair::new();
+    let ix = set_category(program_id, collection_keypair.pubkey(), intruder.pubkey(), Some(CollectionCategory::Art));
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &intruder], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::NotCollectionAuthority as u32),
+        ),
+    );
+
+    for category in [Some(CollectionCategory::Gaming), None] {
+        let ix = set_category(program_id, collection_keypair.pubkey(), payer_pubkey, category);
+        let mut transaction = Transaction::new_with_payer(
+            &[ix],
+            Some(&payer_pubkey),
+        );
+        transaction.sign(&[&context.payer], context.last_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+        let collection = get_collection(&mut context, &collection_keypair.pubkey()).await;
+        assert_eq!(collection.category, category);
+        assert_eq!(collection.title, collection_args().title);
+    }
+}
+
+#[tokio::test]
+async fn test_set_treasury_recipient() {
+    let program_id = id();
+    let mut program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let treasury_manager = Keypair::new();
+    add_program_data(&mut program_test, &treasury_manager.pubkey());
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let (treasury_account, _) = get_treasury_account();
+    let payer_pubkey = context.payer.pubkey();
+    let recipient = Keypair::new().pubkey();
+
+    let ix = light_up_stars_hundred(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        treasury_account,
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    // nothing leaves the treasury before a recipient is bound
+    let ix = withdraw(program_id, treasury_manager.pubkey(), treasury_account, recipient);
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &treasury_manager], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::WrongRecipient as u32),
+        ),
+    );
+
+    // only the upgrade authority binds the recipient
+    let intruder = Keypair::new();
+    let ix = set_treasury_recipient(program_id, intruder.pubkey(), payer_pubkey, intruder.pubkey());
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &intruder], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::NotTreasuryManager as u32),
+        ),
+    );
+
+    bind_treasury_recipient(&mut context, &treasury_manager, &recipient).await.unwrap();
+    let treasury_config = context.banks_client
+        .get_account(find_treasury_config_address(&program_id).0)
+        .await
+        .unwrap()
+        .unwrap();
+    let treasury_config = TreasuryConfigAccountData::try_from_slice_unchecked(&treasury_config.data).unwrap();
+    assert!(treasury_config.is_initialized());
+    assert_eq!(treasury_config.treasury_recipient, recipient);
+
+    let ix = withdraw_amount(program_id, treasury_manager.pubkey(), treasury_account, payer_pubkey, LAMPORTS_PER_STAR);
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &treasury_manager], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::WrongRecipient as u32),
+        ),
+    );
+
+    let ix = withdraw_amount(program_id, treasury_manager.pubkey(), treasury_account, recipient, 10 * LAMPORTS_PER_STAR);
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &treasury_manager], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(context.banks_client.get_balance(recipient).await.expect("get_balance"), 10 * LAMPORTS_PER_STAR);
+
+    // rebinding moves withdrawals over to the new recipient
+    bind_treasury_recipient(&mut context, &treasury_manager, &payer_pubkey).await.unwrap();
+    let ix = withdraw_amount(program_id, treasury_manager.pubkey(), treasury_account, recipient, 10 * LAMPORTS_PER_STAR);
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    let blockhash = context.banks_client.get_new_latest_blockhash(&context.last_blockhash).await.unwrap();
+    transaction.sign(&[&context.payer, &treasury_manager], blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::WrongRecipient as u32),
+        ),
+    );
+}
+
+/// A downstream program including the token its PDA is delegated through `cpi`.
+fn process_cpi_caller(program_id: &Pubkey, accounts: &[AccountInfo], _input: &[u8]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let include_token_accounts = IncludeTokenAccounts {
+        collection_program: next_account_info(account_info_iter)?,
+        collection: next_account_info(account_info_iter)?,
+        collection_authority: next_account_info(account_info_iter)?,
+        secondary_authority: None,
+        mint: next_account_info(account_info_iter)?,
+        mint_token_account: next_account_info(account_info_iter)?,
+        index: next_account_info(account_info_iter)?,
+        payer: next_account_info(account_info_iter)?,
+        rent_sysvar: next_account_info(account_info_iter)?,
+        system_program: next_account_info(account_info_iter)?,
+        allowlist: next_account_info(account_info_iter)?,
+    };
+    let (_, bump_seed) = Pubkey::find_program_address(&[b"authority"], program_id);
+    invoke_include_token(&include_token_accounts, &[&[b"authority", &[bump_seed]]])
+}
+
+#[tokio::test]
+async fn test_include_token_through_cpi() {
+    let program_id = id();
+    let caller_id = Pubkey::new_unique();
+    let mut program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    program_test.add_program("cpi_caller", caller_id, processor!(process_cpi_caller));
+    let mut context = program_test.start_with_context().await;
+    let payer_pubkey = context.payer.pubkey();
+    let (caller_authority, _) = Pubkey::find_program_address(&[b"authority"], &caller_id);
+
+    // the caller's PDA becomes the collection authority and the delegate of the token
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let (mint_keypair, nft_ata) = create_nft(&mut context).await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            spl_token::instruction::approve(&spl_token::id(), &nft_ata, &caller_authority, &payer_pubkey, &[], 1).unwrap(),
+            transfer_authority(program_id, collection_keypair.pubkey(), payer_pubkey, caller_authority),
+        ],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let (index_account, _) = find_index_address(&program_id, &mint_keypair.pubkey());
+    let (allowlist_account, _) = find_allowlist_address(&program_id, &collection_keypair.pubkey());
+    let ix = Instruction {
+        program_id: caller_id,
+        accounts: vec![
+            AccountMeta::new_readonly(program_id, false),
+            AccountMeta::new(collection_keypair.pubkey(), false),
+            AccountMeta::new(caller_authority, false),
+            AccountMeta::new(mint_keypair.pubkey(), false),
+            AccountMeta::new(nft_ata, false),
+            AccountMeta::new(index_account, false),
+            AccountMeta::new(payer_pubkey, true),
+            AccountMeta::new_readonly(solana_sdk::sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(allowlist_account, false),
+        ],
+        data: vec![],
+    };
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let index = context.banks_client.get_account(index_account).await.unwrap().unwrap();
+    let index_data = CollectionIndexAccountData::try_from_slice_unchecked(&index.data).unwrap();
+    assert_eq!(index_data.collection, collection_keypair.pubkey());
+    assert_eq!(index_data.mint, mint_keypair.pubkey());
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.supply, 1);
+}
+
+#[tokio::test]
+async fn test_migrate_account() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+
+    // a version 0 account, allocated for nothing but its own fields
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let mut account = context.banks_client
+        .get_account(collection_keypair.pubkey())
+        .await
+        .expect("get_account")
+        .unwrap();
+    let mut collection: CollectionAccountData = try_from_slice_unchecked(&account.data).unwrap();
+    collection.version = 0;
+    collection.stars = 42;
+    account.data = collection.try_to_vec().unwrap();
+    account.lamports = rent.minimum_balance(account.data.len());
+    let legacy_len = account.data.len();
+    context.set_account(&collection_keypair.pubkey(), &account.into());
+
+    let ix = migrate_account(program_id, collection_keypair.pubkey(), payer_pubkey, payer_pubkey);
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let account = context.banks_client.get_account(collection_keypair.pubkey()).await.unwrap().unwrap();
+    assert!(legacy_len < CollectionAccountData::LEN);
+    assert_eq!(account.data.len(), CollectionAccountData::LEN);
+    assert!(rent.is_exempt(account.lamports, account.data.len()));
+    let collection = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    assert_eq!(collection.version, COLLECTION_ACCOUNT_VERSION);
+    assert_eq!(collection.stars, 42);
+    assert_eq!(collection.created_at, 0);
+    assert_eq!(collection.title, collection_args().title);
+
+    // running it again changes nothing
+    let blockhash = context.banks_client.get_new_latest_blockhash(&context.last_blockhash).await.unwrap();
+    let ix = migrate_account(program_id, collection_keypair.pubkey(), payer_pubkey, payer_pubkey);
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let migrated = context.banks_client.get_account(collection_keypair.pubkey()).await.unwrap().unwrap();
+    assert_eq!(migrated.data, account.data);
+    assert_eq!(migrated.lamports, account.lamports);
+}
+
+#[tokio::test]
+async fn test_legacy_account_shorter_write() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+
+    // a version 0 account, allocated for nothing but its own fields
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let mut account = context.banks_client
+        .get_account(collection_keypair.pubkey())
+        .await
+        .expect("get_account")
+        .unwrap();
+    let mut collection: CollectionAccountData = try_from_slice_unchecked(&account.data).unwrap();
+    collection.version = 0;
+    account.data = collection.try_to_vec().unwrap();
+    account.lamports = rent.minimum_balance(account.data.len());
+    let legacy_len = account.data.len();
+    context.set_account(&collection_keypair.pubkey(), &account.into());
+
+    // the shorter tags leave the end of the account behind them
+    let transaction = Transaction::new_signed_with_payer(
+        &[remove_tag(program_id, collection_keypair.pubkey(), payer_pubkey, "music".to_string())],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let account = context.banks_client.get_account(collection_keypair.pubkey()).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), legacy_len);
+    let collection = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    assert_eq!(collection.version, 0);
+    assert_eq!(collection.tags, Some(vec!["art".to_string()]));
+    assert_eq!(collection.title, collection_args().title);
+}
+
+#[tokio::test]
+async fn test_legacy_account_setters_require_migration() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let parent_keypair = Keypair::new();
+    create_collection(&mut context, &parent_keypair, collection_args()).await.unwrap();
+    let (mint_keypair, _) = create_nft(&mut context).await.unwrap();
+    let collection_account = collection_keypair.pubkey();
+    let payer_pubkey = context.payer.pubkey();
+    let legacy_len = make_legacy_collection(&mut context, &collection_account).await;
+
+    // every setter writes the current layout, which a version 0 account has no room for
+    let setters = vec![
+        set_star_price(program_id, collection_account, payer_pubkey, 5),
+        set_metadata_uri(program_id, collection_account, payer_pubkey, Some("https://www.solana.com/collection.json".to_string())),
+        set_category(program_id, collection_account, payer_pubkey, Some(CollectionCategory::Music)),
+        add_to_allowlist(program_id, collection_account, payer_pubkey, payer_pubkey, mint_keypair.pubkey()),
+        set_cooldown(program_id, collection_account, payer_pubkey, payer_pubkey, 10),
+        set_parent(program_id, collection_account, payer_pubkey, parent_keypair.pubkey(), payer_pubkey),
+        set_collection_mint(program_id, collection_account, payer_pubkey, mint_keypair.pubkey()),
+        freeze_collection(program_id, collection_account, payer_pubkey),
+    ];
+    for ix in setters.iter() {
+        let name = CollectionInstruction::unpack(&ix.data).unwrap().variant_name();
+        let transaction = Transaction::new_signed_with_payer(
+            std::slice::from_ref(ix),
+            Some(&payer_pubkey),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::MigrationRequired as u32)),
+            "{}",
+            name,
+        );
+    }
+    let account = context.banks_client.get_account(collection_account).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), legacy_len);
+    assert_eq!(get_collection(&mut context, &collection_account).await.version, 0);
+
+    // once migrated they all fit
+    let mut ixs = setters;
+    ixs.insert(0, migrate_account(program_id, collection_account, payer_pubkey, payer_pubkey));
+    let transaction = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let collection = get_collection(&mut context, &collection_account).await;
+    assert_eq!(collection.version, COLLECTION_ACCOUNT_VERSION);
+    assert_eq!(collection.star_price, 5);
+    assert_eq!(collection.metadata_uri, Some("https://www.solana.com/collection.json".to_string()));
+    assert_eq!(collection.category, Some(CollectionCategory::Music));
+    assert!(collection.has_allowlist);
+    assert_eq!(collection.cooldown_slots, 10);
+    assert_eq!(collection.parent, Some(parent_keypair.pubkey()));
+    assert_eq!(collection.collection_mint, Some(mint_keypair.pubkey()));
+    assert!(collection.frozen);
+}
+
+#[tokio::test]
+async fn test_create_collection_with_creators() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let creators = vec![(Pubkey::new_unique(), 7_500), (Pubkey::new_unique(), 2_500)];
+    let collection_keypair = Keypair::new();
+    let args = CreateCollectionAccountArgs { creators: Some(creators.clone()), ..collection_args() };
+    create_collection(&mut context, &collection_keypair, args).await.unwrap();
+    let collection = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    assert_eq!(collection.creators(), Some(&creators[..]));
+
+    let args = CreateCollectionAccountArgs { creators: Some(vec![(Pubkey::new_unique(), 9_999)]), ..collection_args() };
+    let err = create_collection(&mut context, &Keypair::new(), args).await.unwrap_err().unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::InvalidMetadata as u32)),
+    );
+}
+
+#[tokio::test]
+async fn test_set_parent() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+    let payer_pubkey = context.payer.pubkey();
+
+    // the parent belongs to someone else, who has to agree to the link
+    let parent_authority = Keypair::new();
+    let parent_keypair = Keypair::new();
+    create_collection(&mut context, &parent_keypair, collection_args()).await.unwrap();
+    let mut transaction = Transaction::new_with_payer(
+        &[transfer_authority(program_id, parent_keypair.pubkey(), payer_pubkey, parent_authority.pubkey())],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let child_keypair = Keypair::new();
+    create_collection(&mut context, &child_keypair, collection_args()).await.unwrap();
+
+    let mut ix = set_parent(program_id, child_keypair.pubkey(), payer_pubkey, parent_keypair.pubkey(), parent_authority.pubkey());
+    ix.accounts[3].is_signer = false;
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(CollectionError::NotCollectionAuthority as u32),
+        ),
+    );
+
+    let ix = set_parent(program_id, child_keypair.pubkey(), payer_pubkey, parent_keypair.pubkey(), parent_authority.pubkey());
+    let mut transaction = Transaction::new_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer, &parent_authority], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &child_keypair.pubkey()).await.parent, Some(parent_keypair.pubkey()));
+
+    // neither the child itself nor the parent's own child can become its parent
+    let ix = set_parent(program_id, child_keypair.pubkey(), payer_pubkey, child_keypair.pubkey(), payer_pubkey);
+    let ix_cycle = set_parent(program_id, parent_keypair.pubkey(), parent_authority.pubkey(), child_keypair.pubkey(), payer_pubkey);
+    for (ix, signers) in [(ix, vec![&context.payer]), (ix_cycle, vec![&context.payer, &parent_authority])] {
+        let mut transaction = Transaction::new_with_payer(
+            &[ix],
+            Some(&payer_pubkey),
+        );
+        transaction.sign(&signers, context.last_blockhash);
+        assert_eq!(
+            context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(CollectionError::InvalidParent as u32),
+            ),
+        );
+    }
+    assert_eq!(get_collection(&mut context, &parent_keypair.pubkey()).await.parent, None);
+
+    // detaching only takes the child's authority
+    let mut transaction = Transaction::new_with_payer(
+        &[clear_parent(program_id, child_keypair.pubkey(), payer_pubkey)],
+        Some(&payer_pubkey),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &child_keypair.pubkey()).await.parent, None);
+}
+
+#[tokio::test]
+async fn test_set_collection_mint() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+    let payer_pubkey = context.payer.pubkey();
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+
+    // a fungible mint is not a collection NFT
+    let fungible_mint_keypair = Keypair::new();
+    create_mint(&mut context, &fungible_mint_keypair, &payer_pubkey, 2, None).await.unwrap();
+    create_associated_account(&mut context, &payer_pubkey, &fungible_mint_keypair.pubkey()).await.unwrap();
+    let token_account = get_associated_token_address(&payer_pubkey, &fungible_mint_keypair.pubkey());
+    mint_tokens(&mut context, &fungible_mint_keypair.pubkey(), &token_account, 1, &payer_pubkey, None).await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[set_collection_mint(program_id, collection_keypair.pubkey(), payer_pubkey, fungible_mint_keypair.pubkey())],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::NotAnNft as u32)),
+    );
+
+    let mint_keypair = Keypair::new();
+    create_mint(&mut context, &mint_keypair, &payer_pubkey, 0, None).await.unwrap();
+    create_associated_account(&mut context, &payer_pubkey, &mint_keypair.pubkey()).await.unwrap();
+    let token_account = get_associated_token_address(&payer_pubkey, &mint_keypair.pubkey());
+    mint_tokens(&mut context, &mint_keypair.pubkey(), &token_account, 1, &payer_pubkey, None).await.unwrap();
+
+    // only the collection authority links a mint
+    let intruder = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[set_collection_mint(program_id, collection_keypair.pubkey(), intruder.pubkey(), mint_keypair.pubkey())],
+        Some(&payer_pubkey),
+        &[&context.payer, &intruder],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::NotCollectionAuthority as u32)),
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[set_collection_mint(program_id, collection_keypair.pubkey(), payer_pubkey, mint_keypair.pubkey())],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let collection_data = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    assert_eq!(collection_data.collection_mint, Some(mint_keypair.pubkey()));
+    assert_eq!(collection_data.version, COLLECTION_ACCOUNT_VERSION);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[clear_collection_mint(program_id, collection_keypair.pubkey(), payer_pubkey)],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.collection_mint, None);
+}
+
+#[tokio::test]
+async fn test_include_collection_mint() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+    let payer_pubkey = context.payer.pubkey();
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let mint_keypair = Keypair::new();
+    create_mint(&mut context, &mint_keypair, &payer_pubkey, 0, None).await.unwrap();
+    create_associated_account(&mut context, &payer_pubkey, &mint_keypair.pubkey()).await.unwrap();
+    let token_account = get_associated_token_address(&payer_pubkey, &mint_keypair.pubkey());
+    mint_tokens(&mut context, &mint_keypair.pubkey(), &token_account, 1, &payer_pubkey, None).await.unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[set_collection_mint(program_id, collection_keypair.pubkey(), payer_pubkey, mint_keypair.pubkey())],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let ix = include_token(
+        program_id,
+        collection_keypair.pubkey(),
+        payer_pubkey,
+        mint_keypair.pubkey(),
+        token_account,
+        payer_pubkey,
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::CannotIncludeCollectionMint as u32)),
+    );
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.supply, 0);
+    let (index_account, _) = find_index_address(&program_id, &mint_keypair.pubkey());
+    assert!(context.banks_client.get_account(index_account).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_light_up_stars_once_cooldown() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[set_cooldown(program_id, collection_keypair.pubkey(), payer_pubkey, payer_pubkey, 1_000)],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.cooldown_slots, 1_000);
+    let (cooldown_account, _) = find_cooldown_address(&program_id, &collection_keypair.pubkey());
+    let account = context.banks_client.get_account(cooldown_account).await.unwrap().unwrap();
+    assert_eq!(account.owner, program_id);
+    let cooldown_data = CooldownAccountData::try_from_slice_unchecked(&account.data).unwrap();
+    assert_eq!(cooldown_data.collection, collection_keypair.pubkey());
+
+    // without a signer the cooldown can not be kept
+    let transaction = Transaction::new_signed_with_payer(
+        &[light_up_stars_once(program_id, collection_keypair.pubkey())],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys),
+    );
+
+    let signer = Keypair::new();
+    let ix = light_up_stars_once_with_cooldown(program_id, collection_keypair.pubkey(), signer.pubkey());
+    let transaction = Transaction::new_signed_with_payer(
+        std::slice::from_ref(&ix),
+        Some(&payer_pubkey),
+        &[&context.payer, &signer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let slot = context.banks_client.get_sysvar::<Clock>().await.unwrap().slot;
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, 1);
+
+    let blockhash = context.banks_client.get_new_latest_blockhash(&context.last_blockhash).await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        std::slice::from_ref(&ix),
+        Some(&payer_pubkey),
+        &[&context.payer, &signer],
+        blockhash,
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::Cooldown as u32)),
+    );
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, 1);
+
+    // once the cooldown has passed the same signer lights again
+    context.warp_to_slot(slot + 1_000).unwrap();
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer_pubkey),
+        &[&context.payer, &signer],
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, 2);
+
+    // closing the cooldown account turns the cooldown off
+    let transaction = Transaction::new_signed_with_payer(
+        &[close_cooldown_account(program_id, collection_keypair.pubkey(), payer_pubkey, payer_pubkey)],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.cooldown_slots, 0);
+    let transaction = Transaction::new_signed_with_payer(
+        &[light_up_stars_once(program_id, collection_keypair.pubkey())],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &collection_keypair.pubkey()).await.stars, 3);
+}
+
+#[tokio::test]
+async fn test_clear_optional_metadata() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    let args = CreateCollectionAccountArgs { category: Some(CollectionCategory::Art), ..collection_args() };
+    create_collection(&mut context, &collection_keypair, args).await.unwrap();
+    let payer_pubkey = context.payer.pubkey();
+    let before = context.banks_client.get_account(collection_keypair.pubkey()).await.unwrap().unwrap();
+    assert_eq!(before.data.len(), CollectionAccountData::LEN);
+
+    let recipient = Pubkey::new_unique();
+    let transaction = Transaction::new_signed_with_payer(
+        &[clear_optional_metadata(program_id, collection_keypair.pubkey(), payer_pubkey, recipient)],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    // the account shrinks and the freed rent goes to the recipient
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let account = context.banks_client.get_account(collection_keypair.pubkey()).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), CollectionAccountData::CLEARED_LEN);
+    assert_eq!(account.lamports, rent.minimum_balance(CollectionAccountData::CLEARED_LEN));
+    let refund = context.banks_client.get_balance(recipient).await.unwrap();
+    assert_eq!(refund, before.lamports - account.lamports);
+
+    let collection = get_collection(&mut context, &collection_keypair.pubkey()).await;
+    assert_eq!(collection.header_image, None);
+    assert_eq!(collection.short_description, None);
+    assert_eq!(collection.banner, None);
+    assert_eq!(collection.tags, None);
+    assert_eq!(collection.title, collection_args().title);
+    assert_eq!(collection.symbol, collection_args().symbol);
+    assert_eq!(collection.description, collection_args().description);
+    assert_eq!(collection.icon_image, collection_args().icon_image);
+    assert_eq!(collection.category, Some(CollectionCategory::Art));
+    assert_eq!(collection.version, COLLECTION_ACCOUNT_VERSION);
+
+    // the recipient has to be another account than the collection
+    let blockhash = context.banks_client.get_new_latest_blockhash(&context.last_blockhash).await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[clear_optional_metadata(program_id, collection_keypair.pubkey(), payer_pubkey, collection_keypair.pubkey())],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        blockhash,
+    );
+    assert_eq!(
+        context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::InvalidAccountArguments as u32)),
+    );
+
+    // migrating gives the room back
+    let transaction = Transaction::new_signed_with_payer(
+        &[migrate_account(program_id, collection_keypair.pubkey(), payer_pubkey, payer_pubkey)],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    let account = context.banks_client.get_account(collection_keypair.pubkey()).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), CollectionAccountData::LEN);
+    assert!(rent.is_exempt(account.lamports, account.data.len()));
+}
+
+#[tokio::test]
+async fn test_clear_optional_metadata_then_add_tag() {
+    let program_id = id();
+    let program_test = ProgramTest::new("collection", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+
+    let collection_keypair = Keypair::new();
+    create_collection(&mut context, &collection_keypair, collection_args()).await.unwrap();
+    let collection_account = collection_keypair.pubkey();
+    let payer_pubkey = context.payer.pubkey();
+    let transaction = Transaction::new_signed_with_payer(
+        &[clear_optional_metadata(program_id, collection_account, payer_pubkey, Pubkey::new_unique())],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    // a collection whose other fields take all their room keeps none for the cleared ones,
+    // the cleared account trimmed to its encoding stands in for it
+    let mut account = context.banks_client.get_account(collection_account).await.unwrap().unwrap();
+    let collection: CollectionAccountData = try_from_slice_unchecked(&account.data).unwrap();
+    account.data = collection.try_to_vec().unwrap();
+    context.set_account(&collection_account, &account.into());
+
+    let setters = vec![
+        add_tag(program_id, collection_account, payer_pubkey, "film".to_string()),
+        set_tags(program_id, collection_account, payer_pubkey, vec!["art".to_string()]),
+        set_banner(program_id, collection_account, payer_pubkey, Some("https://www.solana.com".to_string())),
+        update_collection_metadata(program_id, collection_account, payer_pubkey, UpdateCollectionMetadataArgs {
+            short_description: Some("www.solana.com".to_string()),
+            ..Default::default()
+        }),
+    ];
+    for ix in setters {
+        let name = CollectionInstruction::unpack(&ix.data).unwrap().variant_name();
+        let transaction = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer_pubkey),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(CollectionError::MigrationRequired as u32)),
+            "{}",
+            name,
+        );
+    }
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            migrate_account(program_id, collection_account, payer_pubkey, payer_pubkey),
+            add_tag(program_id, collection_account, payer_pubkey, "film".to_string()),
+        ],
+        Some(&payer_pubkey),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(get_collection(&mut context, &collection_account).await.tags, Some(vec!["film".to_string()]));
+}
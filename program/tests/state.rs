@@ -0,0 +1,608 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use collection::error::CollectionError;
+use collection::state::{
+    AccountType, CollectionAccountData, CollectionCategory, CollectionIndexAccountData, CooldownAccountData, TreasuryConfigAccountData,
+    COLLECTION_ACCOUNT_VERSION, DERIVED_SHORT_DESCRIPTION_LENGTH, LAMPORTS_PER_STAR, MAX_COOLDOWN_SIGNERS,
+    read_cooldown_slots, read_free_star_counters, read_stars, write_free_star_counters, write_stars,
+};
+use solana_sdk::{borsh::try_from_slice_unchecked, program_error::ProgramError, pubkey::Pubkey};
+
+fn collection_data() -> CollectionAccountData {
+    CollectionAccountData {
+        account_type: AccountType::CollectionAccount,
+        authority: Pubkey::new_unique(),
+        title: "test collection".to_string(),
+        symbol: "TC".to_string(),
+        description: "test collection description".to_string(),
+        icon_image: "https://www.google.com".to_string(),
+        supply: 3,
+        stars: 100,
+        header_image: Some("www.solana.com".to_string()),
+        short_description: None,
+        banner: None,
+        tags: Some(vec!["art".to_string(), "music".to_string()]),
+        version: COLLECTION_ACCOUNT_VERSION,
+        created_at: 1_634_000_000,
+        frozen: false,
+        royalty_bps: None,
+        star_price: LAMPORTS_PER_STAR,
+        index_seq: 5,
+        verified: false,
+        last_star_slot: 0,
+        stars_this_slot: 0,
+        secondary_authority: None,
+        metadata_uri: None,
+        has_allowlist: false,
+        category: None,
+        creators: None,
+        parent: None,
+        cooldown_slots: 0,
+        collection_mint: None,
+    }
+}
+
+#[test]
+fn test_created_at_round_trip() {
+    let data = collection_data().try_to_vec().unwrap();
+    let account_data: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(account_data.version, COLLECTION_ACCOUNT_VERSION);
+    assert_eq!(account_data.created_at, 1_634_000_000);
+    assert_eq!(account_data.stars, 100);
+    assert_eq!(account_data.stars(), 100);
+    assert_eq!(account_data.token_count(), 3);
+}
+
+#[test]
+fn test_legacy_account_without_version() {
+    let mut data = collection_data().try_to_vec().unwrap();
+    // version 0 accounts stop right after the tags
+    data.truncate(data.len() - 55);
+    let account_data: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(account_data.version, 0);
+    assert_eq!(account_data.created_at, 0);
+    assert_eq!(account_data.star_price(), LAMPORTS_PER_STAR);
+    assert_eq!(account_data.title, "test collection".to_string());
+    assert_eq!(account_data.tags, Some(vec!["art".to_string(), "music".to_string()]));
+}
+
+#[test]
+fn test_legacy_account_written_shorter() {
+    let mut account_data = collection_data();
+    account_data.version = 0;
+    let mut data = account_data.try_to_vec().unwrap();
+    // a shorter write over a version 0 account leaves no stale bytes to misread as a version
+    account_data.tags = Some(vec!["art".to_string()]);
+    account_data.write_to(&mut data).unwrap();
+    assert_eq!(data[data.len() - 9..], [0; 9]);
+    let decoded: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(decoded.version, 0);
+    assert_eq!(decoded.tags, Some(vec!["art".to_string()]));
+    assert_eq!(read_cooldown_slots(&data).unwrap(), 0);
+    assert_eq!(read_free_star_counters(&data).unwrap(), None);
+
+    account_data.tags = None;
+    account_data.write_to(&mut data).unwrap();
+    let decoded: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(decoded.version, 0);
+    assert_eq!(decoded.tags, None);
+}
+
+#[test]
+fn test_version_1_account_without_frozen() {
+    let mut account_data = collection_data();
+    account_data.version = 1;
+    let data = account_data.try_to_vec().unwrap();
+    // version 1 accounts stop right after created_at
+    assert_eq!(data.len(), collection_data().try_to_vec().unwrap().len() - 46);
+    let account_data: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(account_data.version, 1);
+    assert_eq!(account_data.created_at, 1_634_000_000);
+    assert!(!account_data.frozen);
+    assert_eq!(account_data.try_to_vec().unwrap(), data);
+}
+
+#[test]
+fn test_frozen_round_trip() {
+    let mut account_data = collection_data();
+    account_data.frozen = true;
+    let data = account_data.try_to_vec().unwrap();
+    let account_data: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert!(account_data.frozen);
+}
+
+#[test]
+fn test_royalty_bps_round_trip() {
+    let mut account_data = collection_data();
+    account_data.royalty_bps = Some(500);
+    let data = account_data.try_to_vec().unwrap();
+    let account_data: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(account_data.royalty_bps(), Some(500));
+}
+
+#[test]
+fn test_star_price_round_trip() {
+    let mut account_data = collection_data();
+    account_data.star_price = 42;
+    let data = account_data.try_to_vec().unwrap();
+    let account_data: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(account_data.star_price(), 42);
+
+    // version 3 accounts stop right after royalty_bps and charge the default price
+    let mut account_data = collection_data();
+    account_data.version = 3;
+    account_data.star_price = 42;
+    let data = account_data.try_to_vec().unwrap();
+    assert_eq!(data.len(), collection_data().try_to_vec().unwrap().len() - 44);
+    let account_data: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(account_data.star_price(), LAMPORTS_PER_STAR);
+}
+
+#[test]
+fn test_verified_round_trip() {
+    let mut account_data = collection_data();
+    account_data.verified = true;
+    let data = account_data.try_to_vec().unwrap();
+    let account_data: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert!(account_data.verified);
+}
+
+#[test]
+fn test_unknown_version_is_rejected() {
+    let mut account_data = collection_data();
+    account_data.version = COLLECTION_ACCOUNT_VERSION + 1;
+    let data = account_data.try_to_vec().unwrap();
+    assert!(try_from_slice_unchecked::<CollectionAccountData>(&data).is_err());
+}
+
+#[test]
+fn test_legacy_treasury_config_without_star_token() {
+    let mut treasury_config = TreasuryConfigAccountData::new(Pubkey::new_unique());
+    treasury_config.star_mint = Some(Pubkey::new_unique());
+    treasury_config.star_token_price = 100;
+    let data = treasury_config.try_to_vec().unwrap();
+    assert_eq!(data.len(), TreasuryConfigAccountData::LEN);
+    let decoded = TreasuryConfigAccountData::try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(decoded.star_mint, treasury_config.star_mint);
+    assert_eq!(decoded.star_token_price, 100);
+
+    // legacy configs stop right after the recipient
+    let legacy = TreasuryConfigAccountData::try_from_slice_unchecked(&data[..1 + 32]).unwrap();
+    assert_eq!(legacy.treasury_recipient, treasury_config.treasury_recipient);
+    assert_eq!(legacy.star_mint, None);
+    assert_eq!(legacy.star_token_price, 0);
+}
+
+#[test]
+fn test_legacy_index_account_without_bump() {
+    let index_data = CollectionIndexAccountData::new(Pubkey::new_unique(), Pubkey::new_unique(), 7, 254);
+    let mut data = index_data.try_to_vec().unwrap();
+    assert_eq!(data.len(), CollectionIndexAccountData::LEN);
+    assert_eq!(try_from_slice_unchecked::<CollectionIndexAccountData>(&data).unwrap().bump, 254);
+
+    // legacy index accounts stop right after the index
+    data.truncate(data.len() - 1);
+    let legacy: CollectionIndexAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(legacy.bump, 0);
+    assert_eq!(legacy.index, 7);
+    assert_eq!(legacy.mint, index_data.mint);
+}
+
+#[test]
+fn test_check_account_type() {
+    let collection = collection_data().try_to_vec().unwrap();
+    let index = CollectionIndexAccountData::new(Pubkey::new_unique(), Pubkey::new_unique(), 0, 255)
+        .try_to_vec()
+        .unwrap();
+
+    assert!(AccountType::check_account_type(&collection, AccountType::CollectionAccount).is_ok());
+    assert!(AccountType::check_account_type(&index, AccountType::CollectionIndexAccount).is_ok());
+    assert_eq!(
+        AccountType::check_account_type(&index, AccountType::CollectionAccount),
+        Err(CollectionError::InvalidAccountType.into())
+    );
+    assert_eq!(
+        AccountType::check_account_type(&collection, AccountType::CollectionIndexAccount),
+        Err(CollectionError::InvalidAccountType.into())
+    );
+    assert_eq!(
+        AccountType::check_account_type(&[0; 8], AccountType::CollectionAccount),
+        Err(CollectionError::Uninitialized.into())
+    );
+    assert_eq!(
+        AccountType::check_account_type(&[], AccountType::CollectionAccount),
+        Err(CollectionError::Uninitialized.into())
+    );
+}
+
+#[test]
+fn test_display_fallbacks() {
+    let mut account_data = collection_data();
+    assert_eq!(account_data.header_image_or_icon(), "www.solana.com");
+    account_data.header_image = None;
+    assert_eq!(account_data.header_image_or_icon(), "https://www.google.com");
+
+    account_data.short_description = Some("short".to_string());
+    assert_eq!(account_data.short_description_or_truncated(), "short");
+    account_data.short_description = None;
+    assert_eq!(account_data.short_description_or_truncated(), "test collection description");
+
+    // the derived short description counts characters, not bytes
+    account_data.description = "描".repeat(DERIVED_SHORT_DESCRIPTION_LENGTH + 1);
+    assert_eq!(
+        account_data.short_description_or_truncated(),
+        "描".repeat(DERIVED_SHORT_DESCRIPTION_LENGTH)
+    );
+}
+
+#[test]
+fn test_try_from_account_data() {
+    let data = collection_data().try_to_vec().unwrap();
+    let account_data = CollectionAccountData::try_from_account_data(&data).unwrap();
+    assert_eq!(account_data.title, "test collection".to_string());
+    assert_eq!(account_data.stars(), 100);
+
+    // cut inside the description
+    assert_eq!(
+        CollectionAccountData::try_from_account_data(&data[..60]).err(),
+        Some(ProgramError::InvalidAccountData)
+    );
+    assert_eq!(
+        CollectionAccountData::try_from_account_data(&data[..CollectionAccountData::MIN_LEN - 1]).err(),
+        Some(ProgramError::InvalidAccountData)
+    );
+
+    let index = CollectionIndexAccountData::new(Pubkey::new_unique(), Pubkey::new_unique(), 0, 255)
+        .try_to_vec()
+        .unwrap();
+    assert_eq!(
+        CollectionAccountData::try_from_account_data(&index).err(),
+        Some(CollectionError::InvalidAccountType.into())
+    );
+}
+
+#[test]
+fn test_declared_string_length_past_the_end() {
+    let mut data = collection_data().try_to_vec().unwrap();
+    // the description declares 27 bytes, claim more than the whole account holds
+    let description_offset = 1 + 32 + 4 + 15 + 4 + 2;
+    let len = data.len() as u32;
+    data[description_offset..description_offset + 4].copy_from_slice(&len.to_le_bytes());
+    assert_eq!(
+        CollectionAccountData::try_from_account_data(&data).err(),
+        Some(CollectionError::InvalidAccountData.into())
+    );
+    assert_eq!(
+        CollectionAccountData::try_from_slice_unchecked(&data).err(),
+        Some(CollectionError::InvalidAccountData.into())
+    );
+
+    // a tag past the end is caught as well
+    let mut data = collection_data().try_to_vec().unwrap();
+    let tag_offset = data.len() - 55 - 4 - 5;
+    data[tag_offset..tag_offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(
+        CollectionAccountData::try_from_account_data(&data).err(),
+        Some(CollectionError::InvalidAccountData.into())
+    );
+}
+
+#[test]
+fn test_index_seq_round_trip() {
+    let data = collection_data().try_to_vec().unwrap();
+    let account_data: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(account_data.index_seq(), 5);
+
+    // version 4 accounts stop right after star_price and continue numbering from the supply
+    let mut account_data = collection_data();
+    account_data.version = 4;
+    let data = account_data.try_to_vec().unwrap();
+    assert_eq!(data.len(), collection_data().try_to_vec().unwrap().len() - 36);
+    let account_data: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(account_data.index_seq(), 3);
+}
+
+#[test]
+fn test_metadata_uri_round_trip() {
+    let mut account_data = collection_data();
+    account_data.metadata_uri = Some("ar://5Dr5BAwBwFwGbnA9MMyMntKUa4mKwLZa2UDS2Ct8Px8".to_string());
+    let data = account_data.try_to_vec().unwrap();
+    let decoded: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(decoded.metadata_uri, account_data.metadata_uri);
+    assert_eq!(decoded.metadata().metadata_uri, account_data.metadata_uri);
+
+    // version 8 accounts stop right after secondary_authority
+    account_data.version = 8;
+    let data = account_data.try_to_vec().unwrap();
+    assert_eq!(data.len(), collection_data().try_to_vec().unwrap().len() - 14);
+    let decoded: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(decoded.metadata_uri, None);
+}
+
+#[test]
+fn test_category_round_trip() {
+    let mut account_data = collection_data();
+    account_data.category = Some(CollectionCategory::Photography);
+    let data = account_data.try_to_vec().unwrap();
+    // followed by the `None` creators and parent, then the cooldown and the `None` collection mint
+    assert_eq!(&data[data.len() - 13..data.len() - 9], &[1, 4, 0, 0]);
+    let decoded: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(decoded.category, Some(CollectionCategory::Photography));
+    assert_eq!(decoded.metadata().category, Some(CollectionCategory::Photography));
+
+    // version 10 accounts stop right after has_allowlist
+    account_data.version = 10;
+    let data = account_data.try_to_vec().unwrap();
+    assert_eq!(data.len(), collection_data().try_to_vec().unwrap().len() - 12);
+    let decoded: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(decoded.category, None);
+}
+
+#[test]
+fn test_out_of_range_category_is_rejected() {
+    assert_eq!(CollectionCategory::try_from_slice(&[5]).unwrap(), CollectionCategory::Other);
+    assert!(CollectionCategory::try_from_slice(&[6]).is_err());
+
+    let mut account_data = collection_data();
+    account_data.category = Some(CollectionCategory::Other);
+    let mut data = account_data.try_to_vec().unwrap();
+    let category_offset = data.len() - 12;
+    data[category_offset] = 6;
+    assert!(try_from_slice_unchecked::<CollectionAccountData>(&data).is_err());
+}
+
+#[test]
+fn test_creators_round_trip() {
+    let mut account_data = collection_data();
+    let creators = vec![(Pubkey::new_unique(), 7_000), (Pubkey::new_unique(), 3_000)];
+    account_data.creators = Some(creators.clone());
+    let data = account_data.try_to_vec().unwrap();
+    let decoded: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(decoded.creators(), Some(&creators[..]));
+    assert_eq!(decoded.metadata().creators, Some(creators));
+
+    // version 11 accounts stop right after category
+    account_data.version = 11;
+    let data = account_data.try_to_vec().unwrap();
+    assert_eq!(data.len(), collection_data().try_to_vec().unwrap().len() - 11);
+    let decoded: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(decoded.creators(), None);
+}
+
+#[test]
+fn test_parent_round_trip() {
+    let mut account_data = collection_data();
+    account_data.parent = Some(Pubkey::new_unique());
+    let data = account_data.try_to_vec().unwrap();
+    let decoded: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(decoded.parent, account_data.parent);
+
+    // version 12 accounts stop right after creators
+    account_data.version = 12;
+    let data = account_data.try_to_vec().unwrap();
+    assert_eq!(data.len(), collection_data().try_to_vec().unwrap().len() - 10);
+    let decoded: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(decoded.parent, None);
+}
+
+#[test]
+fn test_cooldown_slots_round_trip() {
+    let mut account_data = collection_data();
+    account_data.cooldown_slots = 150;
+    let data = account_data.try_to_vec().unwrap();
+    let decoded: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(decoded.cooldown_slots, 150);
+
+    // version 13 accounts stop right after parent
+    account_data.version = 13;
+    let data = account_data.try_to_vec().unwrap();
+    assert_eq!(data.len(), collection_data().try_to_vec().unwrap().len() - 9);
+    let decoded: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(decoded.cooldown_slots, 0);
+}
+
+#[test]
+fn test_collection_mint_round_trip() {
+    let mut account_data = collection_data();
+    let collection_mint = Pubkey::new_unique();
+    account_data.collection_mint = Some(collection_mint);
+    let data = account_data.try_to_vec().unwrap();
+    let decoded: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(decoded.collection_mint, Some(collection_mint));
+
+    // version 14 accounts stop right after cooldown_slots
+    account_data.version = 14;
+    let data = account_data.try_to_vec().unwrap();
+    assert_eq!(data.len(), collection_data().try_to_vec().unwrap().len() - 1);
+    let decoded: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(decoded.collection_mint, None);
+}
+
+#[test]
+fn test_len_fits_maxed_out_account() {
+    let uri = format!("https://{}", "a".repeat(192));
+    let mut account_data = collection_data();
+    account_data.title = "🎵".repeat(32);
+    account_data.symbol = "A".repeat(10);
+    account_data.description = "🎵".repeat(800);
+    account_data.icon_image = uri.clone();
+    account_data.header_image = Some(uri.clone());
+    account_data.short_description = Some("🎵".repeat(800));
+    account_data.banner = Some(uri.clone());
+    account_data.tags = Some(["🎵", "🎨", "🎸", "🎹", "🎺", "🎻"].iter().map(|c| c.repeat(20)).collect());
+    account_data.royalty_bps = Some(10_000);
+    account_data.secondary_authority = Some(Pubkey::new_unique());
+    account_data.metadata_uri = Some(uri);
+    account_data.category = Some(CollectionCategory::Other);
+    account_data.creators = Some((0..5).map(|_| (Pubkey::new_unique(), 2_000)).collect());
+    account_data.parent = Some(Pubkey::new_unique());
+    account_data.cooldown_slots = u64::MAX;
+    account_data.collection_mint = Some(Pubkey::new_unique());
+    assert!(account_data.metadata().is_valid());
+    assert_eq!(account_data.try_to_vec().unwrap().len(), CollectionAccountData::LEN);
+
+    // without the optional metadata it fits the space of a cleared account
+    account_data.header_image = None;
+    account_data.short_description = None;
+    account_data.banner = None;
+    account_data.tags = None;
+    assert_eq!(account_data.try_to_vec().unwrap().len(), CollectionAccountData::CLEARED_LEN);
+}
+
+#[test]
+fn test_too_many_tags_are_not_serialized() {
+    let mut account_data = collection_data();
+    account_data.tags = Some((0..7).map(|i| format!("tag{}", i)).collect());
+    assert!(account_data.try_to_vec().is_err());
+}
+
+#[test]
+fn test_read_and_write_stars() {
+    let mut account_data = collection_data();
+    account_data.description = "描".repeat(300);
+    let mut data = account_data.try_to_vec().unwrap();
+    assert_eq!(read_stars(&data).unwrap(), try_from_slice_unchecked::<CollectionAccountData>(&data).unwrap().stars);
+
+    write_stars(&mut data, 4242).unwrap();
+    let written: CollectionAccountData = try_from_slice_unchecked(&data).unwrap();
+    assert_eq!(written.stars, 4242);
+    assert_eq!(written.supply, account_data.supply);
+    assert_eq!(written.header_image, account_data.header_image);
+    assert_eq!(read_stars(&data).unwrap(), 4242);
+
+    // cut inside the stars
+    assert_eq!(read_stars(&data[..1 + 32 + 4 + 15 + 4 + 2 + 4 + 900 + 4 + 22 + 8 + 4]), Err(ProgramError::InvalidAccountData));
+    let index = CollectionIndexAccountData::new(Pubkey::new_unique(), Pubkey::new_unique(), 0, 255)
+        .try_to_vec()
+        .unwrap();
+    assert_eq!(read_stars(&index), Err(CollectionError::InvalidAccountType.into()));
+}
+
+#[test]
+fn test_read_and_write_free_star_counters() {
+    let mut account_data = collection_data();
+    account_data.royalty_bps = Some(500);
+    account_data.banner = Some("https://www.solana.com".to_string());
+    account_data.last_star_slot = 77;
+    account_data.stars_this_slot = 3;
+    let mut data = account_data.try_to_vec().unwrap();
+    assert_eq!(read_free_star_counters(&data).unwrap(), Some((77, 3)));
+
+    write_free_star_counters(&mut data, 78, 1).unwrap();
+    let mut expected = account_data.clone();
+    expected.last_star_slot = 78;
+    expected.stars_this_slot = 1;
+    assert_eq!(data, expected.try_to_vec().unwrap());
+
+    // accounts older than the counters, also when padded to a larger allocation
+    account_data.version = 6;
+    let mut data = account_data.try_to_vec().unwrap();
+    assert_eq!(read_free_star_counters(&data).unwrap(), None);
+    assert_eq!(write_free_star_counters(&mut data, 78, 1), Err(ProgramError::InvalidAccountData));
+    account_data.version = 0;
+    let mut data = account_data.try_to_vec().unwrap();
+    assert_eq!(read_free_star_counters(&data).unwrap(), None);
+    data.resize(CollectionAccountData::LEN, 0);
+    assert_eq!(read_free_star_counters(&data).unwrap(), None);
+
+    // cut inside the counters
+    let data = collection_data().try_to_vec().unwrap();
+    assert_eq!(read_free_star_counters(&data[..data.len() - 19]), Err(ProgramError::InvalidAccountData));
+}
+
+#[test]
+fn test_read_cooldown_slots() {
+    let mut account_data = collection_data();
+    account_data.cooldown_slots = 150;
+    assert_eq!(read_cooldown_slots(&account_data.try_to_vec().unwrap()).unwrap(), 150);
+
+    // every variable field before the cooldown is skipped
+    account_data.royalty_bps = Some(500);
+    account_data.secondary_authority = Some(Pubkey::new_unique());
+    account_data.metadata_uri = Some("https://www.solana.com/collection.json".to_string());
+    account_data.category = Some(CollectionCategory::Music);
+    account_data.creators = Some(vec![(Pubkey::new_unique(), 4_000), (Pubkey::new_unique(), 6_000)]);
+    account_data.parent = Some(Pubkey::new_unique());
+    let mut data = account_data.try_to_vec().unwrap();
+    assert_eq!(read_cooldown_slots(&data).unwrap(), 150);
+    data.resize(CollectionAccountData::LEN, 0);
+    assert_eq!(read_cooldown_slots(&data).unwrap(), 150);
+
+    // accounts older than the cooldown have none
+    account_data.version = 13;
+    assert_eq!(read_cooldown_slots(&account_data.try_to_vec().unwrap()).unwrap(), 0);
+    account_data.version = 0;
+    assert_eq!(read_cooldown_slots(&account_data.try_to_vec().unwrap()).unwrap(), 0);
+
+    // cut inside the cooldown
+    let data = collection_data().try_to_vec().unwrap();
+    assert_eq!(read_cooldown_slots(&data[..data.len() - 2]), Err(ProgramError::InvalidAccountData));
+}
+
+#[test]
+fn test_cooldown_record() {
+    let collection = Pubkey::new_unique();
+    let signer = Pubkey::new_unique();
+    let mut cooldown_data = CooldownAccountData::new(collection);
+    cooldown_data.record(&signer, 100, 10).unwrap();
+    assert_eq!(cooldown_data.record(&signer, 109, 10), Err(CollectionError::Cooldown));
+    // another signer is not held back
+    cooldown_data.record(&Pubkey::new_unique(), 109, 10).unwrap();
+    cooldown_data.record(&signer, 110, 10).unwrap();
+    assert_eq!(cooldown_data.signers.len(), 2);
+    assert_eq!(cooldown_data.signers.last(), Some(&(signer, 110)));
+
+    // a full account forgets its oldest signer, and never outgrows its space
+    let mut cooldown_data = CooldownAccountData::new(collection);
+    let first = Pubkey::new_unique();
+    cooldown_data.record(&first, 100, 10).unwrap();
+    for _ in 0..MAX_COOLDOWN_SIGNERS {
+        cooldown_data.record(&Pubkey::new_unique(), 101, 10).unwrap();
+    }
+    assert_eq!(cooldown_data.signers.len(), MAX_COOLDOWN_SIGNERS);
+    cooldown_data.record(&first, 102, 10).unwrap();
+    assert_eq!(cooldown_data.try_to_vec().unwrap().len(), CooldownAccountData::LEN);
+    let decoded = CooldownAccountData::try_from_slice_unchecked(&cooldown_data.try_to_vec().unwrap()).unwrap();
+    assert!(decoded.is_initialized());
+    assert_eq!(decoded.collection, collection);
+}
+
+#[test]
+fn test_content_hash() {
+    let account_data = collection_data();
+    let hash = account_data.content_hash();
+    assert_eq!(account_data.clone().content_hash(), hash);
+
+    // stars and supply are not content
+    let mut lit = account_data.clone();
+    lit.stars += 1;
+    lit.supply += 1;
+    assert_eq!(lit.content_hash(), hash);
+
+    let mut updated = account_data.clone();
+    updated.description.push('!');
+    assert_ne!(updated.content_hash(), hash);
+
+    let mut shifted = account_data.clone();
+    shifted.title = "test collectionT".to_string();
+    shifted.symbol = "C".to_string();
+    assert_ne!(shifted.content_hash(), hash);
+
+    let mut with_banner = account_data;
+    with_banner.banner = Some("https://www.solana.com".to_string());
+    assert_ne!(with_banner.content_hash(), hash);
+}
+
+#[test]
+fn test_tags_remaining() {
+    let mut account_data = collection_data();
+    account_data.tags = None;
+    assert_eq!(account_data.tags_remaining(), 6);
+    assert!(account_data.can_add_tag());
+
+    account_data.tags = Some(vec![]);
+    assert_eq!(account_data.tags_remaining(), 6);
+
+    account_data.tags = Some((0..6).map(|i| format!("tag{}", i)).collect());
+    assert_eq!(account_data.tags_remaining(), 0);
+    assert!(!account_data.can_add_tag());
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "client-builders")]
+use collection::{
+    id,
+    instruction::include_token,
+    seeds::{COLLECTION_SEED, TREASURY_SEED},
+    utils::{
+        create_index_address, find_index_address, find_index_addresses, find_treasury_address,
+        get_index_account, get_treasury_account, withdrawable_balance, TREASURY_BUMP,
+    },
+};
+use solana_program::{account_info::AccountInfo, pubkey::Pubkey, rent::Rent, system_program};
+use std::str::FromStr;
+
+#[test]
+fn test_find_treasury_address() {
+    let (treasury_address, bump_seed) = find_treasury_address(&id());
+    assert_eq!((treasury_address, bump_seed), get_treasury_account());
+    assert_eq!(
+        treasury_address,
+        Pubkey::create_program_address(
+            &[b"collection", b"treasury", id().as_ref(), &[bump_seed]],
+            &id(),
+        ).unwrap()
+    );
+    assert_eq!(treasury_address, Pubkey::from_str("D7mQZksqLfqofKep2NH4XpY6hnSzTL5rxtxe3Wwvzf3s").unwrap());
+}
+
+#[test]
+fn test_treasury_seeds_derive_known_address() {
+    let program_id = Pubkey::from_str("co111CrRL738X8TKrqmLcNBstgLFZjuMtZRBW2FGpbC").unwrap();
+    let (treasury_address, bump_seed) =
+        Pubkey::find_program_address(&[COLLECTION_SEED, TREASURY_SEED, program_id.as_ref()], &program_id);
+    assert_eq!(treasury_address, Pubkey::from_str("D7mQZksqLfqofKep2NH4XpY6hnSzTL5rxtxe3Wwvzf3s").unwrap());
+    assert_eq!(bump_seed, TREASURY_BUMP);
+}
+
+#[test]
+fn test_find_index_address() {
+    let mint = Pubkey::new_unique();
+    let (index_address, bump_seed) = find_index_address(&id(), &mint);
+    assert_eq!((index_address, bump_seed), get_index_account(&mint));
+    assert_eq!(
+        index_address,
+        Pubkey::create_program_address(
+            &[b"collection", id().as_ref(), mint.as_ref(), &[bump_seed]],
+            &id(),
+        ).unwrap()
+    );
+
+    let ix = include_token(
+        id(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        mint,
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    assert_eq!(ix.accounts[4].pubkey, index_address);
+    assert_eq!(create_index_address(&id(), &mint, bump_seed), Ok(index_address));
+}
+
+#[test]
+fn test_find_index_addresses() {
+    let mints: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+    let index_addresses = find_index_addresses(&id(), &mints);
+    assert_eq!(index_addresses.len(), mints.len());
+    for (mint, index_address) in mints.iter().zip(index_addresses) {
+        assert_eq!(index_address, find_index_address(&id(), mint));
+    }
+    assert!(find_index_addresses(&id(), &[]).is_empty());
+}
+
+#[test]
+fn test_treasury_bump() {
+    assert_eq!(find_treasury_address(&id()).1, TREASURY_BUMP);
+}
+
+#[test]
+fn test_withdrawable_balance() {
+    let rent = Rent::default();
+    let rent_exempt_minimum = rent.minimum_balance(0);
+    let (treasury_address, _) = get_treasury_account();
+    let owner = system_program::id();
+    let mut data = [];
+
+    let mut lamports = rent_exempt_minimum + 42;
+    let treasury = AccountInfo::new(&treasury_address, false, true, &mut lamports, &mut data, &owner, false, 0);
+    assert_eq!(withdrawable_balance(&treasury, &rent), 42);
+
+    let mut lamports = rent_exempt_minimum - 1;
+    let treasury = AccountInfo::new(&treasury_address, false, true, &mut lamports, &mut data, &owner, false, 0);
+    assert_eq!(withdrawable_balance(&treasury, &rent), 0);
+}